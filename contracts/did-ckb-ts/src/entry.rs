@@ -1,77 +1,9 @@
 use crate::error::Error;
-use crate::molecules::{new_data, new_witness, PlcAuthorization};
-use alloc::vec::Vec;
-use ckb_did_plc_utils::{
-    operation::{parse_local_id, validate_operation_history},
-    reader::validate_cbor_format,
-};
+use crate::molecules::Group;
+use ckb_did_plc_utils::script::{burn, mint, update};
 use ckb_std::error::SysError;
 use ckb_std::syscalls::load_cell;
-use ckb_std::{ckb_constants::Source, high_level::load_tx_hash, type_id::check_type_id};
-use molecule::lazy_reader::Cursor;
-
-fn mint() -> Result<(), Error> {
-    let data = new_data(0, Source::GroupOutput)?;
-    // validate cbor format
-    validate_cbor_format(data.document()?)?;
-
-    let local_id = data.local_id()?;
-    // Allow empty local ID - this indicates the cell has no associated did:plc
-    // and can be minted without requiring did:plc authorization
-    if local_id.is_none() {
-        return Ok(());
-    }
-    let local_id: Vec<u8> = local_id.unwrap().try_into()?;
-
-    let witness = new_witness()?;
-    let auth: PlcAuthorization = witness.local_id_authorization()?;
-
-    let binary_did = parse_local_id(&local_id)?;
-    // History contains DID operations which can be very large. Using Cursor for lazy reading
-    // to avoid loading the entire operation history into memory at once.
-    let history: Vec<Cursor> = auth.history()?.into_iter().collect();
-    let final_sig: Vec<u8> = auth.sig()?.try_into()?;
-    let rotation_key_indices: Vec<u8> = auth.rotation_key_indices()?.try_into()?;
-    let rotation_key_indices: Vec<usize> = rotation_key_indices
-        .into_iter()
-        .map(|e| e as usize)
-        .collect();
-    let msg = load_tx_hash()?;
-    validate_operation_history(&binary_did, history, rotation_key_indices, &msg, &final_sig)?;
-    #[cfg(feature = "enable_log")]
-    log::info!("validate operation history successfully");
-
-    Ok(())
-}
-
-fn update() -> Result<(), Error> {
-    let prev_data = new_data(0, Source::GroupInput)?;
-    let cur_data = new_data(0, Source::GroupOutput)?;
-
-    // validate formats of document
-    validate_cbor_format(cur_data.document()?)?;
-    validate_cbor_format(prev_data.document()?)?;
-
-    let prev_from: Vec<Vec<u8>> = prev_data
-        .local_id()?
-        .into_iter()
-        .map(|c| c.try_into().map_err(|_| Error::Molecule))
-        .collect::<Result<Vec<_>, _>>()?;
-    let cur_from: Vec<Vec<u8>> = cur_data
-        .local_id()?
-        .into_iter()
-        .map(|c| c.try_into().map_err(|_| Error::Molecule))
-        .collect::<Result<Vec<_>, _>>()?;
-    if prev_from != cur_from {
-        Err(Error::MismatchedFrom)
-    } else {
-        Ok(())
-    }
-}
-
-fn burn() -> Result<(), Error> {
-    Ok(())
-}
+use ckb_std::{ckb_constants::Source, type_id::check_type_id};
 
 fn is_cell_present(index: usize, source: Source) -> bool {
     let buf = &mut [];
@@ -87,9 +19,9 @@ pub fn entry() -> Result<(), Error> {
         is_cell_present(0, Source::GroupInput),
         is_cell_present(0, Source::GroupOutput),
     ) {
-        (true, true) => update(),
-        (true, false) => burn(),
-        (false, true) => mint(),
+        (true, true) => update(&Group),
+        (true, false) => burn(&Group),
+        (false, true) => mint(&Group),
         (false, false) => unreachable!(),
     }
 }
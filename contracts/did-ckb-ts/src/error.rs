@@ -1,4 +1,5 @@
 use ckb_did_plc_utils::error::Error as UtilsError;
+use ckb_did_plc_utils::script::ScriptError;
 use ckb_std::error::SysError;
 use core::fmt::Display;
 use molecule::lazy_reader::Error as MoleculeError;
@@ -10,6 +11,15 @@ pub enum Error {
     Molecule,
     InvalidDocumentCbor,
     MismatchedFrom,
+    // No witness, or an empty one, where the authorization is expected
+    WitnessMissing,
+    InvalidWitnessArgs,
+    // The `WitnessArgs` field for the authorization is empty
+    AuthorizationMissing,
+    // The authorization is in `input_type` instead of `output_type`, or the
+    // other way around
+    MisplacedAuthorization,
+    InvalidAuthorization,
 }
 
 impl Display for Error {
@@ -32,6 +42,21 @@ impl From<UtilsError> for Error {
     }
 }
 
+impl From<ScriptError> for Error {
+    fn from(e: ScriptError) -> Self {
+        match e {
+            ScriptError::Molecule => Error::Molecule,
+            ScriptError::InvalidDocumentCbor => Error::InvalidDocumentCbor,
+            ScriptError::MismatchedFrom => Error::MismatchedFrom,
+            ScriptError::WitnessMissing => Error::WitnessMissing,
+            ScriptError::InvalidWitnessArgs => Error::InvalidWitnessArgs,
+            ScriptError::AuthorizationMissing => Error::AuthorizationMissing,
+            ScriptError::MisplacedAuthorization => Error::MisplacedAuthorization,
+            ScriptError::InvalidAuthorization => Error::InvalidAuthorization,
+        }
+    }
+}
+
 impl From<MoleculeError> for Error {
     fn from(_: MoleculeError) -> Self {
         Error::Molecule
@@ -52,28 +77,16 @@ impl Error {
                 _ => 27,
             },
             // crate ckb-did-plc-utils error starts from 31
-            Error::Utils(e) => match e {
-                UtilsError::InvalidOperation => 31,
-                UtilsError::RotationKeysDecodeError => 32,
-                UtilsError::InvalidKey => 33,
-                UtilsError::InvalidSignature => 34,
-                UtilsError::InvalidSignaturePadding => 35,
-                UtilsError::VerifySignatureFailed => 36,
-                UtilsError::InvalidPrev => 37,
-                UtilsError::MissingPrevField => 38,
-                UtilsError::NotGenesisOperation => 39,
-                UtilsError::DidMismatched => 40,
-                UtilsError::ReaderError => 41,
-                UtilsError::InvalidKeyIndex => 42,
-                UtilsError::InvalidHistory => 43,
-                UtilsError::MoleculeError(_) => 44,
-                UtilsError::InvalidCbor => 45,
-                UtilsError::InvalidDidFormat => 46,
-            },
+            Error::Utils(e) => e.error_code(),
             // this script error starts from 51
             Error::Molecule => 51,
             Error::InvalidDocumentCbor => 52,
             Error::MismatchedFrom => 53,
+            Error::WitnessMissing => 54,
+            Error::InvalidWitnessArgs => 55,
+            Error::AuthorizationMissing => 56,
+            Error::MisplacedAuthorization => 57,
+            Error::InvalidAuthorization => 58,
         }
     }
 }
@@ -1,16 +1,10 @@
-#[allow(clippy::all, unused_imports, dead_code)]
-mod cell_data;
-#[allow(clippy::all, unused_imports, dead_code)]
-mod witness;
-
 use crate::error::Error;
-use alloc::{boxed::Box, vec::Vec};
-use ckb_did_plc_utils::cbor4ii::core::{dec::Decode, utils::SliceReader, Value};
-use ckb_std::{ckb_constants::Source, error::SysError, syscalls};
+use alloc::boxed::Box;
+use ckb_did_plc_utils::script::{GroupSource, ScriptGroup};
+use ckb_std::{ckb_constants::Source, error::SysError, high_level::load_tx_hash, syscalls};
 
-pub use cell_data::*;
-pub use molecule::lazy_reader::{Cursor, Error as MoleculeError, Read};
-pub use witness::*;
+pub use did_ckb_molecules::lazy_reader::*;
+pub use molecule::lazy_reader::Error as MoleculeError;
 
 fn read_data<F: Fn(&mut [u8], usize) -> Result<usize, SysError>>(
     load_func: F,
@@ -77,25 +71,6 @@ impl From<DataReader> for Cursor {
     }
 }
 
-pub fn new_data(index: usize, source: Source) -> Result<DidCkbDataV1, Error> {
-    let reader = DataReader::new(index, source);
-    let cursor: Cursor = reader.into();
-    let data = DidCkbData::try_from(cursor)?;
-    data.verify(false)?;
-
-    let DidCkbData::DidCkbDataV1(data) = data;
-    let doc: Vec<u8> = data
-        .document()?
-        .try_into()
-        .map_err(|_| Error::InvalidDocumentCbor)?;
-
-    // check that the document with cbor format
-    let mut reader = SliceReader::new(&doc);
-    let _ = Value::decode(&mut reader).map_err(|_| Error::InvalidDocumentCbor)?;
-
-    Ok(data)
-}
-
 pub struct WitnessArgsReader {
     total_size: usize,
     index: usize,
@@ -103,13 +78,22 @@ pub struct WitnessArgsReader {
 }
 
 impl WitnessArgsReader {
-    pub fn new(index: usize, source: Source) -> Self {
-        let total_size = read_size(|buf| syscalls::load_witness(buf, 0, index, source)).unwrap();
-        Self {
+    /// `None` when there is no witness, or an empty one, at `index` of
+    /// `source`.
+    pub fn new(index: usize, source: Source) -> Result<Option<Self>, Error> {
+        let total_size = match syscalls::load_witness(&mut [], 0, index, source) {
+            Ok(size) | Err(SysError::LengthNotEnough(size)) => size,
+            Err(SysError::IndexOutOfBound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if total_size == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
             total_size,
             source,
             index,
-        }
+        }))
     }
 }
 
@@ -130,19 +114,28 @@ impl From<WitnessArgsReader> for Cursor {
     }
 }
 
-pub fn new_witness_args(index: usize, source: Source) -> Result<witness::WitnessArgs, Error> {
-    let reader = WitnessArgsReader::new(index, source);
-    let cursor: Cursor = reader.into();
-    let witness_args = WitnessArgs::from(cursor);
-    witness_args.verify(false)?;
-    Ok(witness_args)
+/// The script group of the running script, loaded with syscalls.
+pub struct Group;
+
+impl ScriptGroup for Group {
+    type Error = Error;
+
+    fn load_data(&self, source: GroupSource) -> Result<Cursor, Error> {
+        Ok(DataReader::new(0, group_source(source)).into())
+    }
+
+    fn load_witness(&self, source: GroupSource) -> Result<Option<Cursor>, Error> {
+        Ok(WitnessArgsReader::new(0, group_source(source))?.map(Into::into))
+    }
+
+    fn tx_hash(&self) -> Result<[u8; 32], Error> {
+        Ok(load_tx_hash()?)
+    }
 }
 
-pub fn new_witness() -> Result<witness::DidCkbWitness, Error> {
-    let witness_args = new_witness_args(0, Source::GroupOutput)?;
-    let output_type = witness_args.output_type()?;
-    let output_type = output_type.ok_or(Error::Molecule)?;
-    let witness = DidCkbWitness::from(output_type);
-    witness.verify(false)?;
-    Ok(witness)
+fn group_source(source: GroupSource) -> Source {
+    match source {
+        GroupSource::Input => Source::GroupInput,
+        GroupSource::Output => Source::GroupOutput,
+    }
 }
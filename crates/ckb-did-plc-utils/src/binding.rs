@@ -0,0 +1,191 @@
+// The optional strict-binding rule between a did:ckb document and its did:plc
+// local ID. A document opts in with a `localIdBinding` field:
+//
+// - `"strict"`: its `verificationMethods`, `alsoKnownAs` and `services` are
+//   those of the did:plc after the latest operation of the authorization;
+// - `"superset"`: they include those of the did:plc, and may add others.
+//
+// The rule is checked when the local ID is authorized: at mint, and at an
+// update of a document declaring it, which must then be re-authorized. An
+// update dropping the field is re-authorized too, without the comparison.
+// Without the field, documents are not compared to the did:plc.
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use cbor4ii::core::{Value, dec::Decode, utils::SliceReader};
+
+use crate::error::Error;
+use crate::local_id::Authorization;
+use crate::operation::{LOCAL_ID_PREFIX, parse_local_id, resolve_operation_history};
+use crate::state::{PlcState, Service};
+
+/// The field of a did:ckb document declaring its binding.
+pub const BINDING_FIELD: &str = "localIdBinding";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /// The document matches the did:plc
+    Strict,
+    /// The document includes the did:plc
+    Superset,
+}
+
+fn invalid_binding(actual: Option<String>) -> Error {
+    Error::InvalidBinding.with_context(|c| {
+        c.field = Some(BINDING_FIELD);
+        c.actual = actual;
+    })
+}
+
+fn decode(document: &[u8]) -> Result<Vec<(Value, Value)>, Error> {
+    match Value::decode(&mut SliceReader::new(document)) {
+        Ok(Value::Map(fields)) => Ok(fields),
+        Ok(_) => Ok(Vec::new()),
+        Err(_) => Err(Error::InvalidCbor),
+    }
+}
+
+fn get<'a>(fields: &'a [(Value, Value)], key: &str) -> Option<&'a Value> {
+    fields.iter().find_map(|(k, v)| match k {
+        Value::Text(k) if k == key => Some(v),
+        _ => None,
+    })
+}
+
+/// The binding `document` declares, `None` for a document without
+/// `localIdBinding`, or one that isn't a CBOR map.
+pub fn read_binding(document: &[u8]) -> Result<Option<Binding>, Error> {
+    match get(&decode(document)?, BINDING_FIELD) {
+        None => Ok(None),
+        Some(Value::Text(binding)) if binding == "strict" => Ok(Some(Binding::Strict)),
+        Some(Value::Text(binding)) if binding == "superset" => Ok(Some(Binding::Superset)),
+        Some(Value::Text(binding)) => Err(invalid_binding(Some(binding.clone()))),
+        Some(_) => Err(invalid_binding(None)),
+    }
+}
+
+// The fields of a document compared with the did:plc, as a `PlcState` without
+// rotation keys. A missing field is empty, a malformed one doesn't match.
+fn document_state(fields: &[(Value, Value)]) -> Option<PlcState> {
+    let text = |value: &Value| match value {
+        Value::Text(text) => Some(text.clone()),
+        _ => None,
+    };
+    let map = |key: &str| match get(fields, key) {
+        None => Some(Vec::new()),
+        Some(Value::Map(entries)) => Some(entries.clone()),
+        Some(_) => None,
+    };
+    let verification_methods = map("verificationMethods")?
+        .iter()
+        .map(|(id, key)| Some((text(id)?, text(key)?)))
+        .collect::<Option<BTreeMap<_, _>>>()?;
+    let also_known_as = match get(fields, "alsoKnownAs") {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items.iter().map(text).collect::<Option<_>>()?,
+        Some(_) => return None,
+    };
+    let services = map("services")?
+        .iter()
+        .map(|(id, service)| {
+            let Value::Map(service) = service else {
+                return None;
+            };
+            let service = Service {
+                r#type: text(get(service, "type")?)?,
+                endpoint: text(get(service, "endpoint")?)?,
+            };
+            Some((text(id)?, service))
+        })
+        .collect::<Option<BTreeMap<_, _>>>()?;
+    Some(PlcState {
+        did: String::new(),
+        rotation_keys: Vec::new(),
+        verification_methods,
+        also_known_as,
+        services,
+        head: String::new(),
+    })
+}
+
+/// Checks that `document` is bound to `state` the way `binding` requires.
+pub fn check_binding(document: &[u8], binding: Binding, state: &PlcState) -> Result<(), Error> {
+    let mismatch = |field: &'static str| {
+        Error::BindingMismatch.with_context(|c| {
+            c.field = Some(field);
+            c.expected = Some(state.did.clone());
+        })
+    };
+    let Some(document) = document_state(&decode(document)?) else {
+        return Err(mismatch("document"));
+    };
+    let (verification_methods, also_known_as, services) = match binding {
+        Binding::Strict => (
+            document.verification_methods == state.verification_methods,
+            document.also_known_as == state.also_known_as,
+            document.services == state.services,
+        ),
+        Binding::Superset => (
+            state
+                .verification_methods
+                .iter()
+                .all(|(id, key)| document.verification_methods.get(id) == Some(key)),
+            state
+                .also_known_as
+                .iter()
+                .all(|uri| document.also_known_as.contains(uri)),
+            state
+                .services
+                .iter()
+                .all(|(id, service)| document.services.get(id) == Some(service)),
+        ),
+    };
+    if !verification_methods {
+        return Err(mismatch("verificationMethods"));
+    }
+    if !also_known_as {
+        return Err(mismatch("alsoKnownAs"));
+    }
+    if !services {
+        return Err(mismatch("services"));
+    }
+    Ok(())
+}
+
+/// The binary did:plc a document declaring a binding is bound to, from its
+/// `local_id`. The rule only applies to did:plc local IDs.
+pub fn bound_did(local_id: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+    match local_id {
+        Some(local_id) if local_id.starts_with(LOCAL_ID_PREFIX.as_bytes()) => {
+            parse_local_id(local_id)
+        }
+        Some(local_id) => Err(invalid_binding(
+            core::str::from_utf8(local_id).ok().map(ToOwned::to_owned),
+        )),
+        None => Err(invalid_binding(None)),
+    }
+}
+
+/// Verifies the did:plc `authorization` of `binary_did` like
+/// `LocalIdVerifier::verify`, and that `document` is bound to the resolved
+/// did:plc the way `binding` requires.
+pub fn verify_binding(
+    document: &[u8],
+    binding: Binding,
+    binary_did: &[u8],
+    authorization: Authorization,
+    msg: &[u8],
+) -> Result<(), Error> {
+    let Authorization::Plc {
+        history,
+        rotation_key_indices,
+        sig,
+    } = authorization
+    else {
+        return Err(Error::MismatchedAuthorization);
+    };
+    let state = resolve_operation_history(binary_did, history, rotation_key_indices, msg, &sig)?;
+    check_binding(document, binding, &state)
+}
@@ -0,0 +1,208 @@
+// CIDv1, as far as did:plc needs them: operations are addressed by the
+// sha2-256 of their DAG-CBOR encoding, and `prev` names the previous one.
+// https://github.com/multiformats/cid
+//
+// Only the codecs and multihashes below are supported, a CID with another one
+// is invalid. CIDv0 is dag-pb only and never names an operation.
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::str::FromStr;
+
+use multibase::Base;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+const CID_VERSION: u64 = 1;
+
+/// The CBOR tag of a DAG-CBOR link.
+pub const CID_TAG: u64 = 42;
+
+// the multibase prefix of the binary CID in a link
+const IDENTITY_PREFIX: u8 = 0;
+
+/// The multicodec of the data a CID names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    DagCbor,
+    Raw,
+}
+
+impl Codec {
+    pub fn code(self) -> u64 {
+        match self {
+            Codec::DagCbor => 0x71,
+            Codec::Raw => 0x55,
+        }
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0x71 => Some(Codec::DagCbor),
+            0x55 => Some(Codec::Raw),
+            _ => None,
+        }
+    }
+}
+
+/// The multihash of the data a CID names, one variant per hash function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Multihash {
+    Sha2_256([u8; 32]),
+}
+
+impl Multihash {
+    pub fn sha2_256(data: &[u8]) -> Self {
+        Multihash::Sha2_256(Sha256::digest(data).into())
+    }
+
+    pub fn code(&self) -> u64 {
+        match self {
+            Multihash::Sha2_256(_) => 0x12,
+        }
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        match self {
+            Multihash::Sha2_256(digest) => digest,
+        }
+    }
+
+    fn from_digest(code: u64, digest: &[u8]) -> Option<Self> {
+        match code {
+            0x12 => Some(Multihash::Sha2_256(digest.try_into().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+/// A CIDv1. Two CIDs are equal when they name the same data with the same
+/// codec, whatever their text or binary form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cid {
+    codec: Codec,
+    hash: Multihash,
+}
+
+fn invalid() -> Error {
+    Error::InvalidCid
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, Error> {
+    let mut value: u64 = 0;
+    // unsigned-varint caps values at 63 bits, 9 bytes
+    for i in 0..9 {
+        let (&byte, rest) = bytes.split_first().ok_or_else(invalid)?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            // the minimal encoding only
+            if byte == 0 && i > 0 {
+                return Err(invalid());
+            }
+            return Ok(value);
+        }
+    }
+    Err(invalid())
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl Cid {
+    pub fn new(codec: Codec, hash: Multihash) -> Self {
+        Self { codec, hash }
+    }
+
+    /// The CID of the DAG-CBOR encoded `data`, hashed with sha2-256.
+    pub fn dag_cbor(data: &[u8]) -> Self {
+        Self::new(Codec::DagCbor, Multihash::sha2_256(data))
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    pub fn hash(&self) -> &Multihash {
+        &self.hash
+    }
+
+    /// Parses the binary form of a CID: its version, codec and multihash.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = &mut bytes;
+        if read_varint(bytes)? != CID_VERSION {
+            return Err(invalid());
+        }
+        let codec = Codec::from_code(read_varint(bytes)?).ok_or_else(invalid)?;
+        let code = read_varint(bytes)?;
+        let len = read_varint(bytes)?;
+        if len != bytes.len() as u64 {
+            return Err(invalid());
+        }
+        let hash = Multihash::from_digest(code, bytes).ok_or_else(invalid)?;
+        Ok(Self::new(codec, hash))
+    }
+
+    /// The binary form of the CID.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let digest = self.hash.digest();
+        let mut bytes = Vec::with_capacity(digest.len() + 4);
+        write_varint(&mut bytes, CID_VERSION);
+        write_varint(&mut bytes, self.codec.code());
+        write_varint(&mut bytes, self.hash.code());
+        write_varint(&mut bytes, digest.len() as u64);
+        bytes.extend_from_slice(digest);
+        bytes
+    }
+
+    /// Parses the bytes of a DAG-CBOR link, the binary CID behind the
+    /// identity multibase prefix.
+    pub fn from_link(bytes: &[u8]) -> Result<Self, Error> {
+        match bytes.split_first() {
+            Some((&IDENTITY_PREFIX, cid)) => Self::from_bytes(cid),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The bytes of the DAG-CBOR link to the CID.
+    pub fn to_link(&self) -> Vec<u8> {
+        let mut bytes = Vec::from([IDENTITY_PREFIX]);
+        bytes.extend(self.to_bytes());
+        bytes
+    }
+}
+
+impl FromStr for Cid {
+    type Err = Error;
+
+    /// Parses the text form of a CID in base32, lower or upper case, or in
+    /// base58btc.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let context = |e: Error| e.with_context(|c| c.actual = Some(s.into()));
+        let mut chars = s.chars();
+        let base = match chars.next() {
+            Some('b') => Base::Base32Lower,
+            Some('B') => Base::Base32Upper,
+            Some('z') => Base::Base58Btc,
+            _ => return Err(context(invalid())),
+        };
+        let bytes = base
+            .decode(chars.as_str())
+            .map_err(|_| context(invalid()))?;
+        Self::from_bytes(&bytes).map_err(context)
+    }
+}
+
+impl Display for Cid {
+    /// The canonical text form, in lower case base32.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let text: String = multibase::encode(Base::Base32Lower, self.to_bytes());
+        f.write_str(&text)
+    }
+}
@@ -0,0 +1,151 @@
+// Conversion between PLC operations as JSON, the way the PLC directory serves
+// them, and their DAG-CBOR encoding, the bytes `Operation::from_slice` reads
+// and the CID is computed over.
+// https://ipld.io/specs/codecs/dag-json/spec/
+// https://ipld.io/specs/codecs/dag-cbor/spec/
+//
+// Both directions round-trip: `to_dag_json` only accepts canonical DAG-CBOR,
+// so converting its JSON back with `to_dag_cbor` gives the same bytes, and the
+// same CID. Floats are not supported, no PLC operation has one.
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use cbor4ii::core::Value;
+use cbor4ii::core::dec::Decode;
+use cbor4ii::core::enc::Encode;
+use cbor4ii::core::utils::{BufWriter, SliceReader};
+use serde_json::{Map, Value as Json};
+
+use crate::cid::{CID_TAG, Cid};
+use crate::error::Error;
+
+// the key of the DAG-JSON forms of links and bytes
+const RESERVED_KEY: &str = "/";
+
+fn invalid_json(actual: String) -> Error {
+    Error::InvalidOperation.with_context(|c| c.actual = Some(actual))
+}
+
+fn invalid_cbor(actual: String) -> Error {
+    Error::InvalidCbor.with_context(|c| c.actual = Some(actual))
+}
+
+fn encode(value: &Value) -> Result<Vec<u8>, Error> {
+    let mut writer = BufWriter::new(Vec::new());
+    value.encode(&mut writer).map_err(|_| Error::InvalidCbor)?;
+    Ok(writer.into_inner())
+}
+
+// A link `{"/": "<cid>"}` or bytes `{"/": {"bytes": "<base64>"}}`
+fn reserved(object: &Map<String, Json>) -> Option<&Json> {
+    match object.len() {
+        1 => object.get(RESERVED_KEY),
+        _ => None,
+    }
+}
+
+fn cbor_value(json: &Json) -> Result<Value, Error> {
+    Ok(match json {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => Value::Integer(n.into()),
+            (_, Some(n)) => Value::Integer(n.into()),
+            _ => return Err(invalid_json(n.to_string())),
+        },
+        Json::String(s) => Value::Text(s.clone()),
+        Json::Array(items) => Value::Array(items.iter().map(cbor_value).collect::<Result<_, _>>()?),
+        Json::Object(object) => match reserved(object) {
+            Some(Json::String(cid)) => {
+                let link = Cid::from_str(cid)?.to_link();
+                Value::Tag(CID_TAG, Box::new(Value::Bytes(link)))
+            }
+            Some(Json::Object(bytes)) => match (bytes.len(), bytes.get("bytes")) {
+                (1, Some(Json::String(base64))) => Value::Bytes(
+                    STANDARD_NO_PAD
+                        .decode(base64)
+                        .map_err(|_| invalid_json(base64.clone()))?,
+                ),
+                _ => return Err(invalid_json(json.to_string())),
+            },
+            Some(_) => return Err(invalid_json(json.to_string())),
+            None => {
+                let mut fields = object
+                    .iter()
+                    .map(|(k, v)| Ok((k.as_str(), cbor_value(v)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                // the canonical order: shorter keys first, then bytewise
+                fields.sort_by(|(a, _), (b, _)| (a.len(), *a).cmp(&(b.len(), *b)));
+                Value::Map(
+                    fields
+                        .into_iter()
+                        .map(|(k, v)| (Value::Text(k.into()), v))
+                        .collect(),
+                )
+            }
+        },
+    })
+}
+
+fn json_value(value: &Value) -> Result<Json, Error> {
+    Ok(match value {
+        Value::Null => Json::Null,
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Integer(n) => match (u64::try_from(*n), i64::try_from(*n)) {
+            (Ok(n), _) => Json::from(n),
+            (_, Ok(n)) => Json::from(n),
+            _ => return Err(invalid_cbor(n.to_string())),
+        },
+        Value::Text(s) => Json::String(s.clone()),
+        Value::Bytes(bytes) => {
+            let mut object = Map::new();
+            object.insert("bytes".into(), Json::String(STANDARD_NO_PAD.encode(bytes)));
+            let mut bytes = Map::new();
+            bytes.insert(RESERVED_KEY.into(), Json::Object(object));
+            Json::Object(bytes)
+        }
+        Value::Array(items) => Json::Array(items.iter().map(json_value).collect::<Result<_, _>>()?),
+        Value::Map(fields) => Json::Object(
+            fields
+                .iter()
+                .map(|(k, v)| match k {
+                    Value::Text(k) => Ok((k.clone(), json_value(v)?)),
+                    _ => Err(invalid_cbor(format!("{:?}", k))),
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
+        Value::Tag(CID_TAG, link) => match link.as_ref() {
+            Value::Bytes(link) => {
+                let mut object = Map::new();
+                let cid = Cid::from_link(link)?;
+                object.insert(RESERVED_KEY.into(), Json::String(cid.to_string()));
+                Json::Object(object)
+            }
+            _ => return Err(Error::InvalidCid),
+        },
+        _ => return Err(invalid_cbor(format!("{:?}", value))),
+    })
+}
+
+/// The canonical DAG-CBOR encoding of the JSON operation `json`: map keys
+/// sorted, links `{"/": "<cid>"}` as tag 42 and bytes
+/// `{"/": {"bytes": "<base64>"}}` as byte strings.
+pub fn to_dag_cbor(json: &Json) -> Result<Vec<u8>, Error> {
+    encode(&cbor_value(json)?)
+}
+
+/// The JSON of the DAG-CBOR operation `cbor`. The encoding must be the
+/// canonical one, that `to_dag_cbor` gives back.
+pub fn to_dag_json(cbor: &[u8]) -> Result<Json, Error> {
+    let value = Value::decode(&mut SliceReader::new(cbor)).map_err(|_| Error::InvalidCbor)?;
+    let json = json_value(&value)?;
+    if to_dag_cbor(&json)? != cbor {
+        return Err(invalid_cbor("not canonical DAG-CBOR".into()));
+    }
+    Ok(json)
+}
@@ -1,7 +1,31 @@
+#[cfg(feature = "error_context")]
+use alloc::boxed::Box;
+use alloc::string::String;
 use core::fmt::Display;
 use molecule::lazy_reader::Error as MoleculeError;
 
+/// Where and why an error happened. It is only attached to errors with the
+/// `error_context` feature, the script keeps plain errors.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// Index of the operation in the history, `history.len()` for the final
+    /// authorization
+    pub history_index: Option<usize>,
+    /// The operation field at fault
+    pub field: Option<&'static str>,
+    /// The value the field should have, such as the CID of the previous
+    /// operation
+    pub expected: Option<String>,
+    /// The value the field has
+    pub actual: Option<String>,
+    /// The rotation key index tried
+    pub key_index: Option<usize>,
+    /// The `did:key` at fault
+    pub key: Option<String>,
+}
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     InvalidOperation,
     RotationKeysDecodeError,
@@ -19,6 +43,48 @@ pub enum Error {
     MoleculeError(MoleculeError),
     InvalidCbor,
     InvalidDidFormat,
+    /// The local ID is not a did:key of a secp256k1 or p256 key
+    InvalidDidKey,
+    /// The signature of a did:key authorization doesn't verify with its key
+    DidKeySignatureFailed,
+    /// The authorization is the witness variant of another DID method
+    MismatchedAuthorization,
+    /// The local ID is not a valid did:web
+    InvalidDidWeb,
+    /// The document of a did:web authorization is not JSON, or not the
+    /// document of the local ID
+    InvalidDidWebDocument,
+    /// The verification method of a did:web authorization is not in the
+    /// document, or has no supported key
+    InvalidVerificationMethod,
+    /// The signature of a did:web authorization doesn't verify with the key of
+    /// its verification method
+    DidWebSignatureFailed,
+    /// The `localIdBinding` of a document is not `strict` or `superset`, or is
+    /// declared without a did:plc local ID
+    InvalidBinding,
+    /// The document doesn't match the did:plc state its `localIdBinding`
+    /// binds it to
+    BindingMismatch,
+    /// The `rotationKeys` of a `plc_operation` has no key, or more than
+    /// `MAX_ROTATION_KEYS`
+    InvalidRotationKeyCount,
+    /// The `verificationMethods` of a `plc_operation` is not a map of
+    /// `did:key` strings
+    InvalidVerificationMethods,
+    /// The `alsoKnownAs` of a `plc_operation` is not an array of `at://` URIs
+    InvalidAlsoKnownAs,
+    /// The `services` of a `plc_operation` is not a map of services with
+    /// `type` and `endpoint` strings
+    InvalidService,
+    /// A CID is malformed, or uses a codec or multihash that isn't supported
+    InvalidCid,
+    /// The rotation keys tried for unspecified rotation key indices failed
+    /// more than `MAX_KEY_TRIALS` times over a history
+    TooManyKeyTrials,
+    /// An error with its context, see [`Error::root`] and [`Error::context`].
+    #[cfg(feature = "error_context")]
+    Context(Box<(Error, Context)>),
 }
 
 impl From<MoleculeError> for Error {
@@ -29,8 +95,138 @@ impl From<MoleculeError> for Error {
 
 impl Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{:?}", self.root())?;
+        if let Some(context) = self.context() {
+            write!(f, " ({})", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Context {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut separator = "";
+        let mut item = |f: &mut core::fmt::Formatter<'_>, args: core::fmt::Arguments| {
+            let result = write!(f, "{}{}", separator, args);
+            separator = ", ";
+            result
+        };
+        if let Some(index) = self.history_index {
+            item(f, format_args!("operation {}", index))?;
+        }
+        if let Some(field) = self.field {
+            item(f, format_args!("field {}", field))?;
+        }
+        if let Some(expected) = &self.expected {
+            item(f, format_args!("expected {}", expected))?;
+        }
+        if let Some(actual) = &self.actual {
+            item(f, format_args!("actual {}", actual))?;
+        }
+        if let Some(index) = self.key_index {
+            item(f, format_args!("rotation key index {}", index))?;
+        }
+        if let Some(key) = &self.key {
+            item(f, format_args!("key {}", key))?;
+        }
+        Ok(())
     }
 }
 
 impl core::error::Error for Error {}
+
+impl Error {
+    /// The error without its context.
+    pub fn root(&self) -> &Error {
+        match self {
+            #[cfg(feature = "error_context")]
+            Error::Context(context) => &context.0,
+            _ => self,
+        }
+    }
+
+    // The error with its root replaced by `f` of it, keeping the context
+    pub(crate) fn map_root(self, f: impl FnOnce(Error) -> Error) -> Error {
+        match self {
+            #[cfg(feature = "error_context")]
+            Error::Context(context) => {
+                let (root, context) = *context;
+                Error::Context(Box::new((f(root), context)))
+            }
+            _ => f(self),
+        }
+    }
+
+    /// The context of the error, always `None` without the `error_context`
+    /// feature.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            #[cfg(feature = "error_context")]
+            Error::Context(context) => Some(&context.1),
+            _ => None,
+        }
+    }
+
+    /// Adds context to the error with `add`, merged into the context it may
+    /// already have.
+    #[cfg(feature = "error_context")]
+    pub fn with_context(mut self, add: impl FnOnce(&mut Context)) -> Self {
+        if let Error::Context(context) = &mut self {
+            add(&mut context.1);
+            return self;
+        }
+        let mut context = Context::default();
+        add(&mut context);
+        Error::Context(Box::new((self, context)))
+    }
+
+    /// Without the `error_context` feature the error is returned as is and
+    /// `add` is never called.
+    #[cfg(not(feature = "error_context"))]
+    #[inline(always)]
+    pub fn with_context(self, _add: impl FnOnce(&mut Context)) -> Self {
+        self
+    }
+
+    /// Exit code returned by the did-ckb-ts script when it fails with this error.
+    /// Errors from this crate start from 31, and continue from 61. Context
+    /// doesn't change it.
+    pub fn error_code(&self) -> i8 {
+        match self {
+            Error::InvalidOperation => 31,
+            Error::RotationKeysDecodeError => 32,
+            Error::InvalidKey => 33,
+            Error::InvalidSignature => 34,
+            Error::InvalidSignaturePadding => 35,
+            Error::VerifySignatureFailed => 36,
+            Error::InvalidPrev => 37,
+            Error::MissingPrevField => 38,
+            Error::NotGenesisOperation => 39,
+            Error::DidMismatched => 40,
+            Error::ReaderError => 41,
+            Error::InvalidKeyIndex => 42,
+            Error::InvalidHistory => 43,
+            Error::MoleculeError(_) => 44,
+            Error::InvalidCbor => 45,
+            Error::InvalidDidFormat => 46,
+            Error::InvalidDidKey => 47,
+            Error::DidKeySignatureFailed => 48,
+            Error::MismatchedAuthorization => 49,
+            Error::InvalidDidWeb => 50,
+            // continues from 61, after the script's own errors
+            Error::InvalidDidWebDocument => 61,
+            Error::InvalidVerificationMethod => 62,
+            Error::DidWebSignatureFailed => 63,
+            Error::InvalidBinding => 64,
+            Error::BindingMismatch => 65,
+            Error::InvalidRotationKeyCount => 66,
+            Error::InvalidVerificationMethods => 67,
+            Error::InvalidAlsoKnownAs => 68,
+            Error::InvalidService => 69,
+            Error::InvalidCid => 70,
+            Error::TooManyKeyTrials => 71,
+            #[cfg(feature = "error_context")]
+            Error::Context(context) => context.0.error_code(),
+        }
+    }
+}
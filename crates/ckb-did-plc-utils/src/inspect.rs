@@ -0,0 +1,235 @@
+// Step-by-step diagnosis of an operation history, the structured counterpart
+// of the `enable_log` warnings. Every step runs the same validation as
+// `validate_operation_history`, so the reported error is the one the script
+// returns, and the details around it are collected best effort.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use base32::Alphabet;
+use molecule::lazy_reader::Cursor;
+
+use crate::error::Error;
+use crate::operation::{
+    KeyTrials, Operation, validate_2_operations_with, validate_final_operation,
+    validate_genesis_operation_with,
+};
+use crate::pubkey::PublicKey;
+
+/// What a step validates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    /// The genesis operation, signed by one of its own rotation keys
+    #[default]
+    Genesis,
+    /// An operation signed by a rotation key of the previous one
+    Operation,
+    /// The final signature over the message, by a rotation key of the last
+    /// operation
+    Authorization,
+}
+
+/// One step of the validation of a history: an operation, or the final
+/// authorization at index `history.len()`.
+#[derive(Debug, Default)]
+pub struct Step {
+    pub kind: StepKind,
+    pub index: usize,
+    /// CID of the operation, none for the final authorization
+    pub cid: Option<String>,
+    /// The `prev` field of the operation
+    pub prev: Option<String>,
+    /// CID of the previous operation, the expected `prev`
+    pub expected_prev: Option<String>,
+    /// DID created by the genesis operation
+    pub did: Option<String>,
+    /// The rotation key index given for this step, possibly
+    /// [`UNSPECIFIED_KEY_INDEX`]
+    ///
+    /// [`UNSPECIFIED_KEY_INDEX`]: crate::operation::UNSPECIFIED_KEY_INDEX
+    pub rotation_key_index: usize,
+    /// `did:key`s of the rotation keys that may sign this step
+    pub rotation_keys: Vec<String>,
+    /// Indices of the rotation keys verifying the signature of this step
+    pub verifying_key_indices: Vec<usize>,
+    /// The error the script returns at this step
+    pub error: Option<Error>,
+}
+
+impl Step {
+    /// The rotation key tried, when the index is in range.
+    pub fn rotation_key(&self) -> Option<&str> {
+        self.rotation_keys
+            .get(self.rotation_key_index)
+            .map(String::as_str)
+    }
+}
+
+/// The result of [`inspect_operation_history`].
+#[derive(Debug, Default)]
+pub struct Inspection {
+    /// The DID the history must create
+    pub did: String,
+    /// The steps validated, up to the first failing one
+    pub steps: Vec<Step>,
+    /// An error before any step, e.g. a history of the wrong length
+    pub error: Option<Error>,
+}
+
+impl Inspection {
+    /// The first failing step.
+    pub fn failure(&self) -> Option<&Step> {
+        self.steps.iter().find(|step| step.error.is_some())
+    }
+
+    /// The error `validate_operation_history` returns for the same input.
+    pub fn error(&self) -> Option<&Error> {
+        self.error
+            .as_ref()
+            .or_else(|| self.failure().and_then(|step| step.error.as_ref()))
+    }
+}
+
+fn rotation_keys(op: &Operation) -> Vec<(String, Option<PublicKey>)> {
+    op.get_rotation_key_ids()
+        .into_iter()
+        .map(|id| {
+            let key = id.parse().ok();
+            (id, key)
+        })
+        .collect()
+}
+
+fn verifying_key_indices(
+    keys: &[(String, Option<PublicKey>)],
+    verify: impl Fn(&PublicKey) -> bool,
+) -> Vec<usize> {
+    keys.iter()
+        .enumerate()
+        .filter(|(_, (_, key))| key.as_ref().is_some_and(&verify))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Details of the operation `cur` following `prev`, or of a genesis operation
+fn inspect_operation(step: &mut Step, prev: Option<&[u8]>, cur: &[u8]) {
+    let Ok(cur_op) = Operation::from_slice(cur) else {
+        return;
+    };
+    step.cid = cur_op.generate_cid().ok();
+    step.prev = cur_op
+        .get_prev()
+        .ok()
+        .flatten()
+        .map(|prev| prev.to_string());
+    let keys = match prev.map(Operation::from_slice) {
+        Some(Ok(prev_op)) => {
+            step.expected_prev = prev_op.generate_cid().ok();
+            rotation_keys(&prev_op)
+        }
+        Some(Err(_)) => vec![],
+        None => {
+            step.did = cur_op.get_did().ok();
+            rotation_keys(&cur_op)
+        }
+    };
+    let Ok(msg) = cur_op.unsigned_message() else {
+        return;
+    };
+    if let Ok(sig) = cur_op.get_signature() {
+        step.verifying_key_indices =
+            verifying_key_indices(&keys, |key| key.verify(&msg, &sig).is_ok());
+    }
+    step.rotation_keys = keys.into_iter().map(|(id, _)| id).collect();
+}
+
+/// Walks the history like [`validate_operation_history`] and reports every
+/// step up to the first failing one, with the CIDs compared and the rotation
+/// keys that were tried or would have verified.
+///
+/// [`validate_operation_history`]: crate::operation::validate_operation_history
+pub fn inspect_operation_history(
+    binary_did: &[u8],
+    history: Vec<Cursor>,
+    rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Inspection {
+    let mut inspection = Inspection {
+        did: format!(
+            "did:plc:{}",
+            base32::encode(Alphabet::Rfc4648Lower { padding: false }, binary_did)
+        ),
+        ..Default::default()
+    };
+    let history_len = history.len();
+    if history_len == 0 || (history_len + 1) != rotation_key_indices.len() {
+        inspection.error = Some(Error::InvalidHistory.with_context(|c| {
+            c.field = Some("rotation_key_indices");
+            c.expected = Some(format!("{}", history_len + 1));
+            c.actual = Some(format!("{}", rotation_key_indices.len()));
+        }));
+        return inspection;
+    }
+    // the errors of a step carry its index, as `validate_operation_history`
+    // reports them
+    let at = |index: usize| move |e: Error| e.with_context(|c| c.history_index = Some(index));
+
+    let mut trials = KeyTrials::default();
+    let mut prev: Option<Vec<u8>> = None;
+    for (index, cursor) in history.into_iter().enumerate() {
+        let rotation_key_index = rotation_key_indices[index];
+        let mut step = Step {
+            kind: match index {
+                0 => StepKind::Genesis,
+                _ => StepKind::Operation,
+            },
+            index,
+            rotation_key_index,
+            ..Default::default()
+        };
+        let cur: Vec<u8> = match cursor.try_into() {
+            Ok(cur) => cur,
+            Err(e) => {
+                step.error = Some(at(index)(Error::from(e)));
+                inspection.steps.push(step);
+                return inspection;
+            }
+        };
+        inspect_operation(&mut step, prev.as_deref(), &cur);
+        let result = match &prev {
+            None => {
+                validate_genesis_operation_with(&cur, binary_did, rotation_key_index, &mut trials)
+            }
+            Some(prev) => validate_2_operations_with(prev, &cur, rotation_key_index, &mut trials),
+        };
+        step.error = result.map_err(at(index)).err();
+        let failed = step.error.is_some();
+        inspection.steps.push(step);
+        if failed {
+            return inspection;
+        }
+        prev = Some(cur);
+    }
+
+    // the final authorization, signed by a rotation key of the last operation
+    let last = prev.expect("a history with an operation");
+    let rotation_key_index = rotation_key_indices[history_len];
+    let mut step = Step {
+        kind: StepKind::Authorization,
+        index: history_len,
+        rotation_key_index,
+        ..Default::default()
+    };
+    if let Ok(op) = Operation::from_slice(&last) {
+        let keys = rotation_keys(&op);
+        step.verifying_key_indices =
+            verifying_key_indices(&keys, |key| key.verify(msg, final_sig).is_ok());
+        step.rotation_keys = keys.into_iter().map(|(id, _)| id).collect();
+    }
+    step.error = validate_final_operation(&last, final_sig, msg, rotation_key_index, &mut trials)
+        .map_err(at(history_len))
+        .err();
+    inspection.steps.push(step);
+    inspection
+}
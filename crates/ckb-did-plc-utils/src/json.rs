@@ -0,0 +1,243 @@
+// A minimal JSON reader for DID documents, see `web`. Like `operation`, it
+// avoids serde: the script only needs to look up a few string fields.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Nesting deeper than this is rejected, it bounds the recursion of the
+/// parser on the script's stack.
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// The number as written, it is never needed as a number
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// Members in document order, duplicates kept
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Parses a whole JSON text, `None` when it is malformed, nested deeper
+    /// than [`MAX_DEPTH`] or followed by anything but whitespace.
+    pub fn parse(text: &[u8]) -> Option<Value> {
+        let mut parser = Parser { text, pos: 0 };
+        let value = parser.value(0)?;
+        parser.whitespace();
+        (parser.pos == text.len()).then_some(value)
+    }
+
+    /// The member `key` of an object. Duplicate keys are ambiguous, the
+    /// lookup fails on them.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let Value::Object(members) = self else {
+            return None;
+        };
+        let mut found = members.iter().filter(|(k, _)| k == key).map(|(_, v)| v);
+        let value = found.next()?;
+        found.next().is_none().then_some(value)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn literal(&mut self, literal: &[u8], value: Value) -> Option<Value> {
+        let end = self.pos + literal.len();
+        (self.text.get(self.pos..end)? == literal).then(|| {
+            self.pos = end;
+            value
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.whitespace();
+        match self.peek()? {
+            b'n' => self.literal(b"null", Value::Null),
+            b't' => self.literal(b"true", Value::Bool(true)),
+            b'f' => self.literal(b"false", Value::Bool(false)),
+            b'"' => self.string().map(Value::String),
+            b'[' => self.array(depth),
+            b'{' => self.object(depth),
+            _ => self.number(),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Option<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Some(Value::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.whitespace();
+            match self.next()? {
+                b',' => continue,
+                b']' => return Some(Value::Array(items)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Option<Value> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Some(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.peek()? != b'"' {
+                return None;
+            }
+            let key = self.string()?;
+            self.whitespace();
+            if self.next()? != b':' {
+                return None;
+            }
+            members.push((key, self.value(depth + 1)?));
+            self.whitespace();
+            match self.next()? {
+                b',' => continue,
+                b'}' => return Some(Value::Object(members)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => {
+                self.digits();
+            }
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return None;
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return None;
+            }
+        }
+        let number = core::str::from_utf8(&self.text[start..self.pos]).ok()?;
+        Some(Value::Number(number.into()))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let hex = self.text.get(self.pos..self.pos + 4)?;
+        let hex = core::str::from_utf8(hex).ok()?;
+        let code = u32::from_str_radix(hex, 16).ok()?;
+        // `from_str_radix` takes a sign
+        if hex.starts_with('+') {
+            return None;
+        }
+        self.pos += 4;
+        Some(code)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.next()? {
+                b'"' => return String::from_utf8(bytes).ok(),
+                b'\\' => {
+                    let c = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // a surrogate pair
+                                if self.next()? != b'\\' || self.next()? != b'u' {
+                                    return None;
+                                }
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return None;
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code)?
+                        }
+                        _ => return None,
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                0x00..=0x1F => return None,
+                byte => bytes.push(byte),
+            }
+        }
+    }
+}
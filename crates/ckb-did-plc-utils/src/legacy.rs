@@ -0,0 +1,92 @@
+// Legacy `create` operations, normalized into the `plc_operation` they stand
+// for, like `normalizeOp` of the did-method-plc reference implementation:
+// https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/operations.ts
+//
+// The normalized fields are only read. The signature and the CID of a legacy
+// operation are still those of its fields as signed.
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use cbor4ii::core::Value;
+
+use crate::error::Error;
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_owned())
+}
+
+pub fn ensure_atproto_prefix(handle: &str) -> String {
+    if handle.starts_with("at://") {
+        return handle.to_owned();
+    }
+    let stripped = handle
+        .replacen("http://", "", 1)
+        .replacen("https://", "", 1);
+    format!("at://{}", stripped)
+}
+
+pub fn ensure_http_prefix(service: &str) -> String {
+    if service.starts_with("http://") || service.starts_with("https://") {
+        return service.to_owned();
+    }
+    format!("https://{}", service)
+}
+
+/// The `plc_operation` fields of the legacy `create` operation `raw`: the
+/// signing and recovery keys as rotation keys, the signing key
+/// as the `atproto` verification method, the handle as an `at://` URI and the
+/// service as the `atproto_pds` endpoint. `prev` and `sig` are kept.
+pub(crate) fn normalize(raw: &[(Value, Value)]) -> Result<Vec<(Value, Value)>, Error> {
+    let field = |name: &'static str| {
+        raw.iter()
+            .find_map(|(k, v)| match (k, v) {
+                (Value::Text(k), Value::Text(v)) if k == name => Some(v.as_str()),
+                _ => None,
+            })
+            .ok_or_else(|| Error::InvalidOperation.with_context(|c| c.field = Some(name)))
+    };
+    let signing_key = field("signingKey")?;
+    field("recoveryKey")?;
+    let handle = field("handle")?;
+    let service = field("service")?;
+    // Unlike did-method-plc, which lists the recovery key first, the keys keep
+    // the order of the fields, the rotation key indices witnesses have always
+    // used: 0 for `signingKey` and 1 for `recoveryKey` in canonical DAG-CBOR.
+    let rotation_keys = raw
+        .iter()
+        .filter(|(k, _)| matches!(k, Value::Text(k) if k == "signingKey" || k == "recoveryKey"))
+        .map(|(_, v)| v.clone())
+        .collect();
+
+    let mut fields = vec![
+        (text("type"), text("plc_operation")),
+        (text("rotationKeys"), Value::Array(rotation_keys)),
+        (
+            text("verificationMethods"),
+            Value::Map(vec![(text("atproto"), text(signing_key))]),
+        ),
+        (
+            text("alsoKnownAs"),
+            Value::Array(vec![Value::Text(ensure_atproto_prefix(handle))]),
+        ),
+        (
+            text("services"),
+            Value::Map(vec![(
+                text("atproto_pds"),
+                Value::Map(vec![
+                    (text("type"), text("AtprotoPersonalDataServer")),
+                    (text("endpoint"), Value::Text(ensure_http_prefix(service))),
+                ]),
+            )]),
+        ),
+    ];
+    fields.extend(
+        raw.iter()
+            .filter(|(k, _)| matches!(k, Value::Text(k) if k == "prev" || k == "sig"))
+            .cloned(),
+    );
+    Ok(fields)
+}
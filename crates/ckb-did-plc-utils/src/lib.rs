@@ -2,11 +2,29 @@
 
 extern crate alloc;
 
+pub mod binding;
+pub mod cid;
+#[cfg(feature = "std")]
+pub mod dag_json;
 pub mod error;
+pub mod inspect;
+pub mod json;
+mod legacy;
+pub mod local_id;
 pub mod operation;
 pub mod pubkey;
 pub mod reader;
+pub mod script;
+pub mod state;
+pub mod web;
 // re-exports
 pub use base32;
 pub use base64;
 pub use cbor4ii;
+#[cfg(feature = "std")]
+pub use serde_json;
+// for the test vectors of the tests crate, which normalize legacy operations
+// the same way
+#[cfg(feature = "test_vectors")]
+#[doc(hidden)]
+pub use legacy::{ensure_atproto_prefix, ensure_http_prefix};
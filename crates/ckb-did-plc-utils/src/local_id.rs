@@ -0,0 +1,255 @@
+// Local IDs by DID method. The script reads the local ID of a minted cell,
+// finds the verifier of its method by prefix, reads the authorization variant
+// of that method from the witness and hands both to the verifier. A new method
+// is a `LocalIdVerifier` added to `Registry::default()`; only a new witness
+// variant, an `AuthorizationKind`, needs the script to learn to read it.
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use molecule::lazy_reader::Cursor;
+
+use crate::error::Error;
+use crate::operation::{LOCAL_ID_PREFIX, parse_local_id, validate_operation_history};
+use crate::pubkey::PublicKey;
+use crate::web;
+
+/// The witness variant a method reads its authorization from, in the field of
+/// `WitnessArgs` the authorization is placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorizationKind {
+    /// `DidCkbWitness`, holding a `PlcAuthorization`
+    Plc,
+    /// `DidCkbKeyWitness`, holding a `KeyAuthorization`
+    Key,
+    /// `DidCkbWebWitness`, holding a `WebAuthorization`
+    Web,
+}
+
+/// An authorization read from the witness, one variant per
+/// [`AuthorizationKind`].
+pub enum Authorization {
+    /// `PlcAuthorization`: the operations from genesis to the latest, the
+    /// rotation key index of each operation and of the final signature, and
+    /// the final signature
+    Plc {
+        history: Vec<Cursor>,
+        rotation_key_indices: Vec<usize>,
+        sig: Vec<u8>,
+    },
+    /// `KeyAuthorization`: a signature of the transaction hash
+    Key { sig: Vec<u8> },
+    /// `WebAuthorization`: the DID document, the `id` of one of its
+    /// verification methods and its signature of the transaction hash
+    Web {
+        document: Vec<u8>,
+        verification_method: Vec<u8>,
+        sig: Vec<u8>,
+    },
+}
+
+impl Authorization {
+    pub fn kind(&self) -> AuthorizationKind {
+        match self {
+            Authorization::Plc { .. } => AuthorizationKind::Plc,
+            Authorization::Key { .. } => AuthorizationKind::Key,
+            Authorization::Web { .. } => AuthorizationKind::Web,
+        }
+    }
+}
+
+/// Verifies the local IDs of one DID method.
+pub trait LocalIdVerifier {
+    /// The prefix of the local IDs of the method, such as `did:plc:`.
+    fn prefix(&self) -> &'static str;
+
+    /// The witness variant of its authorizations.
+    fn kind(&self) -> AuthorizationKind;
+
+    /// Checks the method-specific part of `local_id`, without authorization.
+    fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error>;
+
+    /// Checks that `authorization` allows binding `local_id` to the
+    /// transaction signing `msg`.
+    fn verify(
+        &self,
+        local_id: &[u8],
+        authorization: Authorization,
+        msg: &[u8],
+    ) -> Result<(), Error>;
+}
+
+/// did:plc, authorized by an operation history whose rotation keys sign the
+/// transaction hash.
+pub struct Plc;
+
+impl LocalIdVerifier for Plc {
+    fn prefix(&self) -> &'static str {
+        LOCAL_ID_PREFIX
+    }
+
+    fn kind(&self) -> AuthorizationKind {
+        AuthorizationKind::Plc
+    }
+
+    fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error> {
+        parse_local_id(local_id).map(|_| ())
+    }
+
+    fn verify(
+        &self,
+        local_id: &[u8],
+        authorization: Authorization,
+        msg: &[u8],
+    ) -> Result<(), Error> {
+        let binary_did = parse_local_id(local_id)?;
+        let Authorization::Plc {
+            history,
+            rotation_key_indices,
+            sig,
+        } = authorization
+        else {
+            return Err(Error::MismatchedAuthorization);
+        };
+        validate_operation_history(&binary_did, history, rotation_key_indices, msg, &sig)
+            .map(|_| ())
+    }
+}
+
+/// did:key, authorized by a signature of the transaction hash by the key of
+/// the identifier. Only compressed secp256k1 and p256 keys are supported, as
+/// for did:plc rotation keys.
+pub struct Key;
+
+impl Key {
+    fn parse(local_id: &[u8]) -> Result<PublicKey, Error> {
+        let local_id = core::str::from_utf8(local_id).map_err(|_| Error::InvalidDidKey)?;
+        local_id.parse().map_err(|_| {
+            Error::InvalidDidKey.with_context(|c| c.actual = Some(local_id.to_owned()))
+        })
+    }
+}
+
+impl LocalIdVerifier for Key {
+    fn prefix(&self) -> &'static str {
+        "did:key:"
+    }
+
+    fn kind(&self) -> AuthorizationKind {
+        AuthorizationKind::Key
+    }
+
+    fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error> {
+        Self::parse(local_id).map(|_| ())
+    }
+
+    fn verify(
+        &self,
+        local_id: &[u8],
+        authorization: Authorization,
+        msg: &[u8],
+    ) -> Result<(), Error> {
+        let key = Self::parse(local_id)?;
+        let Authorization::Key { sig } = authorization else {
+            return Err(Error::MismatchedAuthorization);
+        };
+        key.verify(msg, &sig)
+            .map_err(|_| Error::DidKeySignatureFailed.with_context(|c| c.key = Some(key.did_key())))
+    }
+}
+
+/// did:web, authorized by its DID document and a signature of the transaction
+/// hash by one of its verification methods, see [`crate::web`].
+pub struct Web;
+
+impl LocalIdVerifier for Web {
+    fn prefix(&self) -> &'static str {
+        web::PREFIX
+    }
+
+    fn kind(&self) -> AuthorizationKind {
+        AuthorizationKind::Web
+    }
+
+    fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error> {
+        let local_id = core::str::from_utf8(local_id).map_err(|_| Error::InvalidDidWeb)?;
+        web::check_did_web(local_id)
+    }
+
+    fn verify(
+        &self,
+        local_id: &[u8],
+        authorization: Authorization,
+        msg: &[u8],
+    ) -> Result<(), Error> {
+        let did = core::str::from_utf8(local_id).map_err(|_| Error::InvalidDidWeb)?;
+        web::check_did_web(did)?;
+        let Authorization::Web {
+            document,
+            verification_method,
+            sig,
+        } = authorization
+        else {
+            return Err(Error::MismatchedAuthorization);
+        };
+        let method = core::str::from_utf8(&verification_method)
+            .map_err(|_| Error::InvalidVerificationMethod)?;
+        let key = web::verification_key(did, &document, method)?;
+        key.verify(msg, &sig)
+            .map_err(|_| Error::DidWebSignatureFailed.with_context(|c| c.key = Some(key.did_key())))
+    }
+}
+
+/// The verifiers of the supported DID methods, keyed by prefix.
+pub struct Registry {
+    verifiers: Vec<Box<dyn LocalIdVerifier>>,
+}
+
+impl Default for Registry {
+    /// The methods the script supports: did:plc, did:key and did:web.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Plc).register(Key).register(Web);
+        registry
+    }
+}
+
+impl Registry {
+    /// A registry without any method.
+    pub fn new() -> Self {
+        Self {
+            verifiers: Vec::new(),
+        }
+    }
+
+    /// Adds `verifier`, replacing the one with the same prefix.
+    pub fn register(&mut self, verifier: impl LocalIdVerifier + 'static) -> &mut Self {
+        self.verifiers.retain(|v| v.prefix() != verifier.prefix());
+        self.verifiers.push(Box::new(verifier));
+        self
+    }
+
+    /// The prefixes of the registered methods.
+    pub fn prefixes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.verifiers.iter().map(|v| v.prefix())
+    }
+
+    /// The verifier of the method of `local_id`, `InvalidDidFormat` for a
+    /// method without one.
+    pub fn find(&self, local_id: &[u8]) -> Result<&dyn LocalIdVerifier, Error> {
+        self.verifiers
+            .iter()
+            .find(|v| local_id.starts_with(v.prefix().as_bytes()))
+            .map(|v| v.as_ref())
+            .ok_or_else(|| {
+                Error::InvalidDidFormat.with_context(|c| {
+                    c.actual = core::str::from_utf8(local_id).ok().map(ToOwned::to_owned)
+                })
+            })
+    }
+
+    /// Finds the method of `local_id` and checks it.
+    pub fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error> {
+        self.find(local_id)?.check_local_id(local_id)
+    }
+}
@@ -1,11 +1,13 @@
 // Implementation choices:
 // 1. No serde used. It would make code bloat and is not necessary.
-// 2. No CID crate used. The CID implementation would require multibase, multihash,
-//    and multicodec support, which adds too many dependency crates.
+// 2. No CID crate used. It would require multibase, multihash, and multicodec
+//    support, which adds too many dependency crates. See `cid` for the subset
+//    did:plc needs.
 use alloc::borrow::ToOwned;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use alloc::{format, vec};
+use core::str::FromStr;
 
 use base32::Alphabet;
 use cbor4ii::core::dec::Decode;
@@ -17,24 +19,108 @@ use base64::Engine;
 use molecule::lazy_reader::Cursor;
 use sha2::{Digest, Sha256};
 
-use crate::error::Error;
+use crate::cid::{CID_TAG, Cid};
+use crate::error::{Context, Error};
+use crate::legacy;
 use crate::pubkey::PublicKey;
+use crate::state::PlcState;
 
-// this is the only one valid local id so far
-const LOCAL_ID_PREFIX: &str = "did:plc:";
+// the prefix of did:plc local IDs, see `local_id` for the other methods
+pub(crate) const LOCAL_ID_PREFIX: &str = "did:plc:";
+// the length of the identifier after the prefix, the base32 encoding of the
+// 15 bytes of a binary DID
+const LOCAL_ID_LEN: usize = 24;
 
 pub fn parse_local_id(id: &[u8]) -> Result<Vec<u8>, Error> {
     let str = core::str::from_utf8(id).map_err(|_| Error::InvalidDidFormat)?;
-    if let Some(str) = str.strip_prefix(LOCAL_ID_PREFIX) {
-        base32::decode(Alphabet::Rfc4648Lower { padding: false }, str)
-            .ok_or(Error::InvalidDidFormat)
-    } else {
-        Err(Error::InvalidDidFormat)
+    let invalid = || Error::InvalidDidFormat.with_context(|c| c.actual = Some(str.to_owned()));
+    match str.strip_prefix(LOCAL_ID_PREFIX) {
+        // base32 decoding drops trailing bits, only the canonical length maps
+        // to a single binary DID
+        Some(str) if str.len() == LOCAL_ID_LEN => {
+            base32::decode(Alphabet::Rfc4648Lower { padding: false }, str).ok_or_else(invalid)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+pub(crate) fn did_string(binary_did: &[u8]) -> String {
+    format!(
+        "{}{}",
+        LOCAL_ID_PREFIX,
+        base32::encode(Alphabet::Rfc4648Lower { padding: false }, binary_did)
+    )
+}
+
+/// The most rotation keys a `plc_operation` may list.
+pub const MAX_ROTATION_KEYS: usize = 5;
+
+/// A rotation key index left unspecified in a history: the rotation keys of
+/// the step are tried in priority order instead, see [`MAX_KEY_TRIALS`]. It
+/// is the largest index a witness can hold.
+pub const UNSPECIFIED_KEY_INDEX: usize = u8::MAX as usize;
+
+/// The most signature verifications that may fail over a history while the
+/// keys of its unspecified rotation key indices are tried, bounding their
+/// cost.
+pub const MAX_KEY_TRIALS: usize = 8;
+
+// The failed verifications left to the unspecified rotation key indices of a
+// history
+pub(crate) struct KeyTrials(usize);
+
+impl Default for KeyTrials {
+    fn default() -> Self {
+        Self(MAX_KEY_TRIALS)
+    }
+}
+
+impl KeyTrials {
+    // The rotation key index verifying the step: `index` itself, or for an
+    // unspecified one the first of the `key_count` keys that `verify` accepts
+    fn find(
+        &mut self,
+        index: usize,
+        key_count: usize,
+        verify: impl Fn(usize) -> Result<(), Error>,
+    ) -> Result<usize, Error> {
+        if index != UNSPECIFIED_KEY_INDEX {
+            return verify(index).map(|_| index);
+        }
+        for index in 0..key_count {
+            let Err(e) = verify(index) else {
+                return Ok(index);
+            };
+            // a failure of this key is worth trying the next one, while a
+            // malformed signature fails whatever the key
+            if !matches!(e.root(), Error::VerifySignatureFailed | Error::InvalidKey) {
+                return Err(e);
+            }
+            self.0 = self.0.checked_sub(1).ok_or_else(|| {
+                Error::TooManyKeyTrials.with_context(|c| c.key_index = Some(index))
+            })?;
+        }
+        // none of the keys
+        Err(Error::VerifySignatureFailed
+            .with_context(|c| c.key_index = Some(UNSPECIFIED_KEY_INDEX)))
     }
 }
 
+fn is_did_key(key: &str) -> bool {
+    key.strip_prefix("did:key:")
+        .is_some_and(|key| !key.is_empty())
+}
+
+fn is_at_uri(uri: &str) -> bool {
+    uri.strip_prefix("at://")
+        .is_some_and(|authority| !authority.is_empty())
+}
+
 pub struct Operation {
+    // the fields as signed, which the signature and the CID cover
     raw: Vec<(Value, Value)>,
+    // the `plc_operation` fields of a legacy `create`, read instead of `raw`
+    normalized: Option<Vec<(Value, Value)>>,
 }
 
 impl Operation {
@@ -46,8 +132,28 @@ impl Operation {
             Value::Map(map) => map,
             _ => return Err(Error::InvalidOperation),
         };
+        let is_legacy = raw.iter().any(|(k, v)| {
+            matches!((k, v), (Value::Text(k), Value::Text(v)) if k == "type" && v == "create")
+        });
+        let normalized = match is_legacy {
+            true => Some(legacy::normalize(&raw)?),
+            false => None,
+        };
+
+        Ok(Operation { raw, normalized })
+    }
+
+    // The fields of the operation, a legacy `create` normalized
+    fn fields(&self) -> &[(Value, Value)] {
+        self.normalized.as_deref().unwrap_or(&self.raw)
+    }
 
-        Ok(Operation { raw })
+    // The value of the field `key`
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        self.fields().iter().find_map(|(k, v)| match k {
+            Value::Text(k) if k == key => Some(v),
+            _ => None,
+        })
     }
 
     pub(crate) fn new_unsigned_operation(&self) -> Result<Self, Error> {
@@ -62,58 +168,129 @@ impl Operation {
                 }
             }
         }
-        Ok(Operation { raw: unsigned_raw })
+        Ok(Operation {
+            raw: unsigned_raw,
+            normalized: None,
+        })
     }
 
     pub(crate) fn validate(&self) -> Result<(), Error> {
-        if self.is_legacy() {
-            if self.has_keys(&[
-                "type",
-                "signingKey",
-                "recoveryKey",
-                "handle",
-                "service",
-                "prev",
-                "sig",
-            ]) {
-                Ok(())
-            } else {
-                Err(Error::InvalidOperation)
-            }
-        } else if self.is_operation() {
-            if self.has_keys(&[
-                "type",
-                "rotationKeys",
-                "verificationMethods",
-                "alsoKnownAs",
-                "services",
-                "prev",
-                "sig",
-            ]) {
-                Ok(())
-            } else {
-                Err(Error::InvalidOperation)
-            }
-        } else {
-            Err(Error::InvalidOperation)
+        if !self.is_operation() {
+            return Err(Error::InvalidOperation.with_context(|c| c.field = Some("type")));
+        }
+        let keys = [
+            "type",
+            "rotationKeys",
+            "verificationMethods",
+            "alsoKnownAs",
+            "services",
+            "prev",
+            "sig",
+        ];
+        if let Some(key) = self.missing_key(&keys) {
+            return Err(Error::InvalidOperation.with_context(|c| c.field = Some(key)));
         }
+        self.validate_fields()
     }
 
-    pub(crate) fn is_operation(&self) -> bool {
-        for (k, v) in &self.raw {
-            if let (Value::Text(key), Value::Text(value)) = (k, v) {
-                if key == "type" && value == "plc_operation" {
-                    return true;
+    // The types and contents of the fields of a `plc_operation`, as the
+    // did-method-plc schema defines them
+    fn validate_fields(&self) -> Result<(), Error> {
+        let field = |name: &'static str| self.get(name).unwrap_or(&Value::Null);
+        let actual = |value: &str| value.to_owned();
+
+        let rotation_keys = match field("rotationKeys") {
+            Value::Array(keys) => keys,
+            _ => {
+                return Err(
+                    Error::RotationKeysDecodeError.with_context(|c| c.field = Some("rotationKeys"))
+                );
+            }
+        };
+        if rotation_keys.is_empty() || rotation_keys.len() > MAX_ROTATION_KEYS {
+            return Err(Error::InvalidRotationKeyCount.with_context(|c| {
+                c.field = Some("rotationKeys");
+                c.actual = Some(format!("{}", rotation_keys.len()));
+            }));
+        }
+        for (index, key) in rotation_keys.iter().enumerate() {
+            let context = |c: &mut Context| {
+                c.field = Some("rotationKeys");
+                c.key_index = Some(index);
+            };
+            match key {
+                Value::Text(key) => {
+                    PublicKey::from_str(key).map_err(|e| e.with_context(context))?;
                 }
+                _ => return Err(Error::InvalidKey.with_context(context)),
             }
         }
-        false
+
+        let invalid_methods = |actual: Option<String>| {
+            Error::InvalidVerificationMethods.with_context(|c| {
+                c.field = Some("verificationMethods");
+                c.actual = actual;
+            })
+        };
+        let Value::Map(methods) = field("verificationMethods") else {
+            return Err(invalid_methods(None));
+        };
+        for (id, key) in methods {
+            match (id, key) {
+                (Value::Text(_), Value::Text(key)) if is_did_key(key) => {}
+                (_, Value::Text(key)) => return Err(invalid_methods(Some(actual(key)))),
+                _ => return Err(invalid_methods(None)),
+            }
+        }
+
+        let invalid_aka = |actual: Option<String>| {
+            Error::InvalidAlsoKnownAs.with_context(|c| {
+                c.field = Some("alsoKnownAs");
+                c.actual = actual;
+            })
+        };
+        let Value::Array(uris) = field("alsoKnownAs") else {
+            return Err(invalid_aka(None));
+        };
+        for uri in uris {
+            match uri {
+                Value::Text(uri) if is_at_uri(uri) => {}
+                Value::Text(uri) => return Err(invalid_aka(Some(actual(uri)))),
+                _ => return Err(invalid_aka(None)),
+            }
+        }
+
+        let invalid_service = |actual: Option<String>| {
+            Error::InvalidService.with_context(|c| {
+                c.field = Some("services");
+                c.actual = actual;
+            })
+        };
+        let Value::Map(services) = field("services") else {
+            return Err(invalid_service(None));
+        };
+        for (id, service) in services {
+            let (Value::Text(id), Value::Map(service)) = (id, service) else {
+                return Err(invalid_service(None));
+            };
+            let member = |name: &str| {
+                service.iter().find_map(|(k, v)| match (k, v) {
+                    (Value::Text(k), Value::Text(v)) if k == name => Some(v),
+                    _ => None,
+                })
+            };
+            if member("type").is_none() || member("endpoint").is_none() {
+                return Err(invalid_service(Some(id.clone())));
+            }
+        }
+        Ok(())
     }
 
-    pub(crate) fn is_legacy(&self) -> bool {
-        for (k, v) in &self.raw {
+    // a `plc_operation`, or a legacy `create` normalized into one
+    pub(crate) fn is_operation(&self) -> bool {
+        for (k, v) in self.fields() {
             if let (Value::Text(key), Value::Text(value)) = (k, v) {
-                if key == "type" && value == "create" {
+                if key == "type" && value == "plc_operation" {
                     return true;
                 }
             }
@@ -122,7 +299,7 @@ impl Operation {
     }
 
     pub(crate) fn get_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
-        for (k, v) in &self.raw {
+        for (k, v) in self.fields() {
             if let (Value::Text(key), Value::Array(value)) = (k, v) {
                 if key == "rotationKeys" {
                     let mut rotation_keys = vec![];
@@ -136,20 +313,25 @@ impl Operation {
                 }
             }
         }
-        Err(Error::RotationKeysDecodeError)
+        Err(Error::RotationKeysDecodeError.with_context(|c| c.field = Some("rotationKeys")))
     }
 
-    // "signingKey" and "recoveryKey" are both used as rotation keys for legacy operation
-    pub(crate) fn get_legacy_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
-        let mut pubkeys = vec![];
-        for (k, v) in &self.raw {
-            if let (Value::Text(key), Value::Text(value)) = (k, v) {
-                if key == "signingKey" || key == "recoveryKey" {
-                    pubkeys.push(PublicKey::from_str(value)?);
+    // The `did:key`s of `get_rotation_keys`, in the same order
+    pub(crate) fn get_rotation_key_ids(&self) -> Vec<String> {
+        let mut ids = vec![];
+        for (k, v) in self.fields() {
+            if let (Value::Text(key), Value::Array(value)) = (k, v) {
+                if key == "rotationKeys" {
+                    for item in value {
+                        if let Value::Text(id) = item {
+                            ids.push(id.clone());
+                        }
+                    }
+                    break;
                 }
             }
         }
-        Ok(pubkeys)
+        ids
     }
 
     pub(crate) fn get_signature(&self) -> Result<Vec<u8>, Error> {
@@ -157,16 +339,38 @@ impl Operation {
             if let (Value::Text(key), Value::Text(value)) = (k, v) {
                 if key == "sig" {
                     // https://github.com/did-method-plc/did-method-plc/blob/bd5825589a34d1abb377943389ac3838a15cd110/packages/lib/src/operations.ts#L268
+                    let sig_context = |c: &mut Context| {
+                        c.field = Some("sig");
+                        c.actual = Some(value.clone());
+                    };
                     if value.ends_with("=") {
-                        return Err(Error::InvalidSignaturePadding);
+                        return Err(Error::InvalidSignaturePadding.with_context(sig_context));
                     }
                     let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
-                    let decoded_sig = engine.decode(value).map_err(|_| Error::InvalidSignature)?;
+                    let decoded_sig = engine
+                        .decode(value)
+                        .map_err(|_| Error::InvalidSignature.with_context(sig_context))?;
                     return Ok(decoded_sig);
                 }
             }
         }
-        Err(Error::InvalidOperation)
+        Err(Error::InvalidOperation.with_context(|c| c.field = Some("sig")))
+    }
+
+    // The message the signature of the operation covers
+    pub(crate) fn unsigned_message(&self) -> Result<Vec<u8>, Error> {
+        self.new_unsigned_operation()?.encode()
+    }
+
+    fn encode(self) -> Result<Vec<u8>, Error> {
+        let mut writer = BufWriter::new(Vec::new());
+
+        // Convert Vec<(Value, Value)> back to Value::Map for encoding
+        let map_value = Value::Map(self.raw);
+        map_value
+            .encode(&mut writer)
+            .map_err(|_| Error::InvalidOperation)?;
+        Ok(writer.into_inner())
     }
 
     // note, the `pubkeys`` are from previous operation
@@ -177,91 +381,84 @@ impl Operation {
     ) -> Result<(), Error> {
         let unsigned_op = self.new_unsigned_operation()?;
         let sig = self.get_signature()?;
-        let mut writer = BufWriter::new(Vec::new());
-
-        // Convert Vec<(Value, Value)> back to Value::Map for encoding
-        let map_value = Value::Map(unsigned_op.raw);
-        map_value
-            .encode(&mut writer)
-            .map_err(|_| Error::InvalidOperation)?;
-        let msg = writer.into_inner();
+        let msg = unsigned_op.encode()?;
 
         if rotation_key_index >= pubkeys.len() {
-            return Err(Error::InvalidKeyIndex);
+            return Err(
+                Error::InvalidKeyIndex.with_context(|c| c.key_index = Some(rotation_key_index))
+            );
         }
-        if pubkeys[rotation_key_index].verify(&msg, &sig).is_ok() {
-            Ok(())
-        } else {
-            #[cfg(feature = "enable_log")]
-            {
-                log::warn!("verify signature failed");
-                log::warn!("sig: (length = {}), {}", sig.len(), hex::encode(sig));
-                log::warn!("msg: (length = {}), {}", msg.len(), hex::encode(msg));
-                log::warn!("rotation_key_index = {}", rotation_key_index);
-                for pubkey in pubkeys {
-                    let pubkey = pubkey.raw();
-                    log::warn!(
-                        "pubkey: (length = {}), {}",
-                        pubkey.len(),
-                        hex::encode(pubkey)
-                    );
+        match pubkeys[rotation_key_index].verify_key(&msg, &sig) {
+            Ok(()) => Ok(()),
+            // not a signature of any key
+            Err(Error::InvalidSignature) => {
+                Err(Error::InvalidSignature.with_context(|c| c.field = Some("sig")))
+            }
+            // an invalid key verifies no signature either
+            Err(_) => {
+                #[cfg(feature = "enable_log")]
+                {
+                    log::warn!("verify signature failed");
+                    log::warn!("sig: (length = {}), {}", sig.len(), hex::encode(sig));
+                    log::warn!("msg: (length = {}), {}", msg.len(), hex::encode(msg));
+                    log::warn!("rotation_key_index = {}", rotation_key_index);
+                    for pubkey in pubkeys {
+                        let pubkey = pubkey.raw();
+                        log::warn!(
+                            "pubkey: (length = {}), {}",
+                            pubkey.len(),
+                            hex::encode(pubkey)
+                        );
+                    }
                 }
+                Err(Error::VerifySignatureFailed.with_context(|c| {
+                    c.key_index = Some(rotation_key_index);
+                    c.key = Some(pubkeys[rotation_key_index].did_key());
+                }))
             }
-            Err(Error::VerifySignatureFailed)
         }
     }
 
-    pub fn generate_cid(&self) -> Result<String, Error> {
+    pub fn cid(&self) -> Result<Cid, Error> {
         let mut writer = BufWriter::new(Vec::new());
 
         let map_value = types::Map(self.raw.as_slice());
         map_value
             .encode(&mut writer)
             .map_err(|_| Error::InvalidOperation)?;
-        let dag = writer.into_inner();
-        let hashed = Sha256::digest(dag.as_slice());
+        Ok(Cid::dag_cbor(&writer.into_inner()))
+    }
 
-        // the following algorithm is to assemble CID manually:
-        // CIDv1
-        // base32 multibase encoding (prefix: b)
-        // dag-cbor multibase type (code: 0x71)
-        // sha-256 multihash (code: 0x12)
-        let mut raw_cid = vec![0x01, 0x71, 0x12, 0x20];
-        raw_cid.extend_from_slice(hashed.as_slice());
-
-        let b32 = base32::encode(
-            Alphabet::Rfc4648Lower { padding: false },
-            raw_cid.as_slice(),
-        );
-        Ok("b".to_owned() + &b32)
+    /// The CID of the operation in its canonical text form.
+    pub fn generate_cid(&self) -> Result<String, Error> {
+        Ok(self.cid()?.to_string())
     }
 
-    // The `prev` field can be null for genesis operation
-    pub(crate) fn get_prev(&self) -> Result<Option<String>, Error> {
+    // The `prev` field can be null for genesis operation. Otherwise it is a
+    // CID, as text or in binary: bytes, or a DAG-CBOR link (tag 42).
+    pub(crate) fn get_prev(&self) -> Result<Option<Cid>, Error> {
+        let invalid = |e: Error| e.with_context(|c| c.field = Some("prev"));
         for (k, v) in &self.raw {
             if let Value::Text(key) = k {
                 if key == "prev" {
-                    if let Value::Text(value) = v {
-                        return Ok(Some(value.clone()));
-                    } else if let Value::Null = v {
-                        return Ok(None);
-                    } else {
-                        return Err(Error::InvalidOperation);
-                    }
+                    return match v {
+                        Value::Null => Ok(None),
+                        Value::Text(value) => Cid::from_str(value).map(Some).map_err(invalid),
+                        Value::Bytes(value) => Cid::from_bytes(value).map(Some).map_err(invalid),
+                        Value::Tag(CID_TAG, link) => match link.as_ref() {
+                            Value::Bytes(value) => Cid::from_link(value).map(Some).map_err(invalid),
+                            _ => Err(invalid(Error::InvalidCid)),
+                        },
+                        _ => Err(invalid(Error::InvalidOperation)),
+                    };
                 }
             }
         }
-        Err(Error::InvalidOperation)
+        Err(invalid(Error::InvalidOperation))
     }
 
-    #[allow(unused)]
     pub(crate) fn get_did(&self) -> Result<String, Error> {
-        let binary_did = self.get_binary_did()?;
-        let b32 = base32::encode(
-            Alphabet::Rfc4648Lower { padding: false },
-            binary_did.as_slice(),
-        );
-        Ok(format!("did:plc:{}", b32))
+        Ok(did_string(&self.get_binary_did()?))
     }
 
     pub(crate) fn get_binary_did(&self) -> Result<Vec<u8>, Error> {
@@ -278,9 +475,9 @@ impl Operation {
         Ok(hashed[0..15].to_vec())
     }
 
-    fn has_keys(&self, keys: &[&str]) -> bool {
-        keys.iter().all(|&key| {
-            self.raw.iter().any(|(k, _)| {
+    fn missing_key(&self, keys: &[&'static str]) -> Option<&'static str> {
+        keys.iter().copied().find(|&key| {
+            !self.fields().iter().any(|(k, _)| {
                 if let Value::Text(k_str) = k {
                     k_str == key
                 } else {
@@ -306,11 +503,27 @@ pub fn validate_2_operations(
     cur_buf: &[u8],
     rotation_key_index: usize,
 ) -> Result<(), Error> {
+    validate_2_operations_with(
+        prev_buf,
+        cur_buf,
+        rotation_key_index,
+        &mut KeyTrials::default(),
+    )
+    .map(|_| ())
+}
+
+// Validates 2 operations and returns the rotation key index that verified
+pub(crate) fn validate_2_operations_with(
+    prev_buf: &[u8],
+    cur_buf: &[u8],
+    rotation_key_index: usize,
+    trials: &mut KeyTrials,
+) -> Result<usize, Error> {
     let prev_op = Operation::from_slice(prev_buf)?;
     let cur_op = Operation::from_slice(cur_buf)?;
     prev_op.validate()?;
     cur_op.validate()?;
-    let cid = prev_op.generate_cid()?;
+    let cid = prev_op.cid()?;
     match cur_op.get_prev()? {
         Some(prev) => {
             if prev != cid {
@@ -320,18 +533,19 @@ pub fn validate_2_operations(
                     log::warn!("cid: {}", cid);
                     log::warn!("prev: {}", prev);
                 }
-                return Err(Error::InvalidPrev);
+                return Err(Error::InvalidPrev.with_context(|c| {
+                    c.field = Some("prev");
+                    c.expected = Some(cid.to_string());
+                    c.actual = Some(prev.to_string());
+                }));
             }
         }
-        None => return Err(Error::MissingPrevField),
+        None => return Err(Error::MissingPrevField.with_context(|c| c.field = Some("prev"))),
     }
-    let rotation_keys = if prev_op.is_legacy() {
-        prev_op.get_legacy_rotation_keys()?
-    } else {
-        prev_op.get_rotation_keys()?
-    };
-    cur_op.verify_signature(&rotation_keys, rotation_key_index)?;
-    Ok(())
+    let rotation_keys = prev_op.get_rotation_keys()?;
+    trials.find(rotation_key_index, rotation_keys.len(), |index| {
+        cur_op.verify_signature(&rotation_keys, index)
+    })
 }
 
 pub fn validate_genesis_operation(
@@ -339,18 +553,36 @@ pub fn validate_genesis_operation(
     binary_did: &[u8],
     rotation_key_index: usize,
 ) -> Result<(), Error> {
+    validate_genesis_operation_with(
+        buf,
+        binary_did,
+        rotation_key_index,
+        &mut KeyTrials::default(),
+    )
+    .map(|_| ())
+}
+
+// Validates a genesis operation and returns the rotation key index that
+// verified
+pub(crate) fn validate_genesis_operation_with(
+    buf: &[u8],
+    binary_did: &[u8],
+    rotation_key_index: usize,
+    trials: &mut KeyTrials,
+) -> Result<usize, Error> {
     let op = Operation::from_slice(buf)?;
     op.validate()?;
     let prev = op.get_prev()?;
-    if prev.is_some() {
-        return Err(Error::NotGenesisOperation);
+    if let Some(prev) = prev {
+        return Err(Error::NotGenesisOperation.with_context(|c| {
+            c.field = Some("prev");
+            c.actual = Some(prev.to_string());
+        }));
     }
-    let rotation_keys = if op.is_legacy() {
-        op.get_legacy_rotation_keys()?
-    } else {
-        op.get_rotation_keys()?
-    };
-    op.verify_signature(&rotation_keys, rotation_key_index)?;
+    let rotation_keys = op.get_rotation_keys()?;
+    let rotation_key_index = trials.find(rotation_key_index, rotation_keys.len(), |index| {
+        op.verify_signature(&rotation_keys, index)
+    })?;
     let expected_did = op.get_binary_did()?;
     if binary_did != expected_did {
         #[cfg(feature = "enable_log")]
@@ -359,21 +591,45 @@ pub fn validate_genesis_operation(
             log::warn!("did: {:?}", binary_did);
             log::warn!("expected did: {:?}", expected_did);
         }
-        return Err(Error::DidMismatched);
+        return Err(Error::DidMismatched.with_context(|c| {
+            c.expected = Some(did_string(binary_did));
+            c.actual = Some(did_string(&expected_did));
+        }));
     }
-    Ok(())
+    Ok(rotation_key_index)
 }
 
-fn validate_final_operation(
+// Validates the final signature and returns the rotation key index that
+// verified
+pub(crate) fn validate_final_operation(
     buf: &[u8],
     final_sig: &[u8],
     msg: &[u8],
     rotation_key_index: usize,
-) -> Result<(), Error> {
+    trials: &mut KeyTrials,
+) -> Result<usize, Error> {
     let op = Operation::from_slice(buf)?;
     let rotation_keys = op.get_rotation_keys()?;
-    rotation_keys[rotation_key_index].verify(msg, final_sig)?;
-    Ok(())
+    trials
+        .find(rotation_key_index, rotation_keys.len(), |index| {
+            let key_context = |c: &mut Context| c.key_index = Some(index);
+            let key = rotation_keys
+                .get(index)
+                .ok_or_else(|| Error::InvalidKeyIndex.with_context(key_context))?;
+            key.verify_key(msg, final_sig).map_err(|e| {
+                e.with_context(|c| {
+                    key_context(c);
+                    c.key = Some(key.did_key());
+                })
+            })
+        })
+        // as `PublicKey::verify`, a final signature no key verifies is invalid
+        .map_err(|e| {
+            e.map_root(|e| match e {
+                Error::VerifySignatureFailed => Error::InvalidSignature,
+                e => e,
+            })
+        })
 }
 
 /// Validates a complete DID PLC operation history chain and final authorization signature.
@@ -393,36 +649,100 @@ fn validate_final_operation(
 ///   - `rotation_key_indices[0]`: Index for genesis operation signature
 ///   - `rotation_key_indices[1..history_len]`: Indices for transitions between operations
 ///   - `rotation_key_indices[history_len]`: Index for final authorization signature
+///
+///   Any of them may be [`UNSPECIFIED_KEY_INDEX`], the rotation keys of that
+///   step are then tried in priority order, within [`MAX_KEY_TRIALS`] failed
+///   verifications over the whole history.
 /// * `msg` - The message that was signed for the final authorization
 /// * `final_sig` - The signature authorizing the DID PLC operation on-chain
 ///
+/// Returns the rotation key indices that verified, the unspecified ones found.
 pub fn validate_operation_history(
     binary_did: &[u8],
     history: Vec<Cursor>,
     rotation_key_indices: Vec<usize>,
     msg: &[u8],
     final_sig: &[u8],
-) -> Result<(), Error> {
+) -> Result<Vec<usize>, Error> {
+    validate_history(binary_did, history, rotation_key_indices, msg, final_sig)
+        .map(|(_, rotation_key_indices)| rotation_key_indices)
+}
+
+/// Validates a history like [`validate_operation_history`] and returns the
+/// state of the did:plc after its latest operation: its rotation keys,
+/// verification methods, `alsoKnownAs`, services and head CID.
+pub fn resolve_operation_history(
+    binary_did: &[u8],
+    history: Vec<Cursor>,
+    rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Result<PlcState, Error> {
+    let history_len = history.len();
+    let (latest, _) = validate_history(binary_did, history, rotation_key_indices, msg, final_sig)?;
+    let op = Operation::from_slice(&latest)?;
+    PlcState::from_operation(did_string(binary_did), &op)
+        .map_err(|e| e.with_context(|c| c.history_index = Some(history_len - 1)))
+}
+
+// Validates a history and returns its latest operation, with the rotation key
+// indices that verified
+fn validate_history(
+    binary_did: &[u8],
+    history: Vec<Cursor>,
+    mut rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Result<(Vec<u8>, Vec<usize>), Error> {
     let history_len = history.len();
 
     if history_len == 0 || (history_len + 1) != rotation_key_indices.len() {
-        return Err(Error::InvalidHistory);
+        return Err(Error::InvalidHistory.with_context(|c| {
+            c.field = Some("rotation_key_indices");
+            c.expected = Some(format!("{}", history_len + 1));
+            c.actual = Some(format!("{}", rotation_key_indices.len()));
+        }));
     }
-    let genesis_operation: Vec<u8> = history[0].clone().try_into()?;
+    let at = |index: usize| move |e: Error| e.with_context(|c| c.history_index = Some(index));
+    let genesis_operation: Vec<u8> = history[0]
+        .clone()
+        .try_into()
+        .map_err(Error::from)
+        .map_err(at(0))?;
     // Signing key index mapping:
     // - rotation_key_indices[0]: Genesis operation
     // - rotation_key_indices[1]: Transition from operation[0] to operation[1]
     // - ...
     // - rotation_key_indices[history_len - 1]: Transition from operation[history_len-2] to operation[history_len-1]
     // - rotation_key_indices[history_len]: Final operation
-    validate_genesis_operation(&genesis_operation, binary_did, rotation_key_indices[0])?;
+    let mut trials = KeyTrials::default();
+    rotation_key_indices[0] = validate_genesis_operation_with(
+        &genesis_operation,
+        binary_did,
+        rotation_key_indices[0],
+        &mut trials,
+    )
+    .map_err(at(0))?;
     let mut prev = genesis_operation;
     for index in 1..history_len {
-        let cur: Vec<u8> = history[index].clone().try_into()?;
-        validate_2_operations(&prev, &cur, rotation_key_indices[index])?;
+        let cur: Vec<u8> = history[index]
+            .clone()
+            .try_into()
+            .map_err(Error::from)
+            .map_err(at(index))?;
+        rotation_key_indices[index] =
+            validate_2_operations_with(&prev, &cur, rotation_key_indices[index], &mut trials)
+                .map_err(at(index))?;
         prev = cur;
     }
     // Validate the final operation signature to authorize the did:plc operation on chain
-    validate_final_operation(&prev, final_sig, msg, rotation_key_indices[history_len])?;
-    Ok(())
+    rotation_key_indices[history_len] = validate_final_operation(
+        &prev,
+        final_sig,
+        msg,
+        rotation_key_indices[history_len],
+        &mut trials,
+    )
+    .map_err(at(history_len))?;
+    Ok((prev, rotation_key_indices))
 }
@@ -1,8 +1,11 @@
 use crate::error::Error;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
+use core::str::FromStr;
 use multibase::Base::Base58Btc;
 
-pub(crate) struct PublicKey {
+pub struct PublicKey {
     // compressed public key
     pubkey: Vec<u8>,
     // if false, it is secp256r1
@@ -27,26 +30,30 @@ impl PublicKey {
     pub(crate) fn raw(&self) -> &[u8] {
         &self.pubkey
     }
-    pub(crate) fn from_str(key: &str) -> Result<Self, Error> {
-        if !key.starts_with("did:key:") {
-            return Err(Error::InvalidKey);
-        }
-        let key = key.split_at(8).1;
-        let raw_pubkey = decode_base58btc(key)?;
-        let is_secp256k1 = raw_pubkey[0] == 0xE7 && raw_pubkey[1] == 0x01;
-        if !is_secp256k1 && (raw_pubkey[0] != 0x80 || raw_pubkey[1] != 0x24) {
-            return Err(Error::InvalidKey);
-        }
-        let pubkey = raw_pubkey[2..].to_vec();
-        if pubkey.len() != 33 {
-            return Err(Error::InvalidKey);
-        }
-        Ok(PublicKey {
-            pubkey,
-            is_secp256k1,
+
+    /// The `did:key` of the public key.
+    pub fn did_key(&self) -> String {
+        let prefix: [u8; 2] = if self.is_secp256k1 {
+            [0xE7, 0x01]
+        } else {
+            [0x80, 0x24]
+        };
+        let mut raw = prefix.to_vec();
+        raw.extend_from_slice(&self.pubkey);
+        format!("did:key:{}", multibase::encode(Base58Btc, raw))
+    }
+
+    /// Verifies a 64-byte compact signature over the SHA-256 of `msg`.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), Error> {
+        self.verify_key(msg, sig).map_err(|e| match e {
+            Error::VerifySignatureFailed => Error::InvalidSignature,
+            e => e,
         })
     }
-    pub(crate) fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), Error> {
+
+    // Like `verify`, but tells a malformed signature, `InvalidSignature`, from
+    // a well-formed one this key doesn't verify, `VerifySignatureFailed`
+    pub(crate) fn verify_key(&self, msg: &[u8], sig: &[u8]) -> Result<(), Error> {
         if self.is_secp256k1 {
             use k256::ecdsa::signature::Verifier;
             let sig =
@@ -55,7 +62,7 @@ impl PublicKey {
                 .map_err(|_| Error::InvalidKey)?;
             pubkey
                 .verify(msg, &sig)
-                .map_err(|_| Error::InvalidSignature)
+                .map_err(|_| Error::VerifySignatureFailed)
         } else {
             use p256::ecdsa::signature::Verifier;
             let sig =
@@ -64,7 +71,38 @@ impl PublicKey {
                 .map_err(|_| Error::InvalidKey)?;
             pubkey
                 .verify(msg, &sig)
-                .map_err(|_| Error::InvalidSignature)
+                .map_err(|_| Error::VerifySignatureFailed)
+        }
+    }
+}
+
+/// Parses a `did:key` with a secp256k1 or p256 compressed public key.
+impl FromStr for PublicKey {
+    type Err = Error;
+
+    fn from_str(key: &str) -> Result<Self, Error> {
+        Self::parse(key).map_err(|e| e.with_context(|c| c.key = Some(key.into())))
+    }
+}
+
+impl PublicKey {
+    fn parse(key: &str) -> Result<Self, Error> {
+        if !key.starts_with("did:key:") {
+            return Err(Error::InvalidKey);
         }
+        let key = key.split_at(8).1;
+        let raw_pubkey = decode_base58btc(key)?;
+        let (is_secp256k1, pubkey) = match raw_pubkey.split_first_chunk::<2>() {
+            Some(([0xE7, 0x01], pubkey)) => (true, pubkey.to_vec()),
+            Some(([0x80, 0x24], pubkey)) => (false, pubkey.to_vec()),
+            _ => return Err(Error::InvalidKey),
+        };
+        if pubkey.len() != 33 {
+            return Err(Error::InvalidKey);
+        }
+        Ok(PublicKey {
+            pubkey,
+            is_secp256k1,
+        })
     }
 }
@@ -0,0 +1,330 @@
+// The rules of the did-ckb-ts type script, shared by the script and its
+// off-chain mirror in did-ckb-verifier so that both run the same code. Each
+// side implements `ScriptGroup` over its own way of loading cells and
+// witnesses: syscalls on-chain, a transaction and its resolved inputs
+// off-chain. The type ID rule and the dispatch between mint, update and burn
+// stay with each side.
+use alloc::vec::Vec;
+
+use cbor4ii::core::{Value, dec::Decode, utils::SliceReader};
+use did_ckb_molecules::lazy_reader::{
+    Cursor, DidCkbData, DidCkbDataV1, DidCkbKeyWitness, DidCkbWebWitness, DidCkbWitness,
+    KeyAuthorization, PlcAuthorization, WebAuthorization, WitnessArgs,
+};
+use molecule::lazy_reader::Error as MoleculeError;
+
+use crate::binding::{bound_did, read_binding, verify_binding};
+use crate::error::Error;
+use crate::local_id::{Authorization, AuthorizationKind, LocalIdVerifier, Plc, Registry};
+use crate::reader::validate_cbor_format;
+
+/// The errors of the script besides those of [`Error`], which the script and
+/// the verifier turn into their own error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptError {
+    Molecule,
+    InvalidDocumentCbor,
+    MismatchedFrom,
+    /// No witness, or an empty one, where the authorization is expected
+    WitnessMissing,
+    InvalidWitnessArgs,
+    /// The `WitnessArgs` field for the authorization is empty
+    AuthorizationMissing,
+    /// The authorization is in `input_type` instead of `output_type`, or the
+    /// other way around
+    MisplacedAuthorization,
+    InvalidAuthorization,
+}
+
+impl From<MoleculeError> for ScriptError {
+    fn from(_: MoleculeError) -> Self {
+        ScriptError::Molecule
+    }
+}
+
+/// The inputs or the outputs of the script group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupSource {
+    Input,
+    Output,
+}
+
+/// The cells and witnesses of a did-ckb-ts script group, as the script loads
+/// them.
+pub trait ScriptGroup {
+    type Error: From<Error> + From<ScriptError> + From<MoleculeError>;
+
+    /// The data of the cell at index 0 of `source`, which is present.
+    fn load_data(&self, source: GroupSource) -> Result<Cursor, Self::Error>;
+
+    /// The witness at index 0 of `source`, `None` when it is missing or empty.
+    fn load_witness(&self, source: GroupSource) -> Result<Option<Cursor>, Self::Error>;
+
+    /// The hash of the transaction, which authorizations sign.
+    fn tx_hash(&self) -> Result<[u8; 32], Self::Error>;
+}
+
+/// The `DidCkbData` of the cell at index 0 of `source`, with a CBOR document.
+pub fn new_data<G: ScriptGroup>(group: &G, source: GroupSource) -> Result<DidCkbDataV1, G::Error> {
+    let data = DidCkbData::try_from(group.load_data(source)?)?;
+    data.verify(false)?;
+
+    let DidCkbData::DidCkbDataV1(data) = data;
+    let doc: Vec<u8> = data
+        .document()?
+        .try_into()
+        .map_err(|_| ScriptError::InvalidDocumentCbor)?;
+
+    // check that the document with cbor format
+    let mut reader = SliceReader::new(&doc);
+    let _ = Value::decode(&mut reader).map_err(|_| ScriptError::InvalidDocumentCbor)?;
+
+    Ok(data)
+}
+
+/// Reads `witness` as a `WitnessArgs`.
+pub fn parse_witness_args(witness: Cursor) -> Result<WitnessArgs, ScriptError> {
+    let witness_args = WitnessArgs::from(witness);
+    witness_args
+        .verify(false)
+        .map_err(|_| ScriptError::InvalidWitnessArgs)?;
+    Ok(witness_args)
+}
+
+/// Where a local ID authorization is placed. It is always in the
+/// `WitnessArgs` at index 0 of a script group, leaving `lock` to the lock
+/// script:
+///
+/// | Placement    | Witness         | Field         | Authorizes                         |
+/// | ------------ | --------------- | ------------- | ---------------------------------- |
+/// | `OutputType` | `GroupOutput` 0 | `output_type` | mint, update of a bound document   |
+/// | `InputType`  | `GroupInput` 0  | `input_type`  | update of a bound document, when   |
+/// |              |                 |               | `output_type` holds none           |
+///
+/// Burns need no authorization, see [`update_authorization`] for updates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WitnessPlacement {
+    InputType,
+    OutputType,
+}
+
+/// The `placement` field of `witness_args`, holding the witness variant of the
+/// local ID's method: a `DidCkbWitness` for did:plc, a `DidCkbKeyWitness` for
+/// did:key, a `DidCkbWebWitness` for did:web.
+pub fn authorization_from(
+    witness_args: &WitnessArgs,
+    placement: WitnessPlacement,
+) -> Result<Cursor, ScriptError> {
+    let (field, other) = match placement {
+        WitnessPlacement::InputType => (witness_args.input_type(), witness_args.output_type()),
+        WitnessPlacement::OutputType => (witness_args.output_type(), witness_args.input_type()),
+    };
+    let Some(field) = field.map_err(|_| ScriptError::InvalidWitnessArgs)? else {
+        // an authorization in the other type field was meant for this one
+        return Err(match other {
+            Ok(Some(_)) => ScriptError::MisplacedAuthorization,
+            _ => ScriptError::AuthorizationMissing,
+        });
+    };
+    Ok(field)
+}
+
+// The authorization at `placement`, with the errors of the witness apart from
+// those loading it
+fn placed_authorization<G: ScriptGroup>(
+    group: &G,
+    placement: WitnessPlacement,
+) -> Result<Result<Cursor, ScriptError>, G::Error> {
+    let source = match placement {
+        WitnessPlacement::InputType => GroupSource::Input,
+        WitnessPlacement::OutputType => GroupSource::Output,
+    };
+    let Some(witness) = group.load_witness(source)? else {
+        return Ok(Err(ScriptError::WitnessMissing));
+    };
+    Ok(parse_witness_args(witness).and_then(|w| authorization_from(&w, placement)))
+}
+
+/// The authorization at `placement`, see [`WitnessPlacement`].
+pub fn new_authorization<G: ScriptGroup>(
+    group: &G,
+    placement: WitnessPlacement,
+) -> Result<Cursor, G::Error> {
+    Ok(placed_authorization(group, placement)??)
+}
+
+/// The re-authorization of an update: the `output_type` at `GroupOutput` 0,
+/// or, when that witness holds none there, the `input_type` at `GroupInput` 0,
+/// next to the previous state it replaces.
+pub fn update_authorization<G: ScriptGroup>(group: &G) -> Result<Cursor, G::Error> {
+    match placed_authorization(group, WitnessPlacement::OutputType)? {
+        Err(
+            ScriptError::WitnessMissing
+            | ScriptError::AuthorizationMissing
+            | ScriptError::MisplacedAuthorization,
+        ) => new_authorization(group, WitnessPlacement::InputType),
+        result => Ok(result?),
+    }
+}
+
+fn plc_witness(authorization: Cursor) -> Result<DidCkbWitness, ScriptError> {
+    let witness = DidCkbWitness::from(authorization);
+    witness
+        .verify(false)
+        .map_err(|_| ScriptError::InvalidAuthorization)?;
+    Ok(witness)
+}
+
+fn key_witness(authorization: Cursor) -> Result<DidCkbKeyWitness, ScriptError> {
+    let witness = DidCkbKeyWitness::from(authorization);
+    witness
+        .verify(false)
+        .map_err(|_| ScriptError::InvalidAuthorization)?;
+    Ok(witness)
+}
+
+fn web_witness(authorization: Cursor) -> Result<DidCkbWebWitness, ScriptError> {
+    let witness = DidCkbWebWitness::from(authorization);
+    witness
+        .verify(false)
+        .map_err(|_| ScriptError::InvalidAuthorization)?;
+    Ok(witness)
+}
+
+/// The authorization in the witness variant of `kind`.
+pub fn read_authorization(
+    witness: Cursor,
+    kind: AuthorizationKind,
+) -> Result<Authorization, ScriptError> {
+    match kind {
+        AuthorizationKind::Plc => read_plc_authorization(witness),
+        AuthorizationKind::Key => {
+            let auth: KeyAuthorization = key_witness(witness)?.local_id_authorization()?;
+            let sig: Vec<u8> = auth.sig()?.try_into()?;
+            Ok(Authorization::Key { sig })
+        }
+        AuthorizationKind::Web => {
+            let auth: WebAuthorization = web_witness(witness)?.local_id_authorization()?;
+            Ok(Authorization::Web {
+                document: auth.document()?.try_into()?,
+                verification_method: auth.verification_method()?.try_into()?,
+                sig: auth.sig()?.try_into()?,
+            })
+        }
+    }
+}
+
+/// The did:plc authorization in `witness`, an [`Authorization::Plc`].
+pub fn read_plc_authorization(witness: Cursor) -> Result<Authorization, ScriptError> {
+    let auth: PlcAuthorization = plc_witness(witness)?.local_id_authorization()?;
+    // History contains DID operations which can be very large. Using Cursor for lazy reading
+    // to avoid loading the entire operation history into memory at once.
+    let history = auth.history()?;
+    // `BytesVec::verify` leaves the items unchecked and its iterator unwraps
+    // them, read them one by one instead
+    let history: Vec<Cursor> = (0..history.len()?)
+        .map(|i| history.get(i))
+        .collect::<Result<_, _>>()?;
+    let sig: Vec<u8> = auth.sig()?.try_into()?;
+    let rotation_key_indices: Vec<u8> = auth.rotation_key_indices()?.try_into()?;
+    let rotation_key_indices: Vec<usize> = rotation_key_indices
+        .into_iter()
+        .map(|e| e as usize)
+        .collect();
+    Ok(Authorization::Plc {
+        history,
+        rotation_key_indices,
+        sig,
+    })
+}
+
+/// Checks that the authorization in `witness` allows binding `local_id`, of
+/// any registered method, to the transaction signing `msg`.
+pub fn validate_authorization<E: From<Error> + From<ScriptError>>(
+    witness: Cursor,
+    local_id: &[u8],
+    msg: &[u8],
+) -> Result<(), E> {
+    let registry = Registry::default();
+    let verifier = registry.find(local_id)?;
+    let authorization = read_authorization(witness, verifier.kind())?;
+    verifier.verify(local_id, authorization, msg)?;
+    #[cfg(feature = "enable_log")]
+    log::info!("validate local ID authorization successfully");
+    Ok(())
+}
+
+pub fn mint<G: ScriptGroup>(group: &G) -> Result<(), G::Error> {
+    let data = new_data(group, GroupSource::Output)?;
+    // validate cbor format
+    validate_cbor_format(data.document()?)?;
+    let document: Vec<u8> = data.document()?.try_into()?;
+    let binding = read_binding(&document)?;
+
+    let local_id: Option<Vec<u8>> = data.local_id()?.map(TryInto::try_into).transpose()?;
+    // A document bound to its local ID must be minted with a did:plc one
+    if let Some(binding) = binding {
+        let binary_did = bound_did(local_id.as_deref())?;
+        let witness = new_authorization(group, WitnessPlacement::OutputType)?;
+        let authorization = read_plc_authorization(witness)?;
+        let msg = group.tx_hash()?;
+        verify_binding(&document, binding, &binary_did, authorization, &msg)?;
+        return Ok(());
+    }
+    // Allow empty local ID - this indicates the cell has no associated local
+    // ID and can be minted without requiring its authorization
+    let Some(local_id) = local_id else {
+        return Ok(());
+    };
+
+    let witness = new_authorization(group, WitnessPlacement::OutputType)?;
+    validate_authorization(witness, &local_id, &group.tx_hash()?)
+}
+
+pub fn update<G: ScriptGroup>(group: &G) -> Result<(), G::Error> {
+    let prev_data = new_data(group, GroupSource::Input)?;
+    let cur_data = new_data(group, GroupSource::Output)?;
+
+    // validate formats of document
+    validate_cbor_format(cur_data.document()?)?;
+    validate_cbor_format(prev_data.document()?)?;
+
+    let prev_from: Vec<Vec<u8>> = prev_data
+        .local_id()?
+        .into_iter()
+        .map(|c| c.try_into().map_err(|_| ScriptError::Molecule))
+        .collect::<Result<Vec<_>, _>>()?;
+    let cur_from: Vec<Vec<u8>> = cur_data
+        .local_id()?
+        .into_iter()
+        .map(|c| c.try_into().map_err(|_| ScriptError::Molecule))
+        .collect::<Result<Vec<_>, _>>()?;
+    if prev_from != cur_from {
+        return Err(ScriptError::MismatchedFrom.into());
+    }
+
+    // A document bound to its local ID is re-authorized by the did:plc at
+    // every update, against its latest state
+    let document: Vec<u8> = cur_data.document()?.try_into()?;
+    if let Some(binding) = read_binding(&document)? {
+        let binary_did = bound_did(cur_from.first().map(Vec::as_slice))?;
+        let witness = update_authorization(group)?;
+        let authorization = read_plc_authorization(witness)?;
+        let msg = group.tx_hash()?;
+        verify_binding(&document, binding, &binary_did, authorization, &msg)?;
+        return Ok(());
+    }
+    // and so is dropping the binding, which would otherwise escape it
+    let prev_document: Vec<u8> = prev_data.document()?.try_into()?;
+    if read_binding(&prev_document)?.is_some() {
+        let local_id = cur_from.first().map(Vec::as_slice).unwrap_or_default();
+        let witness = update_authorization(group)?;
+        let authorization = read_plc_authorization(witness)?;
+        Plc.verify(local_id, authorization, &group.tx_hash()?)?;
+    }
+    Ok(())
+}
+
+pub fn burn<G: ScriptGroup>(_group: &G) -> Result<(), G::Error> {
+    Ok(())
+}
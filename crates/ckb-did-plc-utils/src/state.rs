@@ -0,0 +1,102 @@
+// The state of a did:plc after a validated history, as the PLC directory
+// resolves it from the latest operation. See `resolve_operation_history`.
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use cbor4ii::core::Value;
+
+use crate::error::Error;
+use crate::operation::Operation;
+
+/// A service of a did:plc, an entry of `services` in an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    pub r#type: String,
+    pub endpoint: String,
+}
+
+/// The resolved state of a did:plc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlcState {
+    pub did: String,
+    /// `did:key`s allowed to sign the next operation, in priority order
+    pub rotation_keys: Vec<String>,
+    /// `did:key`s by id, such as `atproto`
+    pub verification_methods: BTreeMap<String, String>,
+    /// URIs such as `at://alice.test`
+    pub also_known_as: Vec<String>,
+    /// Services by id, such as `atproto_pds`
+    pub services: BTreeMap<String, Service>,
+    /// CID of the latest operation, the `prev` of the next one
+    pub head: String,
+}
+
+fn invalid(field: &'static str) -> Error {
+    Error::InvalidOperation.with_context(|c| c.field = Some(field))
+}
+
+fn text(value: &Value, field: &'static str) -> Result<String, Error> {
+    match value {
+        Value::Text(text) => Ok(text.clone()),
+        _ => Err(invalid(field)),
+    }
+}
+
+fn texts(value: &Value, field: &'static str) -> Result<Vec<String>, Error> {
+    match value {
+        Value::Array(items) => items.iter().map(|item| text(item, field)).collect(),
+        _ => Err(invalid(field)),
+    }
+}
+
+fn entries<'a>(
+    value: &'a Value,
+    field: &'static str,
+) -> Result<impl Iterator<Item = Result<(String, &'a Value), Error>>, Error> {
+    match value {
+        Value::Map(map) => Ok(map.iter().map(move |(k, v)| Ok((text(k, field)?, v)))),
+        _ => Err(invalid(field)),
+    }
+}
+
+impl PlcState {
+    /// The state `op` leaves `did` in. `op` is a validated `plc_operation`.
+    pub(crate) fn from_operation(did: String, op: &Operation) -> Result<Self, Error> {
+        let field = |name: &'static str| op.get(name).ok_or_else(|| invalid(name));
+
+        let rotation_keys = texts(field("rotationKeys")?, "rotationKeys")?;
+        let verification_methods = entries(field("verificationMethods")?, "verificationMethods")?
+            .map(|entry| {
+                let (id, key) = entry?;
+                Ok((id, text(key, "verificationMethods")?))
+            })
+            .collect::<Result<_, Error>>()?;
+        let also_known_as = texts(field("alsoKnownAs")?, "alsoKnownAs")?;
+        let services = entries(field("services")?, "services")?
+            .map(|entry| {
+                let (id, service) = entry?;
+                let member = |name: &str| {
+                    entries(service, "services")?
+                        .filter_map(Result::ok)
+                        .find(|(k, _)| k == name)
+                        .ok_or_else(|| invalid("services"))
+                        .and_then(|(_, v)| text(v, "services"))
+                };
+                let service = Service {
+                    r#type: member("type")?,
+                    endpoint: member("endpoint")?,
+                };
+                Ok((id, service))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(PlcState {
+            did,
+            rotation_keys,
+            verification_methods,
+            also_known_as,
+            services,
+            head: op.generate_cid()?,
+        })
+    }
+}
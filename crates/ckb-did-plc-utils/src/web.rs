@@ -0,0 +1,118 @@
+// did:web local IDs. The script cannot fetch a DID document over HTTPS, so
+// the authorization carries the document as it is served, and a signature by
+// one of its verification methods. Binding the did:web proves control of a key
+// the document claims; it is up to the reader of a did:ckb to check that the
+// domain still serves that document.
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+
+use crate::error::Error;
+use crate::json::Value;
+use crate::pubkey::PublicKey;
+
+pub const PREFIX: &str = "did:web:";
+
+fn invalid(did: &str) -> Error {
+    Error::InvalidDidWeb.with_context(|c| c.actual = Some(did.to_owned()))
+}
+
+// A host name of letters, digits and hyphens, in dot-separated labels
+fn is_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+// Unreserved characters and percent-encoded octets
+fn is_path_segment(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3);
+                if !hex.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                    return false;
+                }
+                i += 3;
+            }
+            b if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') => i += 1,
+            _ => return false,
+        }
+    }
+    !bytes.is_empty()
+}
+
+/// Checks the syntax of a did:web: a host name, with a port encoded as `%3A`,
+/// then optional colon-separated path segments.
+pub fn check_did_web(did: &str) -> Result<(), Error> {
+    let id = did.strip_prefix(PREFIX).ok_or_else(|| invalid(did))?;
+    let mut segments = id.split(':');
+    let authority = segments.next().unwrap_or_default();
+    let (host, port) = match authority.split_once("%3A") {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let valid_port = port.is_none_or(|port| {
+        !port.is_empty() && port.len() <= 5 && port.bytes().all(|b| b.is_ascii_digit())
+    });
+    if !is_host(host) || !valid_port || !segments.all(is_path_segment) {
+        return Err(invalid(did));
+    }
+    Ok(())
+}
+
+/// The key of the verification method `method` of `document`, the DID
+/// document of `did`. `method` is the `id` of the verification method, either
+/// a whole DID URL or a fragment like `#key-1`, relative to `did`.
+///
+/// Only methods with a `publicKeyMultibase` of a compressed secp256k1 or p256
+/// key, as in a did:key, are supported.
+pub fn verification_key(did: &str, document: &[u8], method: &str) -> Result<PublicKey, Error> {
+    let document = Value::parse(document).ok_or(Error::InvalidDidWebDocument)?;
+    let id = document.get("id").and_then(Value::as_str);
+    if id != Some(did) {
+        return Err(Error::InvalidDidWebDocument.with_context(|c| {
+            c.field = Some("id");
+            c.expected = Some(did.to_owned());
+            c.actual = id.map(ToOwned::to_owned);
+        }));
+    }
+    let absolute = |id: &str| -> String {
+        match id.starts_with('#') {
+            true => format!("{}{}", did, id),
+            false => id.to_owned(),
+        }
+    };
+    let method = absolute(method);
+    let not_found = || {
+        Error::InvalidVerificationMethod.with_context(|c| {
+            c.field = Some("verificationMethod");
+            c.actual = Some(method.clone());
+        })
+    };
+    let entry = document
+        .get("verificationMethod")
+        .and_then(Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .find(|entry| {
+            entry
+                .get("id")
+                .and_then(Value::as_str)
+                .is_some_and(|id| absolute(id) == method)
+        })
+        .ok_or_else(not_found)?;
+    let key = entry
+        .get("publicKeyMultibase")
+        .and_then(Value::as_str)
+        .ok_or_else(not_found)?;
+    format!("did:key:{}", key).parse().map_err(|_| not_found())
+}
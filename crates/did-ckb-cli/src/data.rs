@@ -0,0 +1,44 @@
+use crate::error::Error;
+use ckb_did_plc_utils::{
+    cbor4ii::core::{Value, dec::Decode, utils::SliceReader},
+    local_id::Registry,
+};
+use did_ckb_indexer::resolver::{cbor_to_json, decode_cell_data};
+use did_ckb_molecules::types::{DidCkbData, DidCkbDataV1};
+use serde_json::{Value as JsonValue, json};
+
+/// Parses hex with or without a `0x` prefix.
+pub fn parse_hex(s: &str) -> Result<Vec<u8>, Error> {
+    let s = s.trim();
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|_| Error::InvalidHex(s.to_owned()))
+}
+
+/// Decodes `DidCkbData` with its document rendered as JSON.
+pub fn decode(data: &[u8]) -> Result<JsonValue, Error> {
+    let (document, local_id) = decode_cell_data(data)?;
+    Ok(json!({
+        "document": cbor_to_json(&document),
+        "localId": local_id,
+    }))
+}
+
+/// Renders a bare CBOR document as JSON.
+pub fn decode_document(document: &[u8]) -> Result<JsonValue, Error> {
+    let mut reader = SliceReader::new(document);
+    let document = Value::decode(&mut reader).map_err(|_| Error::InvalidDocument)?;
+    Ok(cbor_to_json(&document))
+}
+
+/// Encodes `DidCkbData` after checking the document and local ID the way the
+/// script does.
+pub fn encode(document: &[u8], local_id: Option<&str>) -> Result<Vec<u8>, Error> {
+    decode_document(document)?;
+    if let Some(local_id) = local_id {
+        Registry::default().check_local_id(local_id.as_bytes())?;
+    }
+    Ok(DidCkbData::DidCkbDataV1(DidCkbDataV1 {
+        document: document.to_vec(),
+        local_id: local_id.map(str::to_owned),
+    })
+    .to_vec())
+}
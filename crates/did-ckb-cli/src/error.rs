@@ -0,0 +1,76 @@
+use core::fmt::Display;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Indexer(did_ckb_indexer::error::Error),
+    Verifier(did_ckb_verifier::error::Error),
+    Utils(ckb_did_plc_utils::error::Error),
+    Molecule(did_ckb_molecules::types::Error),
+    InvalidHex(String),
+    InvalidOutPoint(String),
+    InvalidDocument,
+    // The capacity of an output is below what its lock, type and data occupy.
+    InsufficientCapacity { capacity: u64, occupied: u64 },
+    InvalidTransaction(String),
+    InvalidDeployment(String),
+    // A did:web document could not be fetched
+    Fetch(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<did_ckb_indexer::error::Error> for Error {
+    fn from(e: did_ckb_indexer::error::Error) -> Self {
+        Error::Indexer(e)
+    }
+}
+
+impl From<did_ckb_verifier::error::Error> for Error {
+    fn from(e: did_ckb_verifier::error::Error) -> Self {
+        Error::Verifier(e)
+    }
+}
+
+impl From<ckb_did_plc_utils::error::Error> for Error {
+    fn from(e: ckb_did_plc_utils::error::Error) -> Self {
+        Error::Utils(e)
+    }
+}
+
+impl From<did_ckb_molecules::types::Error> for Error {
+    fn from(e: did_ckb_molecules::types::Error) -> Self {
+        Error::Molecule(e)
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(e: ureq::Error) -> Self {
+        Error::Fetch(e.to_string())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            // the explanation of the script exit code
+            Error::Verifier(e) => write!(f, "{}", e),
+            // with its context under the `error_context` feature
+            Error::Utils(e) => write!(f, "{}", e),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
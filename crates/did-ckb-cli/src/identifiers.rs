@@ -0,0 +1,50 @@
+use crate::error::Error;
+use ckb_did_plc_utils::{
+    base32::{self, Alphabet},
+    operation::Operation,
+};
+use ckb_hash::new_blake2b;
+use ckb_types::{H256, packed::CellInput, packed::OutPoint, prelude::*};
+use did_ckb_indexer::resolver::did_from_key;
+use std::str::FromStr;
+
+/// Parses an out point written as `<tx hash>:<index>`.
+pub fn parse_out_point(s: &str) -> Result<OutPoint, Error> {
+    let invalid = || Error::InvalidOutPoint(s.to_owned());
+    let (tx_hash, index) = s.split_once(':').ok_or_else(invalid)?;
+    let tx_hash =
+        H256::from_str(tx_hash.strip_prefix("0x").unwrap_or(tx_hash)).map_err(|_| invalid())?;
+    let index: u32 = index.parse().map_err(|_| invalid())?;
+    Ok(OutPoint::new(tx_hash.pack(), index))
+}
+
+/// The type ID args of the output at `output_index` of a transaction whose
+/// first input is `first_input`.
+pub fn type_id_args(first_input: &CellInput, output_index: u64) -> [u8; 20] {
+    let mut blake2b = new_blake2b();
+    blake2b.update(first_input.as_slice());
+    blake2b.update(&output_index.to_le_bytes());
+    let mut hash = [0u8; 32];
+    blake2b.finalize(&mut hash);
+    hash[..20].try_into().unwrap()
+}
+
+/// The did:ckb minted at `output_index` by a transaction whose first input
+/// spends `first_input`.
+pub fn did_ckb(first_input: &OutPoint, output_index: u64) -> String {
+    let input = CellInput::new(first_input.clone(), 0);
+    did_from_key(&type_id_args(&input, output_index))
+}
+
+/// The did:plc created by the encoded genesis operation `op`. Its signature is
+/// not checked.
+pub fn did_plc(op: &[u8]) -> Result<String, Error> {
+    let cid = Operation::from_slice(op)?.generate_cid()?;
+    // "b" then the base32 of the CID prefix and the SHA-256 of the operation
+    let raw = base32::decode(Alphabet::Rfc4648Lower { padding: false }, &cid[1..])
+        .expect("a CID generated in base32");
+    Ok(format!(
+        "did:plc:{}",
+        base32::encode(Alphabet::Rfc4648Lower { padding: false }, &raw[4..19])
+    ))
+}
@@ -0,0 +1,46 @@
+use ckb_did_plc_utils::error::Error as UtilsError;
+use did_ckb_verifier::{Inspection, Step, StepKind};
+use serde_json::{Value as JsonValue, json};
+
+fn error_to_json(error: Option<&UtilsError>) -> JsonValue {
+    match error {
+        Some(e) => json!({ "name": format!("{:?}", e), "code": e.error_code() }),
+        None => JsonValue::Null,
+    }
+}
+
+fn step_to_json(step: &Step) -> JsonValue {
+    json!({
+        "index": step.index,
+        "kind": match step.kind {
+            StepKind::Genesis => "genesis",
+            StepKind::Operation => "operation",
+            StepKind::Authorization => "authorization",
+        },
+        "cid": step.cid,
+        "prev": step.prev,
+        "expectedPrev": step.expected_prev,
+        "did": step.did,
+        "rotationKeyIndex": step.rotation_key_index,
+        "rotationKey": step.rotation_key(),
+        "rotationKeys": step.rotation_keys,
+        "verifyingKeyIndices": step.verifying_key_indices,
+        "error": error_to_json(step.error.as_ref()),
+    })
+}
+
+/// Renders an inspection as JSON: `valid`, the `error` the script returns and
+/// every step up to the failing one.
+pub fn to_json(inspection: &Inspection) -> JsonValue {
+    json!({
+        "did": inspection.did,
+        "valid": inspection.error().is_none(),
+        "error": error_to_json(inspection.error()),
+        "failedStep": inspection.failure().map(|step| step.index),
+        "steps": inspection
+            .steps
+            .iter()
+            .map(step_to_json)
+            .collect::<Vec<_>>(),
+    })
+}
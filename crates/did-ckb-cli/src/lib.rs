@@ -0,0 +1,20 @@
+//! Command-line tool for did:ckb operators.
+//!
+//! - [`data`]: decodes and encodes did:ckb cell data.
+//! - [`identifiers`]: derives did:ckb and did:plc identifiers.
+//! - [`inspect`]: renders the step-by-step diagnosis of an authorization.
+//! - [`replay`]: replays transactions against two did-ckb-ts binaries.
+//! - [`reproducible`]: checks a reproducible build against a deployment.
+//! - [`resolve`]: renders the DID document of a verified did:plc history.
+//! - [`tx`]: builds unsigned mint, update and burn transactions in the
+//!   transaction file format of `ckb-cli tx`.
+//! - [`web`]: fetches and packages the document of a did:web local ID.
+pub mod data;
+pub mod error;
+pub mod identifiers;
+pub mod inspect;
+pub mod replay;
+pub mod reproducible;
+pub mod resolve;
+pub mod tx;
+pub mod web;
@@ -0,0 +1,494 @@
+use ckb_did_plc_utils::local_id::{AuthorizationKind, Registry};
+use ckb_jsonrpc_types::Script as JsonScript;
+use ckb_types::{
+    core::DepType,
+    packed::{self, CellDep, Script},
+    prelude::*,
+};
+use clap::{Args, Parser, Subcommand};
+use did_ckb_cli::{
+    data::{self, parse_hex},
+    error::Error,
+    identifiers::{self, parse_out_point},
+    inspect, replay, reproducible, resolve,
+    tx::{self, Cell, Deployment},
+    web,
+};
+use did_ckb_molecules::types::{DidCkbData, KeyAuthorization, PlcAuthorization};
+use serde_json::Value as JsonValue;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tools for did:ckb operators. Transactions are printed in the transaction
+/// file format of `ckb-cli tx`, to sign with `ckb-cli tx sign-inputs`.
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    #[command(flatten)]
+    Json(Command),
+    #[command(flatten)]
+    Report(ReportCommand),
+}
+
+/// The commands printing JSON
+#[derive(Subcommand)]
+enum Command {
+    /// Decode the `DidCkbData` of a cell and print its document as JSON
+    Decode {
+        /// Cell data in hex
+        data: String,
+        /// The data is a bare CBOR document
+        #[arg(long)]
+        document: bool,
+    },
+    /// Verify a did:plc authorization offline against a transaction hash
+    Verify {
+        /// The `WitnessArgs` at index 0 of the minted did:ckb, in hex
+        #[arg(long)]
+        witness: String,
+        #[arg(long)]
+        tx_hash: String,
+        /// The did:plc of the minted cell
+        #[arg(long)]
+        local_id: String,
+    },
+    /// Walk a did:plc authorization step by step and report where it fails
+    Inspect {
+        /// The `WitnessArgs` at index 0 of the minted did:ckb, in hex
+        #[arg(long)]
+        witness: String,
+        #[arg(long)]
+        tx_hash: String,
+        /// The did:plc of the minted cell
+        #[arg(long)]
+        local_id: String,
+    },
+    /// Verify a did:plc authorization offline and print the DID document its
+    /// history resolves to
+    Resolve {
+        /// The `WitnessArgs` at index 0 of the minted did:ckb, in hex
+        #[arg(long)]
+        witness: String,
+        #[arg(long)]
+        tx_hash: String,
+        /// The did:plc of the minted cell
+        #[arg(long)]
+        local_id: String,
+    },
+    /// Derive the did:ckb minted by a transaction
+    DeriveCkb {
+        /// The out point spent by the first input, as `<tx hash>:<index>`
+        #[arg(long)]
+        input: String,
+        /// Index of the did:ckb output
+        #[arg(long, default_value_t = 0)]
+        output_index: u64,
+    },
+    /// Derive the did:plc created by a genesis operation
+    DerivePlc {
+        /// The DAG-CBOR genesis operation
+        genesis: PathBuf,
+    },
+    /// Build an unsigned mint of a did:ckb at output 0
+    Mint {
+        #[command(flatten)]
+        tx: TxArgs,
+        #[command(flatten)]
+        data: DataArgs,
+        /// Cells paying for the did:ckb, the first one derives it
+        #[arg(long, required = true)]
+        input: Vec<String>,
+    },
+    /// Build an unsigned update of a did:ckb cell
+    Update {
+        #[command(flatten)]
+        tx: TxArgs,
+        #[command(flatten)]
+        data: DataArgs,
+        /// The current did:ckb cell
+        #[arg(long)]
+        input: String,
+        #[arg(long)]
+        did: String,
+        /// Other cells paying the fee
+        #[arg(long)]
+        fee_input: Vec<String>,
+    },
+    /// Build an unsigned burn of a did:ckb cell
+    Burn {
+        #[command(flatten)]
+        tx: TxArgs,
+        /// The did:ckb cell
+        #[arg(long)]
+        input: String,
+    },
+    /// Place the local ID authorization of a mint, or of an update to a
+    /// document with a `localIdBinding`, in its witness: a did:plc history and
+    /// signature, a did:key signature alone, or a did:web document and the
+    /// signature of one of its verification methods
+    Authorize {
+        /// The transaction file of the mint or update
+        #[arg(long)]
+        tx: PathBuf,
+        /// DAG-CBOR operations from genesis to the latest, in order, for a
+        /// did:plc
+        #[arg(long)]
+        history: Vec<PathBuf>,
+        /// Comma-separated rotation key indices, one per operation and one for
+        /// the signature, for a did:plc
+        #[arg(long, value_delimiter = ',')]
+        rotation_key_indices: Vec<u8>,
+        /// The `id` of the verification method that signed, for a did:web
+        #[arg(long)]
+        verification_method: Option<String>,
+        /// The DID document of the did:web, fetched from its domain when not
+        /// given
+        #[arg(long)]
+        did_document: Option<PathBuf>,
+        /// Fetch the did:web document from this origin instead of
+        /// `https://<domain>`
+        #[arg(long)]
+        origin: Option<String>,
+        /// The signature of the transaction hash, in hex
+        #[arg(long)]
+        sig: String,
+    },
+}
+
+/// The commands printing a Markdown report, failing when it doesn't pass
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Check a reproducible build of did-ckb-ts against a deployment and print
+    /// a Markdown report for multisig signers
+    VerifyBuild {
+        /// Network of the deployment, a directory of `deployment`
+        #[arg(long, default_value = "mainnet")]
+        network: String,
+        /// Repository root holding `build`, `deployment`, `checksums.txt` and
+        /// `README.md`
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+        /// The binary to check instead of `build/release/did-ckb-ts`
+        #[arg(long)]
+        binary: Option<PathBuf>,
+        /// Who signs off the report, e.g. a name and CKB address
+        #[arg(long)]
+        signer: Option<String>,
+    },
+    /// Replay exported did:ckb transactions against the deployed and a new
+    /// did-ckb-ts, and report those whose verdict changes
+    Replay {
+        /// Directory of transactions in the format of `ckb-cli mock-tx dump`
+        #[arg(long)]
+        corpus: PathBuf,
+        /// Deployment directory giving the `code_hash` of did:ckb type scripts
+        #[arg(long, default_value = "deployment/mainnet")]
+        deployment: PathBuf,
+        /// The binary to replay against instead of the one in the cell deps
+        #[arg(long)]
+        old: Option<PathBuf>,
+        /// The new binary
+        #[arg(long)]
+        new: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct TxArgs {
+    /// Deployment directory the did-ckb-ts cell is read from
+    #[arg(long, default_value = "deployment/mainnet")]
+    deployment: PathBuf,
+    /// Lock of the output, as a JSON script
+    #[arg(long)]
+    lock: String,
+    /// Capacity of the output in shannons
+    #[arg(long)]
+    capacity: u64,
+    /// Capacity in shannons returned to `--lock` in another output
+    #[arg(long)]
+    change: Option<u64>,
+    /// Cell dep of the input locks, as `<tx hash>:<index>`
+    #[arg(long)]
+    cell_dep: Vec<String>,
+    /// Dep group of the input locks, as `<tx hash>:<index>`
+    #[arg(long)]
+    dep_group: Vec<String>,
+}
+
+#[derive(Args)]
+struct DataArgs {
+    /// The CBOR document
+    #[arg(long)]
+    document: PathBuf,
+    /// The did:plc bound to the did:ckb
+    #[arg(long)]
+    local_id: Option<String>,
+}
+
+impl TxArgs {
+    fn lock(&self) -> Result<Script, Error> {
+        let lock: JsonScript = serde_json::from_str(&self.lock)?;
+        Ok(lock.into())
+    }
+
+    fn cell(&self, data: Vec<u8>) -> Result<Cell, Error> {
+        Ok(Cell {
+            lock: self.lock()?,
+            capacity: self.capacity,
+            data: data.into(),
+        })
+    }
+
+    fn finish(&self, tx: ckb_types::core::TransactionView) -> Result<JsonValue, Error> {
+        let mut cell_deps = vec![];
+        for (out_points, dep_type) in [
+            (&self.cell_dep, DepType::Code),
+            (&self.dep_group, DepType::DepGroup),
+        ] {
+            for out_point in out_points {
+                cell_deps.push(
+                    CellDep::new_builder()
+                        .out_point(parse_out_point(out_point)?)
+                        .dep_type(dep_type.into())
+                        .build(),
+                );
+            }
+        }
+        let mut tx = tx::add_cell_deps(tx, &cell_deps);
+        if let Some(change) = self.change {
+            tx = tx::add_change(
+                tx,
+                &Cell {
+                    lock: self.lock()?,
+                    capacity: change,
+                    data: Default::default(),
+                },
+            )?;
+        }
+        Ok(tx::to_ckb_cli_json(&tx))
+    }
+}
+
+impl DataArgs {
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        data::encode(&fs::read(&self.document)?, self.local_id.as_deref())
+    }
+}
+
+fn parse_out_points(out_points: &[String]) -> Result<Vec<packed::OutPoint>, Error> {
+    out_points.iter().map(|s| parse_out_point(s)).collect()
+}
+
+fn parse_tx_hash(tx_hash: &str) -> Result<[u8; 32], Error> {
+    parse_hex(tx_hash)?
+        .try_into()
+        .map_err(|_| Error::InvalidHex(tx_hash.to_owned()))
+}
+
+fn run(command: Command) -> Result<JsonValue, Error> {
+    Ok(match command {
+        Command::Decode { data, document } => {
+            let data = parse_hex(&data)?;
+            match document {
+                true => data::decode_document(&data)?,
+                false => data::decode(&data)?,
+            }
+        }
+        Command::Verify {
+            witness,
+            tx_hash,
+            local_id,
+        } => {
+            let tx_hash = parse_tx_hash(&tx_hash)?;
+            did_ckb_verifier::verify_authorization(&parse_hex(&witness)?, &local_id, &tx_hash)?;
+            JsonValue::Bool(true)
+        }
+        Command::Inspect {
+            witness,
+            tx_hash,
+            local_id,
+        } => {
+            let tx_hash = parse_tx_hash(&tx_hash)?;
+            let inspection = did_ckb_verifier::inspect_authorization(
+                &parse_hex(&witness)?,
+                &local_id,
+                &tx_hash,
+            )?;
+            inspect::to_json(&inspection)
+        }
+        Command::Resolve {
+            witness,
+            tx_hash,
+            local_id,
+        } => {
+            let tx_hash = parse_tx_hash(&tx_hash)?;
+            let state = did_ckb_verifier::resolve_authorization(
+                &parse_hex(&witness)?,
+                &local_id,
+                &tx_hash,
+            )?;
+            resolve::to_json(&state)
+        }
+        Command::DeriveCkb {
+            input,
+            output_index,
+        } => identifiers::did_ckb(&parse_out_point(&input)?, output_index).into(),
+        Command::DerivePlc { genesis } => identifiers::did_plc(&fs::read(genesis)?)?.into(),
+        Command::Mint { tx, data, input } => {
+            let deployment = Deployment::load(&tx.deployment)?;
+            let cell = tx.cell(data.encode()?)?;
+            tx.finish(tx::mint(&deployment, &parse_out_points(&input)?, &cell)?)?
+        }
+        Command::Update {
+            tx,
+            data,
+            input,
+            did,
+            fee_input,
+        } => {
+            let deployment = Deployment::load(&tx.deployment)?;
+            let cell = tx.cell(data.encode()?)?;
+            let update = tx::update(&deployment, &parse_out_point(&input)?, &did, &cell)?;
+            tx.finish(tx::add_inputs(update, &parse_out_points(&fee_input)?))?
+        }
+        Command::Burn { tx, input } => {
+            let deployment = Deployment::load(&tx.deployment)?;
+            let cell = tx.cell(vec![])?;
+            tx.finish(tx::burn(&deployment, &parse_out_point(&input)?, &cell)?)?
+        }
+        Command::Authorize {
+            tx: path,
+            history,
+            rotation_key_indices,
+            verification_method,
+            did_document,
+            origin,
+            sig,
+        } => {
+            let tx = tx::from_ckb_cli_json(&serde_json::from_slice(&fs::read(&path)?)?)?;
+            let local_id = output_local_id(&tx)?;
+            let sig = parse_hex(&sig)?;
+            let tx = match Registry::default().find(local_id.as_bytes())?.kind() {
+                AuthorizationKind::Plc => {
+                    let authorization = PlcAuthorization {
+                        history: history.iter().map(fs::read).collect::<Result<_, _>>()?,
+                        sig,
+                        rotation_key_indices,
+                    };
+                    tx::authorize(tx, authorization)?
+                }
+                AuthorizationKind::Key => tx::authorize_key(tx, KeyAuthorization { sig })?,
+                AuthorizationKind::Web => {
+                    let verification_method = verification_method.ok_or_else(|| {
+                        Error::InvalidTransaction(
+                            "a did:web needs --verification-method".to_owned(),
+                        )
+                    })?;
+                    let document = match did_document {
+                        Some(path) => fs::read(path)?,
+                        None => web::fetch_document(&local_id, origin.as_deref())?,
+                    };
+                    let authorization =
+                        web::authorization(&local_id, document, &verification_method, sig)?;
+                    tx::authorize_web(tx, authorization)?
+                }
+            };
+            // refuse an authorization the script would reject
+            let witness = tx
+                .witnesses()
+                .get(0)
+                .ok_or_else(|| {
+                    Error::InvalidTransaction("the transaction has no witness".to_owned())
+                })?
+                .raw_data();
+            did_ckb_verifier::verify_authorization(&witness, &local_id, &tx.hash().unpack())?;
+            tx::to_ckb_cli_json(&tx)
+        }
+    })
+}
+
+// The local ID of the did:ckb minted at output 0
+fn output_local_id(tx: &ckb_types::core::TransactionView) -> Result<String, Error> {
+    let data = tx
+        .outputs_data()
+        .get(0)
+        .ok_or_else(|| Error::InvalidTransaction("the transaction has no output".to_owned()))?;
+    let DidCkbData::DidCkbDataV1(data) = DidCkbData::from_slice(&data.raw_data())?;
+    data.local_id
+        .ok_or_else(|| Error::InvalidTransaction("the did:ckb has no local ID".to_owned()))
+}
+
+// The report and whether all checks passed
+fn verify_build(
+    network: &str,
+    root: &Path,
+    binary: Option<PathBuf>,
+    signer: Option<&str>,
+) -> Result<(String, bool), Error> {
+    let mut sources = reproducible::sources(root, network);
+    if let Some(binary) = binary {
+        sources.binary = binary;
+    }
+    let report = reproducible::verify(&sources)?;
+    Ok((report.to_markdown(signer), report.passed()))
+}
+
+// The report and whether no verdict changes
+fn replay(
+    corpus: &Path,
+    deployment: &Path,
+    old: Option<PathBuf>,
+    new: &Path,
+) -> Result<(String, bool), Error> {
+    let code_hash = Deployment::load(deployment)?.code_hash;
+    let corpus = replay::load_corpus(corpus)?;
+    let old = old.map(fs::read).transpose()?.map(Into::into);
+    let new = fs::read(new)?.into();
+    let comparison = replay::compare(&corpus, &code_hash, old.as_ref(), &new);
+    Ok((comparison.to_markdown(), comparison.compatible()))
+}
+
+// The report and whether it passes
+fn report(command: ReportCommand) -> Result<(String, bool), Error> {
+    match command {
+        ReportCommand::VerifyBuild {
+            network,
+            root,
+            binary,
+            signer,
+        } => verify_build(&network, &root, binary, signer.as_deref()),
+        ReportCommand::Replay {
+            corpus,
+            deployment,
+            old,
+            new,
+        } => replay(&corpus, &deployment, old, &new),
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        CliCommand::Report(command) => report(command),
+        CliCommand::Json(command) => run(command)
+            .and_then(|output| Ok(serde_json::to_string_pretty(&output)?))
+            .map(|output| (output, true)),
+    };
+    match result {
+        Ok((output, passed)) => {
+            println!("{}", output);
+            if !passed {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
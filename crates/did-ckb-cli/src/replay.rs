@@ -0,0 +1,257 @@
+use crate::error::Error;
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction, Resource};
+use ckb_script::{ScriptError, ScriptGroupType, TransactionScriptsVerifier, TxVerifyEnv};
+use ckb_types::{
+    H256,
+    bytes::Bytes,
+    core::{
+        HeaderBuilder, ScriptHashType,
+        cell::resolve_transaction,
+        hardfork::{CKB2021, CKB2023, HardForks},
+    },
+    packed::Byte32,
+    prelude::*,
+};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The cycle limit of a block, above anything a single transaction may use.
+pub const MAX_CYCLES: u64 = 3_500_000_000;
+
+/// How the did:ckb type scripts of a transaction end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every did:ckb script group passed, with the cycles consumed
+    Pass(u64),
+    /// The exit code of the first failing group
+    Fail(i8),
+    /// The transaction could not be run to an exit code, e.g. out of cycles or
+    /// without a did:ckb type script
+    Error(String),
+}
+
+impl Verdict {
+    /// Whether `other` ends the same way, cycles aside.
+    pub fn same_as(&self, other: &Verdict) -> bool {
+        match (self, other) {
+            (Verdict::Pass(_), Verdict::Pass(_)) => true,
+            (Verdict::Fail(a), Verdict::Fail(b)) => a == b,
+            (Verdict::Error(_), Verdict::Error(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl core::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Verdict::Pass(cycles) => write!(f, "pass ({} cycles)", cycles),
+            Verdict::Fail(code) => write!(f, "error code {}", code),
+            Verdict::Error(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A transaction of the corpus, in the mock transaction format of
+/// `ckb-cli mock-tx dump` and `ckb-debugger`.
+#[derive(Clone)]
+pub struct Transaction {
+    pub path: PathBuf,
+    pub mock_tx: MockTransaction,
+}
+
+impl Transaction {
+    pub fn hash(&self) -> H256 {
+        self.mock_tx.core_transaction().hash().unpack()
+    }
+}
+
+/// One transaction replayed against both binaries.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub path: PathBuf,
+    pub tx_hash: H256,
+    pub old: Verdict,
+    pub new: Verdict,
+}
+
+impl Replay {
+    pub fn changed(&self) -> bool {
+        !self.old.same_as(&self.new)
+    }
+}
+
+/// The result of [`compare`].
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    pub replays: Vec<Replay>,
+}
+
+/// Loads the `*.json` mock transactions of `dir`, in file name order.
+pub fn load_corpus(dir: &Path) -> Result<Vec<Transaction>, Error> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let repr: ReprMockTransaction = serde_json::from_slice(&fs::read(&path)?)?;
+            Ok(Transaction {
+                path,
+                mock_tx: repr.into(),
+            })
+        })
+        .collect()
+}
+
+// Replaces the data of the cell deps whose type script hash is `code_hash`,
+// the cells did:ckb type scripts load their code from
+fn with_binary(
+    mock_tx: &MockTransaction,
+    code_hash: &Byte32,
+    binary: &Bytes,
+) -> Option<MockTransaction> {
+    let mut mock_tx = mock_tx.clone();
+    let mut replaced = false;
+    for dep in mock_tx.mock_info.cell_deps.iter_mut() {
+        if dep
+            .output
+            .type_()
+            .to_opt()
+            .is_some_and(|type_| &type_.calc_script_hash() == code_hash)
+        {
+            dep.data = binary.clone();
+            replaced = true;
+        }
+    }
+    replaced.then_some(mock_tx)
+}
+
+/// Runs the did:ckb type scripts of `mock_tx`, with `binary` in place of the
+/// deployed did-ckb-ts when given. Lock scripts and other type scripts are
+/// left out: an upgrade of did-ckb-ts does not change their outcome.
+pub fn replay(mock_tx: &MockTransaction, code_hash: &Byte32, binary: Option<&Bytes>) -> Verdict {
+    let mock_tx = match binary {
+        Some(binary) => match with_binary(mock_tx, code_hash, binary) {
+            Some(mock_tx) => mock_tx,
+            None => return Verdict::Error("no did-ckb-ts cell dep".to_owned()),
+        },
+        None => mock_tx.clone(),
+    };
+    let resource = match Resource::from_mock_tx(&mock_tx) {
+        Ok(resource) => resource,
+        Err(e) => return Verdict::Error(e),
+    };
+    let rtx = match resolve_transaction(
+        mock_tx.core_transaction(),
+        &mut HashSet::new(),
+        &resource,
+        &resource,
+    ) {
+        Ok(rtx) => rtx,
+        Err(e) => return Verdict::Error(e.to_string()),
+    };
+    // all hard forks active, as on mainnet today
+    let consensus = ConsensusBuilder::default()
+        .hardfork_switch(HardForks {
+            ckb2021: CKB2021::new_dev_default(),
+            ckb2023: CKB2023::new_dev_default(),
+        })
+        .build();
+    let tip = HeaderBuilder::default().number(0.pack()).build();
+    let verifier = TransactionScriptsVerifier::new(
+        Arc::new(rtx),
+        resource,
+        Arc::new(consensus),
+        Arc::new(TxVerifyEnv::new_submit(&tip)),
+    );
+    let script_hashes: Vec<Byte32> = verifier
+        .groups_with_type()
+        .filter(|(group_type, _, group)| {
+            *group_type == ScriptGroupType::Type
+                && &group.script.code_hash() == code_hash
+                && group.script.hash_type() == ScriptHashType::Type.into()
+        })
+        .map(|(_, hash, _)| hash.clone())
+        .collect();
+    if script_hashes.is_empty() {
+        return Verdict::Error("no did:ckb type script".to_owned());
+    }
+    let mut cycles = 0;
+    for script_hash in script_hashes {
+        match verifier.verify_single(ScriptGroupType::Type, &script_hash, MAX_CYCLES) {
+            Ok(used) => cycles += used,
+            Err(ScriptError::ValidationFailure(_, code)) => return Verdict::Fail(code),
+            Err(e) => return Verdict::Error(e.to_string()),
+        }
+    }
+    Verdict::Pass(cycles)
+}
+
+/// Replays every transaction of `corpus` against the `old` and `new`
+/// binaries. `old` defaults to the binary in the cell deps of each
+/// transaction, the one it was committed with.
+pub fn compare(
+    corpus: &[Transaction],
+    code_hash: &Byte32,
+    old: Option<&Bytes>,
+    new: &Bytes,
+) -> Comparison {
+    let replays = corpus
+        .iter()
+        .map(|tx| Replay {
+            path: tx.path.clone(),
+            tx_hash: tx.hash(),
+            old: replay(&tx.mock_tx, code_hash, old),
+            new: replay(&tx.mock_tx, code_hash, Some(new)),
+        })
+        .collect();
+    Comparison { replays }
+}
+
+impl Comparison {
+    pub fn changed(&self) -> impl Iterator<Item = &Replay> {
+        self.replays.iter().filter(|replay| replay.changed())
+    }
+
+    /// Whether every transaction ends the same way with the new binary.
+    pub fn compatible(&self) -> bool {
+        self.changed().next().is_none()
+    }
+
+    /// The report in Markdown, listing the transactions whose verdict changes.
+    pub fn to_markdown(&self) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "# did-ckb-ts upgrade replay\n");
+        let _ = writeln!(
+            report,
+            "{} transactions replayed, {} changed.",
+            self.replays.len(),
+            self.changed().count()
+        );
+        if !self.compatible() {
+            let _ = writeln!(report, "\n| Transaction | File | Old | New |");
+            let _ = writeln!(report, "| ----------- | ---- | --- | --- |");
+            for replay in self.changed() {
+                let _ = writeln!(
+                    report,
+                    "| `{:#x}` | {} | {} | {} |",
+                    replay.tx_hash,
+                    replay.path.display(),
+                    replay.old,
+                    replay.new
+                );
+            }
+        }
+        report
+    }
+}
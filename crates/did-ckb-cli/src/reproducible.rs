@@ -0,0 +1,344 @@
+use crate::error::Error;
+use ckb_hash::blake2b_256;
+use ckb_jsonrpc_types::{
+    Script as JsonScript, ScriptHashType as JsonScriptHashType, Transaction as JsonTransaction,
+};
+use ckb_types::{H256, packed, prelude::*};
+use did_ckb_indexer::deployment::{self, CellRecipe};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CELL_NAME: &str = "did-ckb-ts";
+// The path of the binary in `checksums.txt`
+const CHECKSUM_PATH: &str = "build/release/did-ckb-ts";
+// https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0024-ckb-genesis-script-list/0024-ckb-genesis-script-list.md#type-id
+const TYPE_ID_CODE_HASH: &str = "00000000000000000000000000000000000000000000000000545950455f4944";
+
+/// The files a deployment is checked against.
+#[derive(Debug, Clone)]
+pub struct Sources {
+    /// The reproducibly built binary
+    pub binary: PathBuf,
+    /// A deployment directory such as `deployment/mainnet`
+    pub deployment_dir: PathBuf,
+    pub checksums: PathBuf,
+    /// The README with the `code_hash` tables
+    pub readme: PathBuf,
+}
+
+/// One comparison of the report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Check {
+    fn new(name: &str, expected: impl ToString, actual: impl ToString) -> Self {
+        Self {
+            name: name.to_owned(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// The result of [`verify`], to attach to a multisig review.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub network: String,
+    pub sha256: String,
+    pub data_hash: H256,
+    /// Hash of the deployment transaction in `info.json`, the one signers sign
+    pub tx_hash: Option<H256>,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Deserialize)]
+struct DeploymentToml {
+    cells: Vec<CellToml>,
+    lock: JsonScript,
+}
+
+#[derive(Deserialize)]
+struct CellToml {
+    name: String,
+    enable_type_id: bool,
+}
+
+fn h(hash: &H256) -> String {
+    format!("{:#x}", hash)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidDeployment(message)
+}
+
+/// The SHA-256 of `build/release/did-ckb-ts` in a `checksums.txt`.
+pub fn checksum(checksums: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?;
+        (fields.next()? == CHECKSUM_PATH).then(|| hash.to_owned())
+    })
+}
+
+/// The value of `parameter` in the table under `### <network>`, with the
+/// network capitalized as in the README.
+pub fn readme_parameter(readme: &str, network: &str, parameter: &str) -> Option<String> {
+    let heading = format!("### {}", capitalize(network));
+    let section = readme.split_once(&format!("{}\n", heading))?.1;
+    section
+        .lines()
+        .take_while(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+            (cells.get(1)? == &format!("`{}`", parameter))
+                .then(|| cells.get(2).map(|value| value.trim_matches('`').to_owned()))
+                .flatten()
+        })
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// The type ID args of the output at `index` of a transaction creating it
+fn type_id_args(tx: &packed::Transaction, index: u64) -> Option<[u8; 32]> {
+    let first_input = tx.raw().inputs().get(0)?;
+    let mut data = first_input.as_slice().to_vec();
+    data.extend_from_slice(&index.to_le_bytes());
+    Some(blake2b_256(data))
+}
+
+// Checks the deployment transaction of `info.json` against the binary and the
+// latest recipe
+fn check_info(
+    checks: &mut Vec<Check>,
+    info: &JsonValue,
+    deployment: &DeploymentToml,
+    recipe: &CellRecipe,
+    data_hash: &H256,
+) -> Result<H256, Error> {
+    let new_recipe: CellRecipe = info["new_recipe"]["cell_recipes"]
+        .as_array()
+        .and_then(|recipes| recipes.iter().find(|r| r["name"] == CELL_NAME))
+        .map(|r| serde_json::from_value(r.clone()))
+        .transpose()?
+        .ok_or_else(|| invalid(format!("no {} recipe in info.json", CELL_NAME)))?;
+    checks.push(Check::new(
+        "info.json recipe is the latest migration",
+        h(&recipe.tx_hash),
+        h(&new_recipe.tx_hash),
+    ));
+    checks.push(Check::new(
+        "data_hash of the info.json recipe",
+        h(data_hash),
+        h(&new_recipe.data_hash),
+    ));
+
+    let tx: JsonTransaction = serde_json::from_value(info["cell_tx"].clone())?;
+    let tx: packed::Transaction = tx.into();
+    let tx_hash: H256 = tx.calc_tx_hash().unpack();
+    let index = new_recipe.index as usize;
+    let output = tx
+        .raw()
+        .outputs()
+        .get(index)
+        .ok_or_else(|| invalid(format!("no output {} in info.json", index)))?;
+    let data = tx
+        .raw()
+        .outputs_data()
+        .get(index)
+        .ok_or_else(|| invalid(format!("no output data {} in info.json", index)))?;
+    checks.push(Check::new(
+        "data_hash of the info.json cell",
+        h(data_hash),
+        h(&H256(blake2b_256(data.raw_data()))),
+    ));
+
+    let lock: packed::Script = deployment.lock.clone().into();
+    checks.push(Check::new(
+        "lock of the info.json cell",
+        h(&lock.calc_script_hash().unpack()),
+        h(&output.lock().calc_script_hash().unpack()),
+    ));
+
+    let type_ = output
+        .type_()
+        .to_opt()
+        .ok_or_else(|| invalid("the info.json cell has no type script".to_owned()))?;
+    let json_type: JsonScript = type_.clone().into();
+    checks.push(Check::new(
+        "type script is type ID",
+        format!("0x{} {:?}", TYPE_ID_CODE_HASH, JsonScriptHashType::Type),
+        format!("{} {:?}", h(&json_type.code_hash), json_type.hash_type),
+    ));
+    checks.push(Check::new(
+        "type ID of the info.json cell",
+        recipe.type_id.as_ref().map(h).unwrap_or_default(),
+        h(&type_.calc_script_hash().unpack()),
+    ));
+    // an upgrade keeps the args of the cell it replaces
+    if info["last_recipe"].is_null() {
+        let args = type_id_args(&tx, index as u64)
+            .ok_or_else(|| invalid("the info.json transaction has no input".to_owned()))?;
+        checks.push(Check::new(
+            "type ID args of the first deployment",
+            hex(&args),
+            hex(&type_.args().raw_data()),
+        ));
+    }
+    Ok(tx_hash)
+}
+
+/// Checks that the binary is the one recorded in `checksums.txt`, in the
+/// deployment's latest migration and `info.json`, and that the README lists
+/// the deployed `code_hash` and `tx_hash`.
+pub fn verify(sources: &Sources) -> Result<Report, Error> {
+    let network = sources
+        .deployment_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_owned();
+    let binary = fs::read(&sources.binary)?;
+    let sha256 = hex::encode(Sha256::digest(&binary));
+    let data_hash = H256(blake2b_256(&binary));
+    let mut checks = vec![];
+
+    let checksums = fs::read_to_string(&sources.checksums)?;
+    checks.push(Check::new(
+        "sha256 in checksums.txt",
+        checksum(&checksums).unwrap_or_default(),
+        &sha256,
+    ));
+
+    let deployment: DeploymentToml = toml::from_str(&fs::read_to_string(
+        sources.deployment_dir.join("deployment.toml"),
+    )?)
+    .map_err(|e| invalid(format!("deployment.toml: {}", e)))?;
+    let cell = deployment
+        .cells
+        .iter()
+        .find(|cell| cell.name == CELL_NAME)
+        .ok_or_else(|| invalid(format!("no {} cell in deployment.toml", CELL_NAME)))?;
+    checks.push(Check::new(
+        "enable_type_id in deployment.toml",
+        true,
+        cell.enable_type_id,
+    ));
+
+    let recipe = deployment::load_recipe(&sources.deployment_dir)?;
+    checks.push(Check::new(
+        "data_hash of the latest migration",
+        h(&data_hash),
+        h(&recipe.data_hash),
+    ));
+
+    let info_path = sources.deployment_dir.join("info.json");
+    let tx_hash = if info_path.exists() {
+        let info: JsonValue = serde_json::from_slice(&fs::read(&info_path)?)?;
+        Some(check_info(
+            &mut checks,
+            &info,
+            &deployment,
+            &recipe,
+            &data_hash,
+        )?)
+    } else {
+        None
+    };
+
+    let readme = fs::read_to_string(&sources.readme)?;
+    let readme_value =
+        |parameter| readme_parameter(&readme, &network, parameter).unwrap_or_default();
+    checks.push(Check::new(
+        "code_hash in the README",
+        recipe.type_id.as_ref().map(h).unwrap_or_default(),
+        readme_value("code_hash"),
+    ));
+    checks.push(Check::new(
+        "tx_hash in the README",
+        h(&recipe.tx_hash),
+        readme_value("tx_hash"),
+    ));
+
+    Ok(Report {
+        network,
+        sha256,
+        data_hash,
+        tx_hash,
+        checks,
+    })
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(Check::passed)
+    }
+
+    /// The report in Markdown, signed off by `signer` when given, e.g. a name
+    /// and CKB address from the multisig table.
+    pub fn to_markdown(&self, signer: Option<&str>) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "# did-ckb-ts {} build verification\n", self.network);
+        let _ = writeln!(report, "- sha256: `{}`", self.sha256);
+        let _ = writeln!(report, "- data_hash: `{:#x}`", self.data_hash);
+        if let Some(tx_hash) = &self.tx_hash {
+            let _ = writeln!(report, "- deployment tx_hash: `{:#x}`", tx_hash);
+        }
+        let _ = writeln!(report, "\n| Check | Result | Expected | Actual |");
+        let _ = writeln!(report, "| ----- | ------ | -------- | ------ |");
+        for check in &self.checks {
+            let _ = writeln!(
+                report,
+                "| {} | {} | `{}` | `{}` |",
+                check.name,
+                if check.passed() { "pass" } else { "FAIL" },
+                check.expected,
+                check.actual
+            );
+        }
+        let _ = writeln!(
+            report,
+            "\n{}",
+            if self.passed() {
+                "All checks passed."
+            } else {
+                "Verification FAILED."
+            }
+        );
+        if let Some(signer) = signer {
+            let _ = writeln!(report, "\nSigned-off-by: {}", signer);
+        }
+        report
+    }
+}
+
+/// The sources of the repository at `root` for `network`.
+pub fn sources(root: &Path, network: &str) -> Sources {
+    Sources {
+        binary: root.join(CHECKSUM_PATH),
+        deployment_dir: root.join("deployment").join(network),
+        checksums: root.join("checksums.txt"),
+        readme: root.join("README.md"),
+    }
+}
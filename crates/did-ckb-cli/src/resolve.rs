@@ -0,0 +1,50 @@
+use did_ckb_verifier::PlcState;
+use serde_json::{Value as JsonValue, json};
+
+/// Renders the DID document of a resolved did:plc the way the PLC directory
+/// serves it, with `Multikey` verification methods.
+pub fn to_document(state: &PlcState) -> JsonValue {
+    let verification_methods: Vec<JsonValue> = state
+        .verification_methods
+        .iter()
+        .map(|(id, key)| {
+            json!({
+                "id": format!("{}#{}", state.did, id),
+                "type": "Multikey",
+                "controller": state.did,
+                "publicKeyMultibase": key.strip_prefix("did:key:").unwrap_or(key),
+            })
+        })
+        .collect();
+    let services: Vec<JsonValue> = state
+        .services
+        .iter()
+        .map(|(id, service)| {
+            json!({
+                "id": format!("#{}", id),
+                "type": service.r#type,
+                "serviceEndpoint": service.endpoint,
+            })
+        })
+        .collect();
+    json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+        ],
+        "id": state.did,
+        "alsoKnownAs": state.also_known_as,
+        "verificationMethod": verification_methods,
+        "service": services,
+    })
+}
+
+/// Renders the state as JSON: the DID document, and the rotation keys and head
+/// CID the next operation builds on.
+pub fn to_json(state: &PlcState) -> JsonValue {
+    json!({
+        "document": to_document(state),
+        "rotationKeys": state.rotation_keys,
+        "head": state.head,
+    })
+}
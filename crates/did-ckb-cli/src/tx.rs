@@ -0,0 +1,236 @@
+use crate::error::Error;
+use crate::identifiers::type_id_args;
+use ckb_jsonrpc_types::Transaction as JsonTransaction;
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use did_ckb_indexer::{deployment, resolver::parse_did};
+use did_ckb_molecules::types::{
+    DidCkbKeyWitness, DidCkbWebWitness, DidCkbWitness, KeyAuthorization, PlcAuthorization,
+    WebAuthorization, WitnessArgs,
+};
+use serde_json::{Value as JsonValue, json};
+use std::path::Path;
+
+/// The deployed did-ckb-ts script.
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    // The `code_hash` of did:ckb type scripts, with `hash_type` = `type`
+    pub code_hash: ckb_types::packed::Byte32,
+    pub cell_dep: CellDep,
+}
+
+impl Deployment {
+    /// Loads the latest deployment from a directory such as
+    /// `deployment/mainnet`.
+    pub fn load(deployment_dir: &Path) -> Result<Self, Error> {
+        let recipe = deployment::load_recipe(deployment_dir)?;
+        let code_hash = deployment::load_code_hash(deployment_dir)?;
+        Ok(Self {
+            code_hash: code_hash.pack(),
+            cell_dep: CellDep::new_builder()
+                .out_point(OutPoint::new(recipe.tx_hash.pack(), recipe.index))
+                .dep_type(DepType::Code.into())
+                .build(),
+        })
+    }
+
+    pub fn type_script(&self, args: &[u8]) -> Script {
+        Script::new_builder()
+            .code_hash(self.code_hash.clone())
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::copy_from_slice(args).pack())
+            .build()
+    }
+}
+
+/// An output to create, owned by `lock`.
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub lock: Script,
+    pub capacity: u64,
+    pub data: Bytes,
+}
+
+impl Cell {
+    fn output(&self, type_: Option<Script>) -> Result<CellOutput, Error> {
+        let output = CellOutput::new_builder()
+            .capacity(self.capacity.pack())
+            .lock(self.lock.clone())
+            .type_(type_.pack())
+            .build();
+        let occupied = Capacity::bytes(self.data.len())
+            .and_then(|data| output.occupied_capacity(data))
+            .map_err(|e| Error::InvalidTransaction(e.to_string()))?
+            .as_u64();
+        if self.capacity < occupied {
+            return Err(Error::InsufficientCapacity {
+                capacity: self.capacity,
+                occupied,
+            });
+        }
+        Ok(output)
+    }
+}
+
+fn build(
+    deployment: &Deployment,
+    inputs: &[OutPoint],
+    outputs: Vec<(CellOutput, Bytes)>,
+) -> TransactionView {
+    let mut builder = TransactionBuilder::default().cell_dep(deployment.cell_dep.clone());
+    for input in inputs {
+        builder = builder.input(CellInput::new(input.clone(), 0));
+    }
+    for (output, data) in outputs {
+        builder = builder.output(output).output_data(data.pack());
+    }
+    // one witness per input, for the signatures of their locks
+    builder
+        .witnesses(vec![Bytes::new().pack(); inputs.len()])
+        .build()
+}
+
+/// Mints `cell` as a new did:ckb at output 0. The did:ckb is derived from the
+/// first of `inputs`, which pay for it.
+pub fn mint(
+    deployment: &Deployment,
+    inputs: &[OutPoint],
+    cell: &Cell,
+) -> Result<TransactionView, Error> {
+    let first_input = inputs
+        .first()
+        .ok_or_else(|| Error::InvalidTransaction("a mint needs an input".to_owned()))?;
+    let args = type_id_args(&CellInput::new(first_input.clone(), 0), 0);
+    let output = cell.output(Some(deployment.type_script(&args)))?;
+    Ok(build(deployment, inputs, vec![(output, cell.data.clone())]))
+}
+
+/// Updates the did:ckb cell `input` of `did` to `cell`.
+pub fn update(
+    deployment: &Deployment,
+    input: &OutPoint,
+    did: &str,
+    cell: &Cell,
+) -> Result<TransactionView, Error> {
+    let args = parse_did(did)?;
+    let output = cell.output(Some(deployment.type_script(&args)))?;
+    Ok(build(
+        deployment,
+        &[input.clone()],
+        vec![(output, cell.data.clone())],
+    ))
+}
+
+/// Burns the did:ckb cell `input`, sending its capacity to `cell`.
+pub fn burn(
+    deployment: &Deployment,
+    input: &OutPoint,
+    cell: &Cell,
+) -> Result<TransactionView, Error> {
+    let output = cell.output(None)?;
+    Ok(build(
+        deployment,
+        &[input.clone()],
+        vec![(output, cell.data.clone())],
+    ))
+}
+
+/// Adds inputs, for instance to pay the fee of an update.
+pub fn add_inputs(tx: TransactionView, inputs: &[OutPoint]) -> TransactionView {
+    let mut builder = tx.as_advanced_builder();
+    for input in inputs {
+        builder = builder
+            .input(CellInput::new(input.clone(), 0))
+            .witness(Bytes::new().pack());
+    }
+    builder.build()
+}
+
+/// Adds an output returning the change to `cell.lock`.
+pub fn add_change(tx: TransactionView, cell: &Cell) -> Result<TransactionView, Error> {
+    let output = cell.output(None)?;
+    Ok(tx
+        .as_advanced_builder()
+        .output(output)
+        .output_data(cell.data.pack())
+        .build())
+}
+
+/// Adds the cell deps of the input locks.
+pub fn add_cell_deps(tx: TransactionView, cell_deps: &[CellDep]) -> TransactionView {
+    tx.as_advanced_builder()
+        .cell_deps(cell_deps.iter().cloned())
+        .build()
+}
+
+/// Places `authorization` in `output_type` of witness 0, where the script
+/// reads the authorization of the did:ckb minted, or updated to a bound
+/// document, at output 0. The lock field is kept.
+pub fn authorize(
+    tx: TransactionView,
+    authorization: PlcAuthorization,
+) -> Result<TransactionView, Error> {
+    let witness = DidCkbWitness {
+        local_id_authorization: authorization,
+    };
+    place_authorization(tx, witness.to_vec())
+}
+
+/// Like [`authorize`], for a did:key local ID.
+pub fn authorize_key(
+    tx: TransactionView,
+    authorization: KeyAuthorization,
+) -> Result<TransactionView, Error> {
+    let witness = DidCkbKeyWitness {
+        local_id_authorization: authorization,
+    };
+    place_authorization(tx, witness.to_vec())
+}
+
+/// Like [`authorize`], for a did:web local ID, see [`crate::web`].
+pub fn authorize_web(
+    tx: TransactionView,
+    authorization: WebAuthorization,
+) -> Result<TransactionView, Error> {
+    let witness = DidCkbWebWitness {
+        local_id_authorization: authorization,
+    };
+    place_authorization(tx, witness.to_vec())
+}
+
+fn place_authorization(tx: TransactionView, witness: Vec<u8>) -> Result<TransactionView, Error> {
+    let mut witnesses: Vec<Bytes> = tx.witnesses().into_iter().map(|w| w.unpack()).collect();
+    if witnesses.is_empty() {
+        witnesses.push(Bytes::new());
+    }
+    let mut witness_args = match witnesses[0].is_empty() {
+        true => WitnessArgs::default(),
+        false => WitnessArgs::from_slice(&witnesses[0])?,
+    };
+    witness_args.output_type = Some(witness);
+    witnesses[0] = witness_args.to_vec().into();
+    Ok(tx
+        .as_advanced_builder()
+        .set_witnesses(witnesses.into_iter().map(|w| w.pack()).collect())
+        .build())
+}
+
+/// The transaction file of `ckb-cli tx`, ready for `sign-inputs`.
+pub fn to_ckb_cli_json(tx: &TransactionView) -> JsonValue {
+    json!({
+        "transaction": JsonTransaction::from(tx.data()),
+        "multisig_configs": {},
+        "signatures": {},
+    })
+}
+
+/// Reads the transaction of a `ckb-cli tx` file, or a bare transaction.
+pub fn from_ckb_cli_json(json: &JsonValue) -> Result<TransactionView, Error> {
+    let transaction = json.get("transaction").unwrap_or(json);
+    let tx: JsonTransaction = serde_json::from_value(transaction.clone())?;
+    Ok(ckb_types::packed::Transaction::from(tx).into_view())
+}
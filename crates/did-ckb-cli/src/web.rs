@@ -0,0 +1,70 @@
+use crate::error::Error;
+use ckb_did_plc_utils::web::{PREFIX, check_did_web, verification_key};
+use did_ckb_molecules::types::WebAuthorization;
+use std::io::Read;
+
+/// The largest DID document fetched, far above what fits in a witness anyway.
+pub const MAX_DOCUMENT_SIZE: u64 = 1024 * 1024;
+
+// The host, with its port, and the path of the document of `did`
+fn location(did: &str) -> Result<(String, String), Error> {
+    check_did_web(did)?;
+    let mut segments = did[PREFIX.len()..].split(':');
+    let host = segments.next().unwrap_or_default().replace("%3A", ":");
+    let path: Vec<&str> = segments.collect();
+    let path = match path.is_empty() {
+        true => ".well-known/did.json".to_owned(),
+        false => format!("{}/did.json", path.join("/")),
+    };
+    Ok((host, path))
+}
+
+/// The URL the DID document of `did` is served at: `did:web:example.com` at
+/// `https://example.com/.well-known/did.json`, `did:web:example.com:user:alice`
+/// at `https://example.com/user/alice/did.json`.
+pub fn document_url(did: &str) -> Result<String, Error> {
+    let (host, path) = location(did)?;
+    Ok(format!("https://{}/{}", host, path))
+}
+
+/// Fetches the DID document of `did` as served, from `origin` instead of
+/// `https://<host>` when given, e.g. a mirror or a local server.
+pub fn fetch_document(did: &str, origin: Option<&str>) -> Result<Vec<u8>, Error> {
+    let (host, path) = location(did)?;
+    let url = match origin {
+        Some(origin) => format!("{}/{}", origin.trim_end_matches('/'), path),
+        None => format!("https://{}/{}", host, path),
+    };
+    let mut document = vec![];
+    ureq::get(&url)
+        .call()?
+        .into_reader()
+        .take(MAX_DOCUMENT_SIZE + 1)
+        .read_to_end(&mut document)?;
+    if document.len() as u64 > MAX_DOCUMENT_SIZE {
+        return Err(Error::Fetch(format!(
+            "the document at {} is too large",
+            url
+        )));
+    }
+    Ok(document)
+}
+
+/// Packages `document` with the signature `sig` of the verification method
+/// `verification_method`, after checking that the script finds a supported
+/// key for it in the document of `did`. The signature itself is checked with
+/// the whole transaction, see `did_ckb_verifier::verify_authorization`.
+pub fn authorization(
+    did: &str,
+    document: Vec<u8>,
+    verification_method: &str,
+    sig: Vec<u8>,
+) -> Result<WebAuthorization, Error> {
+    check_did_web(did)?;
+    verification_key(did, &document, verification_method)?;
+    Ok(WebAuthorization {
+        document,
+        verification_method: verification_method.as_bytes().to_vec(),
+        sig,
+    })
+}
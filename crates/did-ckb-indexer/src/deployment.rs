@@ -0,0 +1,59 @@
+use crate::error::Error;
+use ckb_types::H256;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const CELL_NAME: &str = "did-ckb-ts";
+
+/// A deployed did-ckb-ts cell, as recorded by ckb-cli in
+/// `deployment/<network>/migrations`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CellRecipe {
+    pub name: String,
+    pub tx_hash: H256,
+    pub index: u32,
+    pub occupied_capacity: u64,
+    pub data_hash: H256,
+    // The `code_hash` of did:ckb type scripts, with `hash_type` = `type`
+    pub type_id: Option<H256>,
+}
+
+#[derive(Deserialize)]
+struct Migration {
+    cell_recipes: Vec<CellRecipe>,
+}
+
+/// Loads the latest did-ckb-ts recipe from a deployment directory such as
+/// `deployment/mainnet`. Migration files are named by their timestamp, so the
+/// last one in lexical order is the current deployment.
+pub fn load_recipe(deployment_dir: &Path) -> Result<CellRecipe, Error> {
+    let migrations = deployment_dir.join("migrations");
+    let mut files: Vec<_> = fs::read_dir(&migrations)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    let latest = files.pop().ok_or_else(|| {
+        Error::InvalidDeployment(format!("no migration found in {}", migrations.display()))
+    })?;
+    let migration: Migration = serde_json::from_slice(&fs::read(&latest)?)?;
+    migration
+        .cell_recipes
+        .into_iter()
+        .find(|recipe| recipe.name == CELL_NAME)
+        .ok_or_else(|| {
+            Error::InvalidDeployment(format!("{} not found in {}", CELL_NAME, latest.display()))
+        })
+}
+
+/// Loads the `code_hash` of did:ckb type scripts from a deployment directory.
+pub fn load_code_hash(deployment_dir: &Path) -> Result<H256, Error> {
+    load_recipe(deployment_dir)?.type_id.ok_or_else(|| {
+        Error::InvalidDeployment(format!(
+            "{} is not deployed with type ID in {}",
+            CELL_NAME,
+            deployment_dir.display()
+        ))
+    })
+}
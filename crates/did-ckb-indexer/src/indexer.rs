@@ -0,0 +1,246 @@
+use crate::error::Error;
+use crate::resolver::{decode_cell_data, did_from_key};
+use crate::source::BlockSource;
+use crate::store::{
+    BLOCKS, Binding, BlockUndo, CELLS, DidKey, HISTORY, LOCAL_IDS, META, Operation, Store, TIP_KEY,
+    Version, local_id_key,
+};
+use ckb_did_plc_utils::local_id::Registry;
+use ckb_jsonrpc_types::JsonBytes;
+use ckb_types::{
+    H256,
+    core::{BlockView, ScriptHashType},
+    packed::{self, Byte32, OutPoint, Script},
+    prelude::*,
+};
+use redb::ReadableTable;
+
+/// How many blocks can be rolled back when the chain reorganizes.
+pub const ROLLBACK_DEPTH: u64 = 256;
+
+// How a transaction touches one did:ckb
+struct Change {
+    key: DidKey,
+    consumed: bool,
+    // the created cell and its data
+    created: Option<(OutPoint, packed::Bytes)>,
+}
+
+/// Follows a chain and records the history of every did:ckb cell into a
+/// [`Store`].
+pub struct Indexer {
+    store: Store,
+    code_hash: Byte32,
+    hash_type: ScriptHashType,
+    start_number: u64,
+}
+
+impl Indexer {
+    /// `code_hash` and `hash_type` identify the deployed did-ckb-ts script.
+    /// Indexing starts at `start_number` when the store is empty, e.g. at the
+    /// block where the script was deployed.
+    pub fn new(
+        store: Store,
+        code_hash: Byte32,
+        hash_type: ScriptHashType,
+        start_number: u64,
+    ) -> Self {
+        Self {
+            store,
+            code_hash,
+            hash_type,
+            start_number,
+        }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    fn did_key(&self, script: &Script) -> Option<DidKey> {
+        if script.code_hash() != self.code_hash || script.hash_type() != self.hash_type.into() {
+            return None;
+        }
+        script.args().raw_data().get(..20)?.try_into().ok()
+    }
+
+    /// Indexes blocks from `source` until it has no more blocks. Returns the
+    /// number of the last indexed block.
+    pub fn sync<S: BlockSource + ?Sized>(&self, source: &S) -> Result<Option<u64>, Error> {
+        loop {
+            let tip = self.store.tip()?;
+            let next = tip.as_ref().map_or(self.start_number, |(n, _)| n + 1);
+            let block = match source.block(next)? {
+                Some(block) => block,
+                None => return Ok(tip.map(|(n, _)| n)),
+            };
+            if let Some((number, hash)) = tip {
+                let parent_hash: H256 = block.parent_hash().unpack();
+                if parent_hash != hash {
+                    log::warn!("chain reorganized at block {}, rolling back", number);
+                    self.store.rollback()?;
+                    continue;
+                }
+            }
+            self.apply_block(&block)?;
+        }
+    }
+
+    /// Records the did:ckb cells created and consumed by `block`, which must be
+    /// the child of the current tip.
+    pub fn apply_block(&self, block: &BlockView) -> Result<(), Error> {
+        let number = block.number();
+        let mut undo = BlockUndo {
+            hash: block.hash().unpack(),
+            ..Default::default()
+        };
+        let txn = self.store.db.begin_write()?;
+        {
+            let mut cells = txn.open_table(CELLS)?;
+            let mut history = txn.open_table(HISTORY)?;
+            let mut local_ids = txn.open_table(LOCAL_IDS)?;
+            for tx in block.transactions() {
+                let tx_hash = tx.hash();
+                let mut changes: Vec<Change> = vec![];
+                for input in tx.inputs() {
+                    let out_point = input.previous_output();
+                    let did = cells
+                        .remove(out_point.as_slice())?
+                        .map(|did| did.value().to_vec());
+                    if let Some(did) = did {
+                        let key: DidKey = did
+                            .as_slice()
+                            .try_into()
+                            .map_err(|_| Error::InvalidCellData)?;
+                        undo.consumed.push((
+                            JsonBytes::from_vec(out_point.as_slice().to_vec()),
+                            JsonBytes::from_vec(did),
+                        ));
+                        changes.push(Change {
+                            key,
+                            consumed: true,
+                            created: None,
+                        });
+                    }
+                }
+                for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                    let Some(key) = output.type_().to_opt().and_then(|s| self.did_key(&s)) else {
+                        continue;
+                    };
+                    let out_point = OutPoint::new(tx_hash.clone(), index as u32);
+                    cells.insert(out_point.as_slice(), key.as_slice())?;
+                    undo.created
+                        .push(JsonBytes::from_vec(out_point.as_slice().to_vec()));
+                    let created = Some((out_point, data.pack()));
+                    match changes.iter_mut().find(|c| c.key == key) {
+                        Some(change) => change.created = created,
+                        None => changes.push(Change {
+                            key,
+                            consumed: false,
+                            created,
+                        }),
+                    }
+                }
+                for Change {
+                    key,
+                    consumed,
+                    created,
+                } in changes
+                {
+                    let operation = match (consumed, &created) {
+                        (true, Some(_)) => Operation::Update,
+                        (true, None) => Operation::Burn,
+                        (false, _) => Operation::Mint,
+                    };
+                    let version = Version {
+                        operation,
+                        block_number: number,
+                        block_hash: undo.hash.clone(),
+                        timestamp: block.timestamp(),
+                        tx_hash: tx_hash.unpack(),
+                        out_point: created.as_ref().map(|(o, _)| o.clone().into()),
+                        data: created.map(|(_, d)| d.into()),
+                    };
+                    let mut versions: Vec<Version> = history
+                        .get(key.as_slice())?
+                        .map(|v| serde_json::from_slice(v.value()))
+                        .transpose()?
+                        .unwrap_or_default();
+                    let previous = versions.last().and_then(local_id_of);
+                    let current = local_id_of(&version);
+                    if previous.as_ref().map(|(key, _)| key) != current.as_ref().map(|(key, _)| key)
+                    {
+                        // the script keeps the local ID across updates, so this is
+                        // a burn dropping it or a mint setting it
+                        let did = did_from_key(&key);
+                        for (local_id, bind) in [(previous, false), (current, true)] {
+                            let Some((local_id_key, local_id)) = local_id else {
+                                continue;
+                            };
+                            let mut bindings: Vec<Binding> = local_ids
+                                .get(local_id_key.as_slice())?
+                                .map(|v| serde_json::from_slice(v.value()))
+                                .transpose()?
+                                .unwrap_or_default();
+                            if bind {
+                                bindings.push(Binding {
+                                    did: did.clone(),
+                                    local_id: local_id.clone(),
+                                    bound_block_number: number,
+                                    bound_tx_hash: tx_hash.unpack(),
+                                    unbound_block_number: None,
+                                    unbound_tx_hash: None,
+                                });
+                            } else if let Some(binding) =
+                                bindings.iter_mut().find(|b| b.did == did && b.is_live())
+                            {
+                                binding.unbound_block_number = Some(number);
+                                binding.unbound_tx_hash = Some(tx_hash.unpack());
+                            }
+                            local_ids.insert(
+                                local_id_key.as_slice(),
+                                serde_json::to_vec(&bindings)?.as_slice(),
+                            )?;
+                            let local_id_key = JsonBytes::from_vec(local_id_key);
+                            if !undo.local_ids.contains(&local_id_key) {
+                                undo.local_ids.push(local_id_key);
+                            }
+                        }
+                    }
+                    versions.push(version);
+                    history.insert(key.as_slice(), serde_json::to_vec(&versions)?.as_slice())?;
+                    let key = JsonBytes::from_vec(key.to_vec());
+                    if !undo.dids.contains(&key) {
+                        undo.dids.push(key);
+                    }
+                }
+            }
+            let mut blocks = txn.open_table(BLOCKS)?;
+            blocks.insert(number, serde_json::to_vec(&undo)?.as_slice())?;
+            if let Some(expired) = number.checked_sub(ROLLBACK_DEPTH) {
+                blocks.remove(expired)?;
+            }
+            txn.open_table(META)?.insert(TIP_KEY, number)?;
+        }
+        txn.commit()?;
+        if !undo.dids.is_empty() {
+            log::info!("block {}: {} did:ckb changed", number, undo.dids.len());
+        }
+        Ok(())
+    }
+}
+
+// Returns the local ID of the cell created by `version` with its `LOCAL_IDS`
+// key, if the script accepts its method.
+fn local_id_of(version: &Version) -> Option<(Vec<u8>, String)> {
+    let data = version.data.as_ref()?;
+    let (_, local_id) = decode_cell_data(data.as_bytes()).ok()?;
+    let local_id = local_id?;
+    match Registry::default().check_local_id(local_id.as_bytes()) {
+        Ok(()) => Some((local_id_key(&local_id), local_id)),
+        Err(_) => {
+            log::warn!("unsupported local ID {} in {}", local_id, version.tx_hash);
+            None
+        }
+    }
+}
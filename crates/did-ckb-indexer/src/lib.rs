@@ -0,0 +1,18 @@
+//! Indexer and resolver for did:ckb.
+//!
+//! The [`Indexer`] follows blocks from a CKB node, or from a local dump of
+//! blocks, and records every mint, update and burn of cells with the
+//! did-ckb-ts type script into an embedded database. The [`Resolver`] answers
+//! queries for the current document and version history of a did:ckb, and
+//! finds the did:ckb bound to a did:plc local ID.
+pub mod deployment;
+pub mod error;
+pub mod indexer;
+pub mod resolver;
+pub mod source;
+pub mod store;
+
+pub use indexer::Indexer;
+pub use resolver::{LocalIdResolution, Resolution, Resolver};
+pub use source::{BlockSource, DumpSource, RpcSource};
+pub use store::Store;
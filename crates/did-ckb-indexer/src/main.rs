@@ -0,0 +1,128 @@
+use ckb_types::{H256, core::ScriptHashType, prelude::*};
+use clap::Parser;
+use did_ckb_indexer::{
+    BlockSource, DumpSource, Indexer, Resolver, RpcSource, Store, deployment, error::Error,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Index did:ckb cells and serve a resolver over HTTP.
+#[derive(Parser)]
+struct Args {
+    /// JSON-RPC URL of a CKB node to follow
+    #[arg(long, conflicts_with = "dump", required_unless_present = "dump")]
+    rpc: Option<String>,
+    /// Directory of `<number>.json` blocks to index instead of a node
+    #[arg(long)]
+    dump: Option<PathBuf>,
+    /// Path of the database file
+    #[arg(long, default_value = "did-ckb-indexer.redb")]
+    db: PathBuf,
+    /// Address the resolver listens on
+    #[arg(long, default_value = "127.0.0.1:8116")]
+    listen: String,
+    /// Deployment directory the did-ckb-ts code hash is read from
+    #[arg(long, default_value = "deployment/mainnet")]
+    deployment: PathBuf,
+    /// Code hash of did-ckb-ts, overrides `--deployment`
+    #[arg(long)]
+    code_hash: Option<H256>,
+    /// Block to start indexing from when the database is empty
+    #[arg(long, default_value_t = 0)]
+    start_block: u64,
+    /// Seconds to wait for new blocks once caught up
+    #[arg(long, default_value_t = 5)]
+    poll_interval: u64,
+}
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    let args = Args::parse();
+    let code_hash = match args.code_hash {
+        Some(code_hash) => code_hash,
+        None => deployment::load_code_hash(&args.deployment)?,
+    };
+    let store = Store::open(&args.db)?;
+    let indexer = Arc::new(Indexer::new(
+        store,
+        code_hash.pack(),
+        ScriptHashType::Type,
+        args.start_block,
+    ));
+
+    let source: Box<dyn BlockSource + Send> = match (&args.rpc, &args.dump) {
+        (Some(url), _) => Box::new(RpcSource::new(url)),
+        (None, Some(dir)) => Box::new(DumpSource::new(dir)),
+        (None, None) => unreachable!("clap requires --rpc or --dump"),
+    };
+    let poll_interval = Duration::from_secs(args.poll_interval);
+    let sync_indexer = indexer.clone();
+    thread::spawn(move || {
+        loop {
+            match sync_indexer.sync(source.as_ref()) {
+                Ok(tip) => log::debug!("synced to {:?}", tip),
+                Err(e) => log::error!("sync failed: {}", e),
+            }
+            thread::sleep(poll_interval);
+        }
+    });
+
+    let server = Server::http(&args.listen).map_err(|e| Error::Rpc(e.to_string()))?;
+    log::info!("resolver listening on {}", args.listen);
+    for request in server.incoming_requests() {
+        let resolver = Resolver::new(indexer.store());
+        if let Err(e) = handle(&resolver, request) {
+            log::error!("failed to respond: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// GET /identifiers/{did} resolves the current document,
+// GET /identifiers/{did}/history returns every version,
+// GET /local-ids/{local_id} finds the did:ckb bound to a local ID.
+fn handle(resolver: &Resolver, request: Request) -> Result<(), Error> {
+    let path = request.url().split('?').next().unwrap_or_default();
+    let result = if *request.method() != Method::Get {
+        Ok(None)
+    } else if let Some(local_id) = path.strip_prefix("/local-ids/") {
+        resolver.resolve_local_id(local_id).and_then(|r| {
+            r.map(|r| serde_json::to_value(&r))
+                .transpose()
+                .map_err(Into::into)
+        })
+    } else if let Some(rest) = path.strip_prefix("/identifiers/") {
+        match rest.strip_suffix("/history") {
+            Some(did) => resolver.resolve(did).and_then(|r| {
+                r.map(|r| serde_json::to_value(&r.versions))
+                    .transpose()
+                    .map_err(Into::into)
+            }),
+            None => resolver.resolve(rest).and_then(|r| {
+                r.map(|r| serde_json::to_value(&r))
+                    .transpose()
+                    .map_err(Into::into)
+            }),
+        }
+    } else {
+        Ok(None)
+    };
+    let (status, body) = match result {
+        Ok(Some(body)) => (200, body),
+        Ok(None) => (404, serde_json::json!({ "error": "not found" })),
+        Err(Error::InvalidDid(did)) => (
+            400,
+            serde_json::json!({ "error": format!("invalid did: {}", did) }),
+        ),
+        Err(e) => (500, serde_json::json!({ "error": e.to_string() })),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    request.respond(response)?;
+    Ok(())
+}
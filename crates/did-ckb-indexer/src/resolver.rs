@@ -0,0 +1,151 @@
+use crate::error::Error;
+use crate::store::{Binding, DidKey, Operation, Store, Version, local_id_key};
+use base32::Alphabet;
+use base64::Engine;
+use ckb_did_plc_utils::cbor4ii::core::{Value, dec::Decode, utils::SliceReader};
+use ckb_did_plc_utils::local_id::Registry;
+use did_ckb_molecules::types::DidCkbData;
+use serde::Serialize;
+use serde_json::{Map, Number, Value as JsonValue, json};
+
+pub const DID_CKB_PREFIX: &str = "did:ckb:";
+
+/// Formats a did:ckb identifier from the first 20 bytes of its type script args.
+pub fn did_from_key(key: &DidKey) -> String {
+    let b32 = base32::encode(Alphabet::Rfc4648Lower { padding: false }, key);
+    format!("{}{}", DID_CKB_PREFIX, b32)
+}
+
+pub fn parse_did(did: &str) -> Result<DidKey, Error> {
+    did.strip_prefix(DID_CKB_PREFIX)
+        .and_then(|s| base32::decode(Alphabet::Rfc4648Lower { padding: false }, s))
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| Error::InvalidDid(did.to_owned()))
+}
+
+/// The current state of a did:ckb and how it got there.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Resolution {
+    pub did: String,
+    // The CBOR document rendered as JSON, `None` once deactivated
+    pub document: Option<JsonValue>,
+    pub local_id: Option<String>,
+    pub deactivated: bool,
+    pub versions: Vec<Version>,
+}
+
+/// The did:ckb cells bound to a local ID.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalIdResolution {
+    pub local_id: String,
+    // The earliest live binding, `None` when every binding is burned
+    pub did: Option<String>,
+    // Other live did:ckb claiming the same local ID
+    pub conflicts: Vec<String>,
+    pub bindings: Vec<Binding>,
+}
+
+pub struct Resolver<'a> {
+    store: &'a Store,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(store: &'a Store) -> Self {
+        Self { store }
+    }
+
+    /// Returns `None` when the did:ckb has never been minted.
+    pub fn resolve(&self, did: &str) -> Result<Option<Resolution>, Error> {
+        let key = parse_did(did)?;
+        let versions = self.store.history(&key)?;
+        let Some(latest) = versions.last() else {
+            return Ok(None);
+        };
+        let deactivated = latest.operation == Operation::Burn;
+        let (document, local_id) = match &latest.data {
+            Some(data) if !deactivated => {
+                let (document, local_id) = decode_cell_data(data.as_bytes())?;
+                (Some(cbor_to_json(&document)), local_id)
+            }
+            _ => (None, None),
+        };
+        Ok(Some(Resolution {
+            did: did_from_key(&key),
+            document,
+            local_id,
+            deactivated,
+            versions,
+        }))
+    }
+
+    /// Finds the did:ckb bound to `local_id`, of any method the script
+    /// accepts. Returns `None` when no cell has ever claimed it.
+    pub fn resolve_local_id(&self, local_id: &str) -> Result<Option<LocalIdResolution>, Error> {
+        Registry::default()
+            .check_local_id(local_id.as_bytes())
+            .map_err(|_| Error::InvalidDid(local_id.to_owned()))?;
+        let bindings = self.store.bindings(&local_id_key(local_id))?;
+        if bindings.is_empty() {
+            return Ok(None);
+        }
+        let mut live = bindings
+            .iter()
+            .filter(|b| b.is_live())
+            .map(|b| b.did.clone());
+        Ok(Some(LocalIdResolution {
+            local_id: local_id.to_owned(),
+            did: live.next(),
+            conflicts: live.collect(),
+            bindings,
+        }))
+    }
+}
+
+/// Decodes `DidCkbData` into its CBOR document and local ID.
+pub fn decode_cell_data(data: &[u8]) -> Result<(Value, Option<String>), Error> {
+    let DidCkbData::DidCkbDataV1(data) =
+        DidCkbData::from_slice(data).map_err(|_| Error::InvalidCellData)?;
+    let mut reader = SliceReader::new(&data.document);
+    let document = Value::decode(&mut reader).map_err(|_| Error::InvalidCellData)?;
+    Ok((document, data.local_id))
+}
+
+/// Renders a CBOR value as JSON. Byte strings use the DAG-JSON form
+/// `{"/": {"bytes": "<base64>"}}`.
+pub fn cbor_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Bool(b) => JsonValue::Bool(*b),
+        Value::Integer(i) => {
+            if let Ok(i) = i64::try_from(*i) {
+                JsonValue::Number(i.into())
+            } else if let Ok(u) = u64::try_from(*i) {
+                JsonValue::Number(u.into())
+            } else {
+                JsonValue::String(i.to_string())
+            }
+        }
+        Value::Float(f) => Number::from_f64(*f).map_or(JsonValue::Null, JsonValue::Number),
+        Value::Bytes(bytes) => {
+            let engine = base64::engine::general_purpose::STANDARD_NO_PAD;
+            json!({ "/": { "bytes": engine.encode(bytes) } })
+        }
+        Value::Text(s) => JsonValue::String(s.clone()),
+        Value::Array(items) => JsonValue::Array(items.iter().map(cbor_to_json).collect()),
+        Value::Map(entries) => {
+            let mut map = Map::new();
+            for (k, v) in entries {
+                let key = match k {
+                    Value::Text(s) => s.clone(),
+                    other => cbor_to_json(other).to_string(),
+                };
+                map.insert(key, cbor_to_json(v));
+            }
+            JsonValue::Object(map)
+        }
+        Value::Tag(_, inner) => cbor_to_json(inner),
+        _ => JsonValue::Null,
+    }
+}
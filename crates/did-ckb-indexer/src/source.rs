@@ -0,0 +1,87 @@
+use crate::error::Error;
+use ckb_jsonrpc_types::{BlockNumber, BlockView as JsonBlockView};
+use ckb_types::core::BlockView;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the indexer reads blocks from.
+pub trait BlockSource {
+    /// Returns the block at `number`, or `None` when the chain is not that
+    /// long yet.
+    fn block(&self, number: u64) -> Result<Option<BlockView>, Error>;
+}
+
+/// Reads blocks from the JSON-RPC interface of a CKB node.
+pub struct RpcSource {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+impl RpcSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<T>, Error> {
+        let response: RpcResponse<T> = ureq::post(&self.url)
+            .send_json(json!({
+                "id": 1,
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+            }))?
+            .into_json()?;
+        match response.error {
+            Some(error) => Err(Error::Rpc(error.to_string())),
+            None => Ok(response.result),
+        }
+    }
+}
+
+impl BlockSource for RpcSource {
+    fn block(&self, number: u64) -> Result<Option<BlockView>, Error> {
+        let block: Option<JsonBlockView> =
+            self.call("get_block_by_number", json!([BlockNumber::from(number)]))?;
+        Ok(block.map(Into::into))
+    }
+}
+
+/// Reads blocks from a directory of `<number>.json` files, each holding the
+/// result of the `get_block_by_number` RPC. Used to index a local dump or
+/// fixtures without a node.
+pub struct DumpSource {
+    dir: PathBuf,
+}
+
+impl DumpSource {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_owned(),
+        }
+    }
+}
+
+impl BlockSource for DumpSource {
+    fn block(&self, number: u64) -> Result<Option<BlockView>, Error> {
+        let path = self.dir.join(format!("{}.json", number));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let block: JsonBlockView = serde_json::from_slice(&fs::read(path)?)?;
+        Ok(Some(block.into()))
+    }
+}
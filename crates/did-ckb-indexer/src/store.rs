@@ -0,0 +1,212 @@
+use crate::error::Error;
+use ckb_did_plc_utils::operation::parse_local_id;
+use ckb_jsonrpc_types::{JsonBytes, OutPoint};
+use ckb_types::H256;
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The first 20 bytes of the type script args, which identify a did:ckb.
+pub type DidKey = [u8; 20];
+
+// live did:ckb cells: out point -> did key
+pub(crate) const CELLS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("cells");
+// did key -> JSON encoded `Vec<Version>`
+pub(crate) const HISTORY: TableDefinition<&[u8], &[u8]> = TableDefinition::new("history");
+// local ID key, of any method the script accepts, see `local_id_key` -> JSON
+// encoded `Vec<Binding>`
+pub(crate) const LOCAL_IDS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("local_ids");
+// block number -> JSON encoded `BlockUndo`, kept for the latest blocks only
+pub(crate) const BLOCKS: TableDefinition<u64, &[u8]> = TableDefinition::new("blocks");
+pub(crate) const META: TableDefinition<&str, u64> = TableDefinition::new("meta");
+pub(crate) const TIP_KEY: &str = "tip";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Mint,
+    Update,
+    Burn,
+}
+
+/// One step in the life of a did:ckb.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Version {
+    pub operation: Operation,
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub timestamp: u64,
+    pub tx_hash: H256,
+    // The cell created by this version, `None` for a burn
+    pub out_point: Option<OutPoint>,
+    // The `DidCkbData` of the cell, `None` for a burn
+    pub data: Option<JsonBytes>,
+}
+
+/// A did:ckb cell claiming a local ID, from the mint or update that set it
+/// until the burn that dropped it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Binding {
+    pub did: String,
+    pub local_id: String,
+    pub bound_block_number: u64,
+    pub bound_tx_hash: H256,
+    pub unbound_block_number: Option<u64>,
+    pub unbound_tx_hash: Option<H256>,
+}
+
+impl Binding {
+    pub fn is_live(&self) -> bool {
+        self.unbound_block_number.is_none()
+    }
+}
+
+/// The key of a checked local ID in the local ID table: the binary DID of a
+/// did:plc, so that every spelling of it shares its bindings, and the local ID
+/// itself for the other methods.
+pub fn local_id_key(local_id: &str) -> Vec<u8> {
+    parse_local_id(local_id.as_bytes()).unwrap_or_else(|_| local_id.as_bytes().to_vec())
+}
+
+/// What is needed to revert a block: the cells it created and consumed and
+/// the did:ckb histories and local ID bindings it changed.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct BlockUndo {
+    pub hash: H256,
+    pub dids: Vec<JsonBytes>,
+    pub local_ids: Vec<JsonBytes>,
+    pub created: Vec<JsonBytes>,
+    pub consumed: Vec<(JsonBytes, JsonBytes)>,
+}
+
+pub struct Store {
+    pub(crate) db: Database,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let db = Database::create(path)?;
+        // create all tables so read transactions can always open them
+        let txn = db.begin_write()?;
+        txn.open_table(CELLS)?;
+        txn.open_table(HISTORY)?;
+        txn.open_table(LOCAL_IDS)?;
+        txn.open_table(BLOCKS)?;
+        txn.open_table(META)?;
+        txn.commit()?;
+        Ok(Self { db })
+    }
+
+    /// Returns the number and hash of the last indexed block.
+    pub fn tip(&self) -> Result<Option<(u64, H256)>, Error> {
+        let txn = self.db.begin_read()?;
+        let number = match txn.open_table(META)?.get(TIP_KEY)? {
+            Some(number) => number.value(),
+            None => return Ok(None),
+        };
+        let blocks = txn.open_table(BLOCKS)?;
+        let undo = blocks.get(number)?.ok_or(Error::ReorgTooDeep(number))?;
+        let undo: BlockUndo = serde_json::from_slice(undo.value())?;
+        Ok(Some((number, undo.hash)))
+    }
+
+    /// Returns every version of a did:ckb, oldest first.
+    pub fn history(&self, did: &DidKey) -> Result<Vec<Version>, Error> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(HISTORY)?;
+        match table.get(did.as_slice())? {
+            Some(value) => Ok(serde_json::from_slice(value.value())?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns every did:ckb that has claimed the local ID of `local_id_key`,
+    /// see [`local_id_key`], in the order they were bound.
+    pub fn bindings(&self, local_id_key: &[u8]) -> Result<Vec<Binding>, Error> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(LOCAL_IDS)?;
+        match table.get(local_id_key)? {
+            Some(value) => Ok(serde_json::from_slice(value.value())?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Reverts the last indexed block.
+    pub fn rollback(&self) -> Result<(), Error> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut meta = txn.open_table(META)?;
+            let number = match meta.get(TIP_KEY)? {
+                Some(number) => number.value(),
+                None => return Ok(()),
+            };
+            let mut blocks = txn.open_table(BLOCKS)?;
+            // the parent becomes the new tip, so its hash must still be known
+            let parent = number.checked_sub(1).ok_or(Error::ReorgTooDeep(number))?;
+            if blocks.get(parent)?.is_none() {
+                return Err(Error::ReorgTooDeep(parent));
+            }
+            let undo = blocks.remove(number)?.ok_or(Error::ReorgTooDeep(number))?;
+            let undo: BlockUndo = serde_json::from_slice(undo.value())?;
+
+            let mut history = txn.open_table(HISTORY)?;
+            for did in &undo.dids {
+                let versions = history
+                    .get(did.as_bytes())?
+                    .map(|v| serde_json::from_slice::<Vec<Version>>(v.value()))
+                    .transpose()?
+                    .unwrap_or_default();
+                let versions: Vec<_> = versions
+                    .into_iter()
+                    .filter(|v| v.block_number != number)
+                    .collect();
+                if versions.is_empty() {
+                    history.remove(did.as_bytes())?;
+                } else {
+                    history.insert(did.as_bytes(), serde_json::to_vec(&versions)?.as_slice())?;
+                }
+            }
+            let mut local_ids = txn.open_table(LOCAL_IDS)?;
+            for local_id in &undo.local_ids {
+                let bindings = local_ids
+                    .get(local_id.as_bytes())?
+                    .map(|v| serde_json::from_slice::<Vec<Binding>>(v.value()))
+                    .transpose()?
+                    .unwrap_or_default();
+                let bindings: Vec<_> = bindings
+                    .into_iter()
+                    .filter(|b| b.bound_block_number != number)
+                    .map(|mut b| {
+                        if b.unbound_block_number == Some(number) {
+                            b.unbound_block_number = None;
+                            b.unbound_tx_hash = None;
+                        }
+                        b
+                    })
+                    .collect();
+                if bindings.is_empty() {
+                    local_ids.remove(local_id.as_bytes())?;
+                } else {
+                    local_ids.insert(
+                        local_id.as_bytes(),
+                        serde_json::to_vec(&bindings)?.as_slice(),
+                    )?;
+                }
+            }
+            // restore consumed cells first, a cell created and consumed in the
+            // same block must be gone afterwards
+            let mut cells = txn.open_table(CELLS)?;
+            for (out_point, did) in &undo.consumed {
+                cells.insert(out_point.as_bytes(), did.as_bytes())?;
+            }
+            for out_point in &undo.created {
+                cells.remove(out_point.as_bytes())?;
+            }
+            meta.insert(TIP_KEY, parent)?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
@@ -1,12 +1,11 @@
 use std::process::Command;
 
-fn compile(schema: &str) {
-    let out_dir = std::path::PathBuf::from("./src/molecules");
+fn compile(schema: &str, language: molecule_codegen::Language, out_dir: &str) {
     let mut compiler = molecule_codegen::Compiler::new();
     let result = compiler
         .input_schema_file(schema)
-        .generate_code(molecule_codegen::Language::RustLazyReader)
-        .output_dir(out_dir)
+        .generate_code(language)
+        .output_dir(std::path::PathBuf::from(out_dir))
         .run();
 
     if let Err(err) = result {
@@ -17,14 +16,22 @@ fn compile(schema: &str) {
 fn main() {
     println!("cargo:rerun-if-changed=molecules/cell_data.mol");
     println!("cargo:rerun-if-changed=molecules/witness.mol");
-    compile("molecules/cell_data.mol");
-    compile("molecules/witness.mol");
+    for schema in ["molecules/cell_data.mol", "molecules/witness.mol"] {
+        compile(
+            schema,
+            molecule_codegen::Language::RustLazyReader,
+            "./src/lazy_reader",
+        );
+        compile(schema, molecule_codegen::Language::Rust, "./src/packed");
+    }
 
     let output = Command::new("cargo")
         .arg("fmt")
         .arg("--")
-        .arg("src/molecules/cell_data.rs")
-        .arg("src/molecules/witness.rs")
+        .arg("src/lazy_reader/cell_data.rs")
+        .arg("src/lazy_reader/witness.rs")
+        .arg("src/packed/cell_data.rs")
+        .arg("src/packed/witness.rs")
         .output()
         .expect("Failed to execute command");
 
@@ -0,0 +1,35 @@
+//! Lazy readers that decode fields on demand through a [`Cursor`], which the
+//! script backs with syscalls so it never loads whole witnesses into memory.
+#[allow(clippy::all, unused_imports, dead_code)]
+pub mod cell_data;
+#[allow(clippy::all, unused_imports, dead_code)]
+pub mod witness;
+
+use alloc::{boxed::Box, vec::Vec};
+
+pub use cell_data::{DidCkbData, DidCkbDataV1, StringOpt};
+pub use molecule::lazy_reader::{Cursor, Error, Read};
+pub use witness::{
+    BytesOpt, BytesVec, DidCkbKeyWitness, DidCkbWebWitness, DidCkbWitness, KeyAuthorization,
+    PlcAuthorization, Uint8Vec, WebAuthorization, WitnessArgs,
+};
+
+struct VecReader {
+    data: Vec<u8>,
+}
+
+impl Read for VecReader {
+    fn read(&self, buf: &mut [u8], offset: usize) -> Result<usize, Error> {
+        if offset >= self.data.len() {
+            return Err(Error::OutOfBound(offset, self.data.len()));
+        }
+        let len = core::cmp::min(buf.len(), self.data.len() - offset);
+        buf[..len].copy_from_slice(&self.data[offset..offset + len]);
+        Ok(len)
+    }
+}
+
+/// Creates a cursor over bytes already in memory.
+pub fn new_cursor(data: Vec<u8>) -> Cursor {
+    Cursor::new(data.len(), Box::new(VecReader { data }))
+}
@@ -303,6 +303,104 @@ impl DidCkbWitness {
         Ok(())
     }
 }
+#[derive(Clone)]
+pub struct KeyAuthorization {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for KeyAuthorization {
+    fn from(cursor: Cursor) -> Self {
+        KeyAuthorization { cursor }
+    }
+}
+impl KeyAuthorization {
+    pub fn sig(&self) -> Result<Cursor, Error> {
+        let cur = self.cursor.table_slice_by_index(0usize)?;
+        cur.convert_to_rawbytes()
+    }
+}
+impl KeyAuthorization {
+    pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+        self.cursor.verify_table(1usize, compatible)?;
+        Ok(())
+    }
+}
+#[derive(Clone)]
+pub struct DidCkbKeyWitness {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for DidCkbKeyWitness {
+    fn from(cursor: Cursor) -> Self {
+        DidCkbKeyWitness { cursor }
+    }
+}
+impl DidCkbKeyWitness {
+    pub fn local_id_authorization(&self) -> Result<KeyAuthorization, Error> {
+        let cur = self.cursor.table_slice_by_index(0usize)?;
+        Ok(cur.into())
+    }
+}
+impl DidCkbKeyWitness {
+    pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+        self.cursor.verify_table(1usize, compatible)?;
+        self.local_id_authorization()?.verify(compatible)?;
+        Ok(())
+    }
+}
+#[derive(Clone)]
+pub struct WebAuthorization {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for WebAuthorization {
+    fn from(cursor: Cursor) -> Self {
+        WebAuthorization { cursor }
+    }
+}
+impl WebAuthorization {
+    pub fn document(&self) -> Result<Cursor, Error> {
+        let cur = self.cursor.table_slice_by_index(0usize)?;
+        cur.convert_to_rawbytes()
+    }
+}
+impl WebAuthorization {
+    pub fn verification_method(&self) -> Result<Cursor, Error> {
+        let cur = self.cursor.table_slice_by_index(1usize)?;
+        cur.convert_to_rawbytes()
+    }
+}
+impl WebAuthorization {
+    pub fn sig(&self) -> Result<Cursor, Error> {
+        let cur = self.cursor.table_slice_by_index(2usize)?;
+        cur.convert_to_rawbytes()
+    }
+}
+impl WebAuthorization {
+    pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+        self.cursor.verify_table(3usize, compatible)?;
+        Ok(())
+    }
+}
+#[derive(Clone)]
+pub struct DidCkbWebWitness {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for DidCkbWebWitness {
+    fn from(cursor: Cursor) -> Self {
+        DidCkbWebWitness { cursor }
+    }
+}
+impl DidCkbWebWitness {
+    pub fn local_id_authorization(&self) -> Result<WebAuthorization, Error> {
+        let cur = self.cursor.table_slice_by_index(0usize)?;
+        Ok(cur.into())
+    }
+}
+impl DidCkbWebWitness {
+    pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+        self.cursor.verify_table(1usize, compatible)?;
+        self.local_id_authorization()?.verify(compatible)?;
+        Ok(())
+    }
+}
 pub struct BytesOpt {
     pub cursor: Cursor,
 }
@@ -0,0 +1,13 @@
+//! Molecule types of did-ckb cell data and witnesses, generated from
+//! `molecules/*.mol`.
+//!
+//! - [`lazy_reader`]: the readers used by the did-ckb-ts script.
+//! - [`packed`]: owned entities and builders.
+//! - [`types`]: plain structs converting between the two, with optional
+//!   serde support.
+#![no_std]
+extern crate alloc;
+
+pub mod lazy_reader;
+pub mod packed;
+pub mod types;
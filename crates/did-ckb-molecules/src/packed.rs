@@ -0,0 +1,13 @@
+//! Owned molecule entities with builders, for off-chain code assembling
+//! cell data and witnesses.
+#[allow(clippy::all)]
+pub mod cell_data;
+#[allow(clippy::all)]
+pub mod witness;
+
+pub use cell_data::{DidCkbData, DidCkbDataUnion, DidCkbDataV1, StringOpt};
+pub use molecule::prelude::{Builder, Entity, Reader};
+pub use witness::{
+    BytesOpt, BytesVec, DidCkbKeyWitness, DidCkbWebWitness, DidCkbWitness, KeyAuthorization,
+    PlcAuthorization, Uint8Vec, WebAuthorization, WitnessArgs,
+};
@@ -0,0 +1,538 @@
+//! Plain Rust mirrors of the molecule types. They convert from the lazy
+//! readers and to and from the packed entities, and with the `serde` feature
+//! they (de)serialize byte fields as `0x` prefixed hex like CKB JSON-RPC does.
+use crate::{lazy_reader, packed};
+use alloc::{string::String, vec::Vec};
+use molecule::error::VerificationError;
+use molecule::prelude::{Builder, Byte, Entity};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum Error {
+    Molecule(lazy_reader::Error),
+    Verification(VerificationError),
+    // `local_id` is declared as UTF-8 in the schema
+    InvalidUtf8,
+}
+
+impl From<lazy_reader::Error> for Error {
+    fn from(e: lazy_reader::Error) -> Self {
+        Error::Molecule(e)
+    }
+}
+
+impl From<VerificationError> for Error {
+    fn from(e: VerificationError) -> Self {
+        Error::Verification(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DidCkbDataV1 {
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub document: Vec<u8>,
+    pub local_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DidCkbData {
+    DidCkbDataV1(DidCkbDataV1),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlcAuthorization {
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes_vec"))]
+    pub history: Vec<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub sig: Vec<u8>,
+    pub rotation_key_indices: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DidCkbWitness {
+    pub local_id_authorization: PlcAuthorization,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyAuthorization {
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub sig: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DidCkbKeyWitness {
+    pub local_id_authorization: KeyAuthorization,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WebAuthorization {
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub document: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub verification_method: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub sig: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DidCkbWebWitness {
+    pub local_id_authorization: WebAuthorization,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WitnessArgs {
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes_opt"))]
+    pub lock: Option<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes_opt"))]
+    pub input_type: Option<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes_opt"))]
+    pub output_type: Option<Vec<u8>>,
+}
+
+macro_rules! impl_molecule {
+    ($($name:ident),*) => {
+        $(
+            impl $name {
+                /// Parses and verifies the molecule encoding.
+                pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
+                    packed::$name::from_slice(data)?.try_into()
+                }
+
+                /// Returns the molecule encoding.
+                pub fn to_vec(&self) -> Vec<u8> {
+                    packed::$name::from(self).as_slice().to_vec()
+                }
+            }
+
+            impl TryFrom<lazy_reader::$name> for $name {
+                type Error = Error;
+
+                fn try_from(reader: lazy_reader::$name) -> Result<Self, Error> {
+                    reader.verify(false)?;
+                    Self::try_from(&reader)
+                }
+            }
+        )*
+    };
+}
+
+impl_molecule!(
+    DidCkbData,
+    DidCkbDataV1,
+    PlcAuthorization,
+    DidCkbWitness,
+    KeyAuthorization,
+    DidCkbKeyWitness,
+    WebAuthorization,
+    DidCkbWebWitness,
+    WitnessArgs
+);
+
+fn pack_bytes<B: Builder<Entity = T> + Default, T>(
+    data: &[u8],
+    set: impl FnOnce(B, Vec<Byte>) -> B,
+) -> T {
+    set(B::default(), data.iter().copied().map(Byte::new).collect()).build()
+}
+
+fn cell_data_bytes(data: &[u8]) -> packed::cell_data::Bytes {
+    pack_bytes(data, packed::cell_data::BytesBuilder::set)
+}
+
+fn witness_bytes(data: &[u8]) -> packed::witness::Bytes {
+    pack_bytes(data, packed::witness::BytesBuilder::set)
+}
+
+fn witness_bytes_opt(data: &Option<Vec<u8>>) -> packed::BytesOpt {
+    packed::BytesOpt::new_builder()
+        .set(data.as_deref().map(witness_bytes))
+        .build()
+}
+
+fn utf8(data: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(data).map_err(|_| Error::InvalidUtf8)
+}
+
+impl From<&DidCkbDataV1> for packed::DidCkbDataV1 {
+    fn from(data: &DidCkbDataV1) -> Self {
+        let local_id = data
+            .local_id
+            .as_ref()
+            .map(|id| pack_bytes(id.as_bytes(), packed::cell_data::StringBuilder::set));
+        packed::DidCkbDataV1::new_builder()
+            .document(cell_data_bytes(&data.document))
+            .local_id(packed::StringOpt::new_builder().set(local_id).build())
+            .build()
+    }
+}
+
+impl TryFrom<packed::DidCkbDataV1> for DidCkbDataV1 {
+    type Error = Error;
+
+    fn try_from(data: packed::DidCkbDataV1) -> Result<Self, Error> {
+        let local_id = match data.local_id().to_opt() {
+            Some(id) => Some(utf8(id.raw_data().to_vec())?),
+            None => None,
+        };
+        Ok(Self {
+            document: data.document().raw_data().to_vec(),
+            local_id,
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::DidCkbDataV1> for DidCkbDataV1 {
+    type Error = Error;
+
+    fn try_from(data: &lazy_reader::DidCkbDataV1) -> Result<Self, Error> {
+        let local_id = match data.local_id()? {
+            Some(id) => Some(utf8(id.try_into()?)?),
+            None => None,
+        };
+        Ok(Self {
+            document: data.document()?.try_into()?,
+            local_id,
+        })
+    }
+}
+
+impl From<&DidCkbData> for packed::DidCkbData {
+    fn from(data: &DidCkbData) -> Self {
+        let DidCkbData::DidCkbDataV1(data) = data;
+        packed::DidCkbData::new_builder()
+            .set(packed::DidCkbDataV1::from(data))
+            .build()
+    }
+}
+
+impl TryFrom<packed::DidCkbData> for DidCkbData {
+    type Error = Error;
+
+    fn try_from(data: packed::DidCkbData) -> Result<Self, Error> {
+        let packed::DidCkbDataUnion::DidCkbDataV1(data) = data.to_enum();
+        Ok(DidCkbData::DidCkbDataV1(data.try_into()?))
+    }
+}
+
+impl TryFrom<&lazy_reader::DidCkbData> for DidCkbData {
+    type Error = Error;
+
+    fn try_from(data: &lazy_reader::DidCkbData) -> Result<Self, Error> {
+        let lazy_reader::DidCkbData::DidCkbDataV1(data) = data;
+        Ok(DidCkbData::DidCkbDataV1(data.try_into()?))
+    }
+}
+
+impl From<&PlcAuthorization> for packed::PlcAuthorization {
+    fn from(auth: &PlcAuthorization) -> Self {
+        let history = auth.history.iter().map(|op| witness_bytes(op)).collect();
+        packed::PlcAuthorization::new_builder()
+            .history(packed::BytesVec::new_builder().set(history).build())
+            .sig(witness_bytes(&auth.sig))
+            .rotation_key_indices(pack_bytes(
+                &auth.rotation_key_indices,
+                packed::witness::Uint8VecBuilder::set,
+            ))
+            .build()
+    }
+}
+
+impl TryFrom<packed::PlcAuthorization> for PlcAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: packed::PlcAuthorization) -> Result<Self, Error> {
+        Ok(Self {
+            history: auth
+                .history()
+                .into_iter()
+                .map(|op| op.raw_data().to_vec())
+                .collect(),
+            sig: auth.sig().raw_data().to_vec(),
+            rotation_key_indices: auth.rotation_key_indices().raw_data().to_vec(),
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::PlcAuthorization> for PlcAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: &lazy_reader::PlcAuthorization) -> Result<Self, Error> {
+        let history = auth
+            .history()?
+            .into_iter()
+            .map(Vec::<u8>::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            history,
+            sig: auth.sig()?.try_into()?,
+            rotation_key_indices: auth.rotation_key_indices()?.try_into()?,
+        })
+    }
+}
+
+impl From<&DidCkbWitness> for packed::DidCkbWitness {
+    fn from(witness: &DidCkbWitness) -> Self {
+        packed::DidCkbWitness::new_builder()
+            .local_id_authorization(packed::PlcAuthorization::from(
+                &witness.local_id_authorization,
+            ))
+            .build()
+    }
+}
+
+impl TryFrom<packed::DidCkbWitness> for DidCkbWitness {
+    type Error = Error;
+
+    fn try_from(witness: packed::DidCkbWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: witness.local_id_authorization().try_into()?,
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::DidCkbWitness> for DidCkbWitness {
+    type Error = Error;
+
+    fn try_from(witness: &lazy_reader::DidCkbWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: (&witness.local_id_authorization()?).try_into()?,
+        })
+    }
+}
+
+impl From<&KeyAuthorization> for packed::KeyAuthorization {
+    fn from(auth: &KeyAuthorization) -> Self {
+        packed::KeyAuthorization::new_builder()
+            .sig(witness_bytes(&auth.sig))
+            .build()
+    }
+}
+
+impl TryFrom<packed::KeyAuthorization> for KeyAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: packed::KeyAuthorization) -> Result<Self, Error> {
+        Ok(Self {
+            sig: auth.sig().raw_data().to_vec(),
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::KeyAuthorization> for KeyAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: &lazy_reader::KeyAuthorization) -> Result<Self, Error> {
+        Ok(Self {
+            sig: auth.sig()?.try_into()?,
+        })
+    }
+}
+
+impl From<&DidCkbKeyWitness> for packed::DidCkbKeyWitness {
+    fn from(witness: &DidCkbKeyWitness) -> Self {
+        packed::DidCkbKeyWitness::new_builder()
+            .local_id_authorization(packed::KeyAuthorization::from(
+                &witness.local_id_authorization,
+            ))
+            .build()
+    }
+}
+
+impl TryFrom<packed::DidCkbKeyWitness> for DidCkbKeyWitness {
+    type Error = Error;
+
+    fn try_from(witness: packed::DidCkbKeyWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: witness.local_id_authorization().try_into()?,
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::DidCkbKeyWitness> for DidCkbKeyWitness {
+    type Error = Error;
+
+    fn try_from(witness: &lazy_reader::DidCkbKeyWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: (&witness.local_id_authorization()?).try_into()?,
+        })
+    }
+}
+
+impl From<&WebAuthorization> for packed::WebAuthorization {
+    fn from(auth: &WebAuthorization) -> Self {
+        packed::WebAuthorization::new_builder()
+            .document(witness_bytes(&auth.document))
+            .verification_method(witness_bytes(&auth.verification_method))
+            .sig(witness_bytes(&auth.sig))
+            .build()
+    }
+}
+
+impl TryFrom<packed::WebAuthorization> for WebAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: packed::WebAuthorization) -> Result<Self, Error> {
+        Ok(Self {
+            document: auth.document().raw_data().to_vec(),
+            verification_method: auth.verification_method().raw_data().to_vec(),
+            sig: auth.sig().raw_data().to_vec(),
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::WebAuthorization> for WebAuthorization {
+    type Error = Error;
+
+    fn try_from(auth: &lazy_reader::WebAuthorization) -> Result<Self, Error> {
+        Ok(Self {
+            document: auth.document()?.try_into()?,
+            verification_method: auth.verification_method()?.try_into()?,
+            sig: auth.sig()?.try_into()?,
+        })
+    }
+}
+
+impl From<&DidCkbWebWitness> for packed::DidCkbWebWitness {
+    fn from(witness: &DidCkbWebWitness) -> Self {
+        packed::DidCkbWebWitness::new_builder()
+            .local_id_authorization(packed::WebAuthorization::from(
+                &witness.local_id_authorization,
+            ))
+            .build()
+    }
+}
+
+impl TryFrom<packed::DidCkbWebWitness> for DidCkbWebWitness {
+    type Error = Error;
+
+    fn try_from(witness: packed::DidCkbWebWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: witness.local_id_authorization().try_into()?,
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::DidCkbWebWitness> for DidCkbWebWitness {
+    type Error = Error;
+
+    fn try_from(witness: &lazy_reader::DidCkbWebWitness) -> Result<Self, Error> {
+        Ok(Self {
+            local_id_authorization: (&witness.local_id_authorization()?).try_into()?,
+        })
+    }
+}
+
+impl From<&WitnessArgs> for packed::WitnessArgs {
+    fn from(args: &WitnessArgs) -> Self {
+        packed::WitnessArgs::new_builder()
+            .lock(witness_bytes_opt(&args.lock))
+            .input_type(witness_bytes_opt(&args.input_type))
+            .output_type(witness_bytes_opt(&args.output_type))
+            .build()
+    }
+}
+
+impl TryFrom<packed::WitnessArgs> for WitnessArgs {
+    type Error = Error;
+
+    fn try_from(args: packed::WitnessArgs) -> Result<Self, Error> {
+        let raw = |opt: packed::BytesOpt| opt.to_opt().map(|b| b.raw_data().to_vec());
+        Ok(Self {
+            lock: raw(args.lock()),
+            input_type: raw(args.input_type()),
+            output_type: raw(args.output_type()),
+        })
+    }
+}
+
+impl TryFrom<&lazy_reader::WitnessArgs> for WitnessArgs {
+    type Error = Error;
+
+    fn try_from(args: &lazy_reader::WitnessArgs) -> Result<Self, Error> {
+        let raw = |opt: Option<lazy_reader::Cursor>| opt.map(Vec::<u8>::try_from).transpose();
+        Ok(Self {
+            lock: raw(args.lock()?)?,
+            input_type: raw(args.input_type()?)?,
+            output_type: raw(args.output_type()?)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use alloc::{format, string::String, vec::Vec};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(data)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode(&s).map_err(D::Error::custom)
+    }
+
+    pub fn decode(s: &str) -> Result<Vec<u8>, String> {
+        let s = s
+            .strip_prefix("0x")
+            .ok_or_else(|| format!("{} is not 0x prefixed", s))?;
+        hex::decode(s).map_err(|e| format!("{}", e))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod hex_bytes_opt {
+    use alloc::{format, string::String, vec::Vec};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        data: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match data {
+            Some(data) => serializer.serialize_some(&format!("0x{}", hex::encode(data))),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| super::hex_bytes::decode(&s).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(feature = "serde")]
+mod hex_bytes_vec {
+    use alloc::{format, string::String, vec::Vec};
+    use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(data.len()))?;
+        for item in data {
+            seq.serialize_element(&format!("0x{}", hex::encode(item)))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| super::hex_bytes::decode(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,137 @@
+use crate::error::{Error, SysError};
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{CellInput, CellOutput, Script},
+    prelude::*,
+};
+
+/// A live cell consumed by the transaction, as it was created by a previous
+/// transaction.
+#[derive(Debug, Clone)]
+pub struct ResolvedInput {
+    pub cell_output: CellOutput,
+    pub data: Bytes,
+}
+
+impl ResolvedInput {
+    pub fn new(cell_output: CellOutput, data: Bytes) -> Self {
+        Self { cell_output, data }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Source {
+    Input,
+    Output,
+    GroupInput,
+    GroupOutput,
+}
+
+/// What a single execution of the script can see through syscalls: the
+/// transaction, its resolved inputs and the cells of the current script group.
+pub(crate) struct ScriptContext<'a> {
+    tx: &'a TransactionView,
+    resolved_inputs: &'a [ResolvedInput],
+    script: Script,
+    input_indices: Vec<usize>,
+    output_indices: Vec<usize>,
+}
+
+impl<'a> ScriptContext<'a> {
+    pub(crate) fn new(
+        tx: &'a TransactionView,
+        resolved_inputs: &'a [ResolvedInput],
+        script: &Script,
+    ) -> Result<Self, Error> {
+        if tx.inputs().len() != resolved_inputs.len() {
+            return Err(Error::UnresolvedInputs);
+        }
+        let input_indices = resolved_inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.cell_output.type_().to_opt().as_ref() == Some(script))
+            .map(|(i, _)| i)
+            .collect();
+        let output_indices = tx
+            .outputs()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, output)| output.type_().to_opt().as_ref() == Some(script))
+            .map(|(i, _)| i)
+            .collect();
+        Ok(Self {
+            tx,
+            resolved_inputs,
+            script: script.clone(),
+            input_indices,
+            output_indices,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.input_indices.is_empty() && self.output_indices.is_empty()
+    }
+
+    pub(crate) fn script(&self) -> &Script {
+        &self.script
+    }
+
+    pub(crate) fn tx_hash(&self) -> [u8; 32] {
+        self.tx.hash().unpack()
+    }
+
+    // Translates group indices into transaction indices, like CKB-VM does.
+    fn absolute_index(&self, index: usize, source: Source) -> Result<usize, SysError> {
+        match source {
+            Source::Input | Source::Output => Some(index),
+            Source::GroupInput => self.input_indices.get(index).copied(),
+            Source::GroupOutput => self.output_indices.get(index).copied(),
+        }
+        .ok_or(SysError::IndexOutOfBound)
+    }
+
+    pub(crate) fn is_cell_present(&self, index: usize, source: Source) -> bool {
+        self.load_cell(index, source).is_ok()
+    }
+
+    pub(crate) fn load_cell(&self, index: usize, source: Source) -> Result<CellOutput, SysError> {
+        let index = self.absolute_index(index, source)?;
+        match source {
+            Source::Input | Source::GroupInput => self
+                .resolved_inputs
+                .get(index)
+                .map(|input| input.cell_output.clone()),
+            Source::Output | Source::GroupOutput => self.tx.outputs().get(index),
+        }
+        .ok_or(SysError::IndexOutOfBound)
+    }
+
+    pub(crate) fn load_cell_data(&self, index: usize, source: Source) -> Result<Bytes, SysError> {
+        let index = self.absolute_index(index, source)?;
+        match source {
+            Source::Input | Source::GroupInput => self
+                .resolved_inputs
+                .get(index)
+                .map(|input| input.data.clone()),
+            Source::Output | Source::GroupOutput => {
+                self.tx.outputs_data().get(index).map(|d| d.raw_data())
+            }
+        }
+        .ok_or(SysError::IndexOutOfBound)
+    }
+
+    pub(crate) fn load_input(&self, index: usize, source: Source) -> Result<CellInput, SysError> {
+        let index = self.absolute_index(index, source)?;
+        self.tx.inputs().get(index).ok_or(SysError::IndexOutOfBound)
+    }
+
+    pub(crate) fn load_witness(&self, index: usize, source: Source) -> Result<Bytes, SysError> {
+        let index = self.absolute_index(index, source)?;
+        self.tx
+            .witnesses()
+            .get(index)
+            .map(|w| w.raw_data())
+            .ok_or(SysError::IndexOutOfBound)
+    }
+}
@@ -0,0 +1,197 @@
+use crate::context::{ResolvedInput, ScriptContext, Source};
+use crate::error::{Error, SysError};
+use ckb_did_plc_utils::{
+    inspect::{Inspection, inspect_operation_history},
+    local_id::Authorization,
+    operation::{parse_local_id, resolve_operation_history},
+    script::{
+        WitnessPlacement, authorization_from, burn, mint, parse_witness_args,
+        read_plc_authorization, update, validate_authorization,
+    },
+    state::PlcState,
+};
+use ckb_hash::new_blake2b;
+use ckb_types::{
+    core::{ScriptHashType, TransactionView},
+    packed::{Byte32, Script},
+    prelude::*,
+};
+use did_ckb_molecules::lazy_reader::{Cursor, new_cursor};
+
+fn read_witness(witness: &[u8]) -> Result<Cursor, Error> {
+    if witness.is_empty() {
+        return Err(Error::WitnessMissing);
+    }
+    let witness_args = parse_witness_args(new_cursor(witness.to_vec()))?;
+    Ok(authorization_from(
+        &witness_args,
+        WitnessPlacement::OutputType,
+    )?)
+}
+
+/// Checks offline that `witness`, the `WitnessArgs` at index 0 of the output
+/// group of a mint, authorizes binding `local_id` in the transaction
+/// `tx_hash`.
+///
+/// Only the authorization is checked, with the errors the script would return
+/// for it. The cell data and type ID rules need the whole transaction, see
+/// [`verify_transaction`].
+pub fn verify_authorization(
+    witness: &[u8],
+    local_id: &str,
+    tx_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let witness = read_witness(witness)?;
+    validate_authorization(witness, local_id.as_bytes(), tx_hash)
+}
+
+/// Walks the did:plc history of the authorization in `witness` step by step,
+/// like [`verify_authorization`], and reports where and why it fails.
+///
+/// Errors before the history, such as a malformed witness, are returned as
+/// [`verify_authorization`] returns them. An [`Inspection`] with no error means
+/// the authorization is valid.
+pub fn inspect_authorization(
+    witness: &[u8],
+    local_id: &str,
+    tx_hash: &[u8; 32],
+) -> Result<Inspection, Error> {
+    with_plc_authorization(witness, local_id, |binary_did, history, indices, sig| {
+        inspect_operation_history(binary_did, history, indices, tx_hash, sig)
+    })
+}
+
+/// Verifies a did:plc authorization like [`verify_authorization`] and returns
+/// the state of the did:plc after the latest operation of its history, from
+/// which its DID document can be rendered without asking the PLC directory.
+pub fn resolve_authorization(
+    witness: &[u8],
+    local_id: &str,
+    tx_hash: &[u8; 32],
+) -> Result<PlcState, Error> {
+    let state = with_plc_authorization(witness, local_id, |binary_did, history, indices, sig| {
+        resolve_operation_history(binary_did, history, indices, tx_hash, sig)
+    })??;
+    Ok(state)
+}
+
+// Calls `f` with the binary did:plc of `local_id` and the did:plc
+// authorization in `witness`
+fn with_plc_authorization<T>(
+    witness: &[u8],
+    local_id: &str,
+    f: impl FnOnce(&[u8], Vec<Cursor>, Vec<usize>, &[u8]) -> T,
+) -> Result<T, Error> {
+    let witness = read_witness(witness)?;
+    let binary_did = parse_local_id(local_id.as_bytes())?;
+    let Authorization::Plc {
+        history,
+        rotation_key_indices,
+        sig,
+    } = read_plc_authorization(witness)?
+    else {
+        unreachable!("a did:plc authorization");
+    };
+    Ok(f(&binary_did, history, rotation_key_indices, &sig))
+}
+
+// Mirrors `ckb_std::type_id::check_type_id`.
+fn check_type_id(ctx: &ScriptContext, offset: usize, length: usize) -> Result<(), SysError> {
+    let args = ctx.script().args().raw_data();
+    let type_id = args
+        .get(offset..offset + length)
+        .ok_or(SysError::TypeIDError)?;
+
+    if ctx.is_cell_present(1, Source::GroupInput) || ctx.is_cell_present(1, Source::GroupOutput) {
+        return Err(SysError::TypeIDError);
+    }
+    if !ctx.is_cell_present(0, Source::GroupInput) {
+        let script_hash = ctx.script().calc_script_hash();
+        let mut index = 0;
+        loop {
+            let output = ctx
+                .load_cell(index, Source::Output)
+                .map_err(|_| SysError::TypeIDError)?;
+            if output.type_().to_opt().map(|s| s.calc_script_hash()) == Some(script_hash.clone()) {
+                break;
+            }
+            index += 1;
+        }
+        let input = ctx.load_input(0, Source::Input)?;
+        let mut blake2b = new_blake2b();
+        blake2b.update(input.as_slice());
+        blake2b.update(&(index as u64).to_le_bytes());
+        let mut ret = [0; 32];
+        blake2b.finalize(&mut ret);
+        if type_id.len() > ret.len() || &ret[..type_id.len()] != type_id {
+            return Err(SysError::TypeIDError);
+        }
+    }
+    Ok(())
+}
+
+fn entry(ctx: &ScriptContext) -> Result<(), Error> {
+    check_type_id(ctx, 0, 20)?;
+    match (
+        ctx.is_cell_present(0, Source::GroupInput),
+        ctx.is_cell_present(0, Source::GroupOutput),
+    ) {
+        (true, true) => update(ctx),
+        (true, false) => burn(ctx),
+        (false, true) => mint(ctx),
+        (false, false) => unreachable!(),
+    }
+}
+
+/// Runs the did-ckb-ts rules for the script group of `script` in `tx`, the same
+/// way the on-chain script does.
+///
+/// `resolved_inputs` must hold the consumed cell of every input of `tx`, in
+/// the order of `tx.inputs()`, or `Error::UnresolvedInputs` is returned. On
+/// any other failure, `Error::error_code()` is the exit code the script would
+/// return.
+///
+/// A script that no cell of `tx` uses is not executed on-chain, so it always
+/// passes.
+pub fn verify_script_group(
+    tx: &TransactionView,
+    resolved_inputs: &[ResolvedInput],
+    script: &Script,
+) -> Result<(), Error> {
+    let ctx = ScriptContext::new(tx, resolved_inputs, script)?;
+    if ctx.is_empty() {
+        return Ok(());
+    }
+    entry(&ctx)
+}
+
+/// Runs [`verify_script_group`] for every did:ckb cell group in `tx`, in the
+/// order the groups first appear among the inputs and then the outputs.
+///
+/// `code_hash` and `hash_type` identify the deployed did-ckb-ts script. The
+/// first failing group is returned together with its type script.
+pub fn verify_transaction(
+    tx: &TransactionView,
+    resolved_inputs: &[ResolvedInput],
+    code_hash: &Byte32,
+    hash_type: ScriptHashType,
+) -> Result<(), (Script, Error)> {
+    let mut scripts: Vec<Script> = vec![];
+    let type_scripts = resolved_inputs
+        .iter()
+        .map(|input| input.cell_output.clone())
+        .chain(tx.outputs())
+        .filter_map(|output| output.type_().to_opt());
+    for script in type_scripts {
+        if &script.code_hash() == code_hash
+            && script.hash_type() == hash_type.into()
+            && !scripts.contains(&script)
+        {
+            scripts.push(script);
+        }
+    }
+    for script in scripts {
+        verify_script_group(tx, resolved_inputs, &script).map_err(|e| (script.clone(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use ckb_did_plc_utils::error::{Context as UtilsContext, Error as UtilsError};
+use ckb_did_plc_utils::script::ScriptError;
+use core::fmt::Display;
+use molecule::lazy_reader::Error as MoleculeError;
+
+/// The subset of `ckb_std::error::SysError` that the did-ckb-ts script can
+/// observe through its syscalls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SysError {
+    IndexOutOfBound,
+    ItemMissing,
+    LengthNotEnough(usize),
+    Encoding,
+    TypeIDError,
+}
+
+/// Mirrors the error type of the did-ckb-ts script. `error_code()` returns the
+/// exact exit code the script would return, while `Display` gives a
+/// human-readable explanation.
+#[derive(Debug)]
+pub enum Error {
+    Syscall(SysError),
+    Utils(UtilsError),
+    Molecule,
+    InvalidDocumentCbor,
+    MismatchedFrom,
+    WitnessMissing,
+    InvalidWitnessArgs,
+    AuthorizationMissing,
+    MisplacedAuthorization,
+    InvalidAuthorization,
+    // Not a script error: the caller did not resolve every input of the
+    // transaction, so the script cannot be run.
+    UnresolvedInputs,
+}
+
+impl From<SysError> for Error {
+    fn from(e: SysError) -> Self {
+        Error::Syscall(e)
+    }
+}
+
+impl From<UtilsError> for Error {
+    fn from(e: UtilsError) -> Self {
+        Error::Utils(e)
+    }
+}
+
+impl From<ScriptError> for Error {
+    fn from(e: ScriptError) -> Self {
+        match e {
+            ScriptError::Molecule => Error::Molecule,
+            ScriptError::InvalidDocumentCbor => Error::InvalidDocumentCbor,
+            ScriptError::MismatchedFrom => Error::MismatchedFrom,
+            ScriptError::WitnessMissing => Error::WitnessMissing,
+            ScriptError::InvalidWitnessArgs => Error::InvalidWitnessArgs,
+            ScriptError::AuthorizationMissing => Error::AuthorizationMissing,
+            ScriptError::MisplacedAuthorization => Error::MisplacedAuthorization,
+            ScriptError::InvalidAuthorization => Error::InvalidAuthorization,
+        }
+    }
+}
+
+impl From<MoleculeError> for Error {
+    fn from(_: MoleculeError) -> Self {
+        Error::Molecule
+    }
+}
+
+impl Error {
+    pub fn error_code(&self) -> i8 {
+        match self {
+            // ckb syserror starts from 21
+            Error::Syscall(e) => match e {
+                SysError::IndexOutOfBound => 21,
+                SysError::ItemMissing => 22,
+                SysError::LengthNotEnough(_) => 23,
+                SysError::Encoding => 24,
+                SysError::TypeIDError => 26,
+            },
+            // crate ckb-did-plc-utils error starts from 31
+            Error::Utils(e) => e.error_code(),
+            // this script error starts from 51
+            Error::Molecule => 51,
+            Error::InvalidDocumentCbor => 52,
+            Error::MismatchedFrom => 53,
+            Error::WitnessMissing => 54,
+            Error::InvalidWitnessArgs => 55,
+            Error::AuthorizationMissing => 56,
+            Error::MisplacedAuthorization => 57,
+            Error::InvalidAuthorization => 58,
+            Error::UnresolvedInputs => -2,
+        }
+    }
+
+    /// Where and why a did:plc authorization failed, with the `error_context`
+    /// feature.
+    pub fn context(&self) -> Option<&UtilsContext> {
+        match self {
+            Error::Utils(e) => e.context(),
+            _ => None,
+        }
+    }
+
+    fn explanation(&self) -> &'static str {
+        match self {
+            Error::Syscall(e) => match e {
+                SysError::IndexOutOfBound => "a cell, input or witness index is out of bound",
+                SysError::ItemMissing => "a requested item is missing",
+                SysError::LengthNotEnough(_) => "a buffer is too small",
+                SysError::Encoding => "a loaded item has an invalid encoding",
+                SysError::TypeIDError => {
+                    "type ID rule violated: args are shorter than 20 bytes, the script group \
+                     has more than one input or output, or the args of a minted cell do not \
+                     match the hash of the first input"
+                }
+            },
+            Error::Utils(e) => match e.root() {
+                UtilsError::InvalidOperation => {
+                    "a did:plc operation is not a CBOR map, has an unknown type or misses required fields"
+                }
+                UtilsError::RotationKeysDecodeError => {
+                    "the rotationKeys field of a did:plc operation is missing or not an array"
+                }
+                UtilsError::InvalidKey => {
+                    "a did:key is malformed or is not a compressed secp256k1/P-256 key"
+                }
+                UtilsError::InvalidKeyIndex => "a rotation key index is out of range",
+                UtilsError::InvalidSignature => {
+                    "a signature is malformed, or the final authorization signature over the \
+                     transaction hash does not verify"
+                }
+                UtilsError::InvalidSignaturePadding => {
+                    "a did:plc operation signature must not end with '=' padding"
+                }
+                UtilsError::VerifySignatureFailed => {
+                    "a did:plc operation is not signed by the selected rotation key of the previous operation"
+                }
+                UtilsError::InvalidPrev => {
+                    "the prev field of a did:plc operation does not match the CID of the previous operation"
+                }
+                UtilsError::MissingPrevField => "a non-genesis did:plc operation has a null prev",
+                UtilsError::NotGenesisOperation => {
+                    "the first operation in the history is not a genesis operation"
+                }
+                UtilsError::DidMismatched => {
+                    "the genesis operation does not derive the did:plc in local_id"
+                }
+                UtilsError::ReaderError => "failed to read the authorization",
+                UtilsError::InvalidHistory => {
+                    "the history is empty, or rotation_key_indices is not one longer than the history"
+                }
+                UtilsError::MoleculeError(_) => "the authorization has a malformed molecule layout",
+                UtilsError::InvalidCbor => "the document is not valid CBOR",
+                UtilsError::InvalidDidFormat => {
+                    "local_id is not a valid identifier of a supported DID method"
+                }
+                UtilsError::InvalidDidKey => {
+                    "local_id is not a did:key of a compressed secp256k1/P-256 key"
+                }
+                UtilsError::DidKeySignatureFailed => {
+                    "the did:key authorization is not a signature of the transaction hash by the \
+                     key of local_id"
+                }
+                UtilsError::MismatchedAuthorization => {
+                    "the authorization is the witness of another DID method than local_id's"
+                }
+                UtilsError::InvalidDidWeb => "local_id is not a valid did:web",
+                UtilsError::InvalidDidWebDocument => {
+                    "the did:web document is not valid JSON, or its id is not local_id"
+                }
+                UtilsError::InvalidVerificationMethod => {
+                    "the verification method is not in the did:web document, or has no \
+                     compressed secp256k1/P-256 publicKeyMultibase"
+                }
+                UtilsError::DidWebSignatureFailed => {
+                    "the did:web authorization is not a signature of the transaction hash by the \
+                     key of its verification method"
+                }
+                UtilsError::InvalidBinding => {
+                    "the localIdBinding of the document is not \"strict\" or \"superset\", or \
+                     local_id is not a did:plc"
+                }
+                UtilsError::BindingMismatch => {
+                    "the verificationMethods, alsoKnownAs or services of the document do not \
+                     match the did:plc state its localIdBinding requires"
+                }
+                UtilsError::InvalidRotationKeyCount => {
+                    "the rotationKeys of a did:plc operation must list 1 to 5 keys"
+                }
+                UtilsError::InvalidVerificationMethods => {
+                    "the verificationMethods of a did:plc operation is not a map of did:key strings"
+                }
+                UtilsError::InvalidAlsoKnownAs => {
+                    "the alsoKnownAs of a did:plc operation is not an array of at:// URIs"
+                }
+                UtilsError::InvalidService => {
+                    "the services of a did:plc operation is not a map of services with type and \
+                     endpoint strings"
+                }
+                UtilsError::InvalidCid => {
+                    "a CID is malformed, or not a CIDv1 of a supported codec and multihash"
+                }
+                UtilsError::TooManyKeyTrials => {
+                    "too many rotation keys failed to verify while trying the keys of unspecified \
+                     rotation key indices"
+                }
+                _ => "the did:plc authorization is invalid",
+            },
+            Error::Molecule => "the cell data or witness has a malformed molecule layout",
+            Error::InvalidDocumentCbor => "the document in the cell data is not valid CBOR",
+            Error::MismatchedFrom => "local_id must not change in an update",
+            Error::WitnessMissing => {
+                "the witness at index 0 of the script group is missing or empty"
+            }
+            Error::InvalidWitnessArgs => "the witness is not a valid WitnessArgs",
+            Error::AuthorizationMissing => {
+                "the WitnessArgs field holding the local ID authorization is empty"
+            }
+            Error::MisplacedAuthorization => {
+                "the local ID authorization is in input_type instead of output_type, or the \
+                 other way around"
+            }
+            Error::InvalidAuthorization => {
+                "the local ID authorization is not a valid DidCkbWitness, or DidCkbKeyWitness \
+                 for a did:key, DidCkbWebWitness for a did:web"
+            }
+            Error::UnresolvedInputs => {
+                "the resolved inputs do not match the inputs of the transaction one to one"
+            }
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "error code {}: {}",
+            self.error_code(),
+            self.explanation()
+        )?;
+        // with the `error_context` feature
+        if let Some(context) = self.context() {
+            write!(f, " ({})", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for Error {}
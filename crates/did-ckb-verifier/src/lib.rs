@@ -0,0 +1,17 @@
+//! Off-chain mirror of the did-ckb-ts type script.
+//!
+//! It applies the same mint, update and burn rules as the on-chain `entry()`
+//! to a transaction and its resolved inputs, so wallets and services can
+//! pre-flight a transaction before signing or submitting it.
+mod context;
+mod entry;
+pub mod error;
+mod molecules;
+
+pub use ckb_did_plc_utils::inspect::{Inspection, Step, StepKind};
+pub use ckb_did_plc_utils::state::{PlcState, Service};
+pub use context::ResolvedInput;
+pub use entry::{
+    inspect_authorization, resolve_authorization, verify_authorization, verify_script_group,
+    verify_transaction,
+};
@@ -0,0 +1,34 @@
+use crate::context::{ScriptContext, Source};
+use crate::error::{Error, SysError};
+use ckb_did_plc_utils::script::{GroupSource, ScriptGroup};
+use did_ckb_molecules::lazy_reader::{Cursor, new_cursor};
+
+// The script's rules run on the readers it shares with the script, backed by
+// the transaction instead of syscalls.
+impl ScriptGroup for ScriptContext<'_> {
+    type Error = Error;
+
+    fn load_data(&self, source: GroupSource) -> Result<Cursor, Error> {
+        let data = self.load_cell_data(0, group_source(source))?;
+        Ok(new_cursor(data.to_vec()))
+    }
+
+    fn load_witness(&self, source: GroupSource) -> Result<Option<Cursor>, Error> {
+        match ScriptContext::load_witness(self, 0, group_source(source)) {
+            Ok(witness) if !witness.is_empty() => Ok(Some(new_cursor(witness.to_vec()))),
+            Ok(_) | Err(SysError::IndexOutOfBound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn tx_hash(&self) -> Result<[u8; 32], Error> {
+        Ok(ScriptContext::tx_hash(self))
+    }
+}
+
+fn group_source(source: GroupSource) -> Source {
+    match source {
+        GroupSource::Input => Source::GroupInput,
+        GroupSource::Output => Source::GroupOutput,
+    }
+}
@@ -0,0 +1,7 @@
+#![no_main]
+use ckb_did_plc_utils_tests::fuzz::check_public_key;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    check_public_key(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,241 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Differential tests against PLC directory audit logs.
+//!
+//! Every `.json` file in `fixtures/plc-audit` holds the audit log of one DID,
+//! as served by `https://plc.directory/<did>/log/audit`, and every `.jsonl`
+//! file holds entries of any DIDs, as served by `/export`. Set
+//! `PLC_AUDIT_LOGS` to a file or directory to check other logs.
+//!
+//! Each operation is converted to DAG-CBOR and its CID and DID are recomputed.
+//! The operations that were not nullified are then verified as a history by
+//! `validate_operation_history`. Any divergence from the directory is reported.
+use crate::plc;
+use ckb_did_plc_utils::{
+    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    cbor4ii::core::Value,
+    dag_json::to_dag_cbor,
+    error::Error,
+    operation::{
+        Operation, parse_local_id, validate_2_operations, validate_genesis_operation,
+        validate_operation_history,
+    },
+};
+use did_ckb_molecules::lazy_reader::new_cursor;
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An entry of an audit log.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub did: String,
+    pub operation: Json,
+    pub cid: String,
+    pub nullified: bool,
+}
+
+impl Entry {
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let text = |name: &str| {
+            json[name]
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| format!("no {} in {}", name, json))
+        };
+        Ok(Self {
+            did: text("did")?,
+            operation: json["operation"].clone(),
+            cid: text("cid")?,
+            nullified: json["nullified"].as_bool().unwrap_or(false),
+        })
+    }
+}
+
+/// The audit logs in a `.json` or `.jsonl` file, by DID and in order.
+pub fn load(path: &Path) -> Result<BTreeMap<String, Vec<Entry>>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let entries: Vec<Json> = if path.extension().is_some_and(|e| e == "jsonl") {
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+    } else {
+        serde_json::from_str(&data)
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut logs: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    for entry in &entries {
+        let entry = Entry::from_json(entry)?;
+        logs.entry(entry.did.clone()).or_default().push(entry);
+    }
+    Ok(logs)
+}
+
+fn is_tombstone(operation: &Json) -> bool {
+    operation["type"] == "plc_tombstone"
+}
+
+// The index of the rotation key verifying `verify`, tried in order
+fn find_rotation_key_index(verify: impl Fn(usize) -> Result<(), Error>) -> Result<usize, Error> {
+    let mut index = 0;
+    loop {
+        match verify(index) {
+            Ok(()) => return Ok(index),
+            Err(Error::VerifySignatureFailed) => index += 1,
+            // none of the rotation keys
+            Err(Error::InvalidKeyIndex) if index > 0 => return Err(Error::VerifySignatureFailed),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The divergences between the audit log of `did` and this implementation.
+pub fn check(did: &str, log: &[Entry]) -> Vec<String> {
+    let mut divergences = vec![];
+    let mut history = vec![];
+    for (i, entry) in log.iter().enumerate() {
+        let op = match to_dag_cbor(&entry.operation) {
+            Ok(op) => op,
+            Err(e) => {
+                divergences.push(format!("{} operation {}: {}", did, i, e));
+                continue;
+            }
+        };
+        match Operation::from_slice(&op).and_then(|op| op.generate_cid()) {
+            Ok(cid) if cid == entry.cid => {}
+            Ok(cid) => divergences.push(format!(
+                "{} operation {}: CID {}, the directory has {}",
+                did, i, cid, entry.cid
+            )),
+            Err(e) => divergences.push(format!("{} operation {}: {:?}", did, i, e)),
+        }
+        // the history ends before a tombstone
+        if !entry.nullified && !is_tombstone(&entry.operation) {
+            history.push((i, op));
+        }
+    }
+    let Some((_, genesis)) = history.first() else {
+        return divergences;
+    };
+    let binary_did = match parse_local_id(did.as_bytes()) {
+        Ok(binary_did) => binary_did,
+        Err(e) => {
+            divergences.push(format!("{}: {:?}", did, e));
+            return divergences;
+        }
+    };
+    if let Some(derived) = plc::binary_did(genesis).map(|b| plc::did(&b)) {
+        if derived != did {
+            divergences.push(format!(
+                "{}: the genesis operation derives {}",
+                did, derived
+            ));
+        }
+    }
+
+    let mut rotation_key_indices = vec![];
+    let mut prev: Option<&[u8]> = None;
+    for (i, op) in &history {
+        let index = find_rotation_key_index(|index| match prev {
+            None => validate_genesis_operation(op, &binary_did, index),
+            Some(prev) => validate_2_operations(prev, op, index),
+        });
+        match index {
+            Ok(index) => rotation_key_indices.push(index),
+            Err(e) => {
+                divergences.push(format!("{} operation {}: {:?}", did, i, e));
+                return divergences;
+            }
+        }
+        prev = Some(op);
+    }
+    // The final authorization is not part of the log: the history is valid when
+    // only the missing signature is rejected.
+    rotation_key_indices.push(0);
+    let cursors = history.into_iter().map(|(_, op)| new_cursor(op)).collect();
+    match validate_operation_history(&binary_did, cursors, rotation_key_indices, &[0; 32], &[]) {
+        Err(Error::InvalidSignature) => {}
+        result => divergences.push(format!("{}: history validation gives {:?}", did, result)),
+    }
+    divergences
+}
+
+fn fixture_paths() -> Vec<PathBuf> {
+    let root = match env::var("PLC_AUDIT_LOGS") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/plc-audit"),
+    };
+    if root.is_file() {
+        return vec![root];
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(&root)
+        .map(|dir| dir.map(|entry| entry.unwrap().path()).collect())
+        .unwrap_or_default();
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|e| e == "json" || e == "jsonl")
+    });
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_audit_logs() {
+    let mut divergences = vec![];
+    let mut count = 0;
+    for path in fixture_paths() {
+        let logs = load(&path).unwrap();
+        for (did, log) in &logs {
+            count += log.len();
+            divergences.extend(
+                check(did, log)
+                    .into_iter()
+                    .map(|d| format!("{}: {}", path.display(), d)),
+            );
+        }
+    }
+    println!("checked {} operations", count);
+    assert!(
+        divergences.is_empty(),
+        "{} divergences:\n{}",
+        divergences.len(),
+        divergences.join("\n")
+    );
+}
+
+#[test]
+fn test_audit_log_divergences() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/plc-audit/synthetic.json");
+    let logs = load(&path).unwrap();
+    let (did, log) = logs.iter().next().unwrap();
+
+    let mut wrong_cid = log.clone();
+    wrong_cid[1].cid = wrong_cid[2].cid.clone();
+    let divergences = check(did, &wrong_cid);
+    assert_eq!(divergences.len(), 1);
+    assert!(divergences[0].contains("operation 1: CID"));
+
+    let mut wrong_sig = log.clone();
+    let mut sig = URL_SAFE_NO_PAD
+        .decode(wrong_sig[2].operation["sig"].as_str().unwrap())
+        .unwrap();
+    sig[0] ^= 1;
+    wrong_sig[2].operation["sig"] = Json::from(URL_SAFE_NO_PAD.encode(sig));
+    let divergences = check(did, &wrong_sig);
+    assert!(
+        divergences
+            .iter()
+            .any(|d| d.contains("operation 2: VerifySignatureFailed"))
+    );
+
+    // a nullified operation taking part in the history
+    let mut unnullified = log.clone();
+    for entry in unnullified.iter_mut() {
+        entry.nullified = false;
+    }
+    let divergences = check(did, &unnullified);
+    assert!(divergences.iter().any(|d| d.contains("InvalidPrev")));
+}
@@ -0,0 +1,243 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Cycles and memory of did-ckb-ts minting a did:ckb bound to a synthetic
+//! did:plc history, by history length, rotation key type and document size.
+//!
+//! `test_bench` runs the built script under ckb-vm and fails when a
+//! configuration uses more than `threshold` above `bench/baseline.json`. Run it
+//! with `BENCH_UPDATE=1` to record a new baseline.
+use crate::plc::{self, History, Key, text};
+use crate::tests::{Env, MAX_CYCLES, cell_data, mint_tx, with_witness};
+use ckb_did_plc_utils::cbor4ii::core::Value as Cbor;
+use ckb_testtool::ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_testtool::ckb_script::{
+    ROOT_VM_ID, RunMode, ScriptGroupType, TransactionScriptsVerifier, TxVerifyEnv,
+};
+use ckb_testtool::ckb_types::{
+    core::{
+        HeaderBuilder, TransactionView,
+        cell::{CellMetaBuilder, ResolvedTransaction},
+        hardfork::{CKB2021, CKB2023, HardForks},
+    },
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use ckb_vm::{CoreMachine, Memory, RISCV_PAGESIZE, memory::FLAG_DIRTY};
+use did_ckb_molecules::types::{DidCkbWitness, PlcAuthorization, WitnessArgs};
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Allowed growth over the baseline when the baseline doesn't set one.
+pub const DEFAULT_THRESHOLD: f64 = 0.05;
+
+#[derive(Clone, Copy, Debug)]
+pub enum KeyType {
+    Secp256k1,
+    P256,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub history_len: usize,
+    pub key_type: KeyType,
+    pub document_size: usize,
+}
+
+impl Config {
+    pub fn name(&self) -> String {
+        let key_type = match self.key_type {
+            KeyType::Secp256k1 => "secp256k1",
+            KeyType::P256 => "p256",
+        };
+        format!(
+            "{}-{}ops-{}b",
+            key_type, self.history_len, self.document_size
+        )
+    }
+}
+
+pub fn configs() -> Vec<Config> {
+    let mut configs = vec![];
+    for key_type in [KeyType::Secp256k1, KeyType::P256] {
+        for history_len in [1, 2, 4, 8, 16, 32] {
+            configs.push(Config {
+                history_len,
+                key_type,
+                document_size: 256,
+            });
+        }
+    }
+    for document_size in [1024, 16 * 1024, 64 * 1024] {
+        configs.push(Config {
+            history_len: 1,
+            key_type: KeyType::Secp256k1,
+            document_size,
+        });
+    }
+    configs
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    pub cycles: u64,
+    /// Bytes of VM memory written, counted in pages.
+    pub peak_memory: u64,
+}
+
+// A CBOR document of about `size` bytes
+fn document(size: usize) -> Vec<u8> {
+    plc::encode(&Cbor::Map(vec![(
+        text("padding"),
+        Cbor::Bytes(vec![0; size.saturating_sub(16)]),
+    )]))
+}
+
+// The same transaction the script verifies in `Context::verify_tx`
+fn resolve(context: &Context, tx: &TransactionView) -> ResolvedTransaction {
+    let resolve_cell = |out_point| {
+        let (output, data) = context.get_cell(&out_point).unwrap();
+        CellMetaBuilder::from_cell_output(output, data)
+            .out_point(out_point)
+            .build()
+    };
+    ResolvedTransaction {
+        transaction: tx.clone(),
+        resolved_cell_deps: tx
+            .cell_deps_iter()
+            .map(|dep| resolve_cell(dep.out_point()))
+            .collect(),
+        resolved_inputs: tx.input_pts_iter().map(resolve_cell).collect(),
+        resolved_dep_groups: vec![],
+    }
+}
+
+// Runs the did-ckb-ts type script of `tx`
+fn run(context: &Context, tx: &TransactionView) -> Result<Measurement, String> {
+    let consensus = ConsensusBuilder::default()
+        .hardfork_switch(HardForks {
+            ckb2021: CKB2021::new_dev_default(),
+            ckb2023: CKB2023::new_dev_default(),
+        })
+        .build();
+    let tip = HeaderBuilder::default().number(0.pack()).build();
+    let verifier = TransactionScriptsVerifier::new(
+        Arc::new(resolve(context, tx)),
+        context.clone(),
+        Arc::new(consensus),
+        Arc::new(TxVerifyEnv::new_submit(&tip)),
+    );
+    let (_, _, group) = verifier
+        .groups_with_type()
+        .find(|(group_type, _, _)| *group_type == ScriptGroupType::Type)
+        .ok_or("no type script group")?;
+    let mut scheduler = verifier
+        .create_scheduler(group)
+        .map_err(|e| e.to_string())?;
+    let (exit_code, cycles) = scheduler
+        .run(RunMode::LimitCycles(MAX_CYCLES))
+        .map_err(|e| e.to_string())?;
+    if exit_code != 0 {
+        return Err(format!("exit code {}", exit_code));
+    }
+    let (_, machine) = scheduler.instantiated.get_mut(&ROOT_VM_ID).unwrap();
+    let memory = machine.machine.memory_mut();
+    let pages = memory.memory_size() as u64 / RISCV_PAGESIZE as u64;
+    let mut dirty = 0;
+    for page in 0..pages {
+        if memory.fetch_flag(page).map_err(|e| e.to_string())? & FLAG_DIRTY != 0 {
+            dirty += 1;
+        }
+    }
+    Ok(Measurement {
+        cycles,
+        peak_memory: dirty * RISCV_PAGESIZE as u64,
+    })
+}
+
+/// Mints a did:ckb bound to a history of `config.history_len` operations and
+/// measures the script.
+pub fn measure(config: &Config) -> Result<Measurement, String> {
+    let key = match config.key_type {
+        KeyType::Secp256k1 => Key::secp256k1(1),
+        KeyType::P256 => Key::p256(1),
+    };
+    let history = History::new(vec![key], config.history_len);
+    let mut env = Env::new();
+    let data = cell_data(&document(config.document_size), Some(&history.did()));
+    let tx = mint_tx(&mut env, data);
+    let (rotation_key_indices, sig) = history.authorize(tx.hash().as_slice(), 0);
+    let witness = DidCkbWitness {
+        local_id_authorization: PlcAuthorization {
+            history: history.operations.clone(),
+            sig,
+            rotation_key_indices: rotation_key_indices.iter().map(|i| *i as u8).collect(),
+        },
+    };
+    let witness = WitnessArgs {
+        output_type: Some(witness.to_vec()),
+        ..Default::default()
+    };
+    let tx = with_witness(tx, witness.to_vec().into());
+    run(&env.context, &tx)
+}
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bench/baseline.json")
+}
+
+// Run with `cargo test -p ckb-did-plc-utils-tests --release bench -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn test_bench() {
+    let baseline: Value = fs::read(baseline_path())
+        .map(|b| serde_json::from_slice(&b).unwrap())
+        .unwrap_or_else(|_| json!({}));
+    let threshold = baseline["threshold"].as_f64().unwrap_or(DEFAULT_THRESHOLD);
+    let mut results = serde_json::Map::new();
+    let mut regressions = vec![];
+    println!("{:<24} {:>14} {:>14}", "config", "cycles", "peak memory");
+    for config in configs() {
+        let name = config.name();
+        let measurement = measure(&config).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        println!(
+            "{:<24} {:>14} {:>14}",
+            name, measurement.cycles, measurement.peak_memory
+        );
+        let base = &baseline["results"][&name];
+        for (metric, value) in [
+            ("cycles", measurement.cycles),
+            ("peak_memory", measurement.peak_memory),
+        ] {
+            let Some(base) = base[metric].as_u64() else {
+                continue;
+            };
+            if value as f64 > base as f64 * (1.0 + threshold) {
+                regressions.push(format!("{} {}: {} -> {}", name, metric, base, value));
+            }
+        }
+        results.insert(
+            name,
+            json!({
+                "cycles": measurement.cycles,
+                "peak_memory": measurement.peak_memory,
+            }),
+        );
+    }
+    if env::var("BENCH_UPDATE").is_ok() {
+        let baseline = json!({ "threshold": threshold, "results": results });
+        fs::write(
+            baseline_path(),
+            serde_json::to_string_pretty(&baseline).unwrap() + "\n",
+        )
+        .unwrap();
+    }
+    assert!(
+        regressions.is_empty(),
+        "over {}% above the baseline:\n{}",
+        threshold * 100.0,
+        regressions.join("\n")
+    );
+}
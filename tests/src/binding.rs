@@ -0,0 +1,231 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::cli::{INPUT, authorize_plc, cell, deployment, mint_document, verify};
+use crate::plc::{self, History, Key, text};
+use crate::verifier::{DID_CKB_CODE_HASH, hash};
+use ckb_did_plc_utils::{
+    binding::{Binding, read_binding},
+    cbor4ii::core::Value,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{ScriptHashType, TransactionView},
+    packed::OutPoint,
+    prelude::*,
+};
+use did_ckb_cli::{data, identifiers, tx};
+use did_ckb_molecules::types::{DidCkbData, KeyAuthorization, WitnessArgs};
+use did_ckb_verifier::{ResolvedInput, verify_transaction};
+
+// The document of the did:plc after the latest operation of `history`, bound
+// to it by `binding`, with `edit` applied to its fields.
+fn bound_document(
+    history: &History,
+    binding: &str,
+    edit: impl FnOnce(&mut Vec<(Value, Value)>),
+) -> Vec<u8> {
+    bound_state_document(history.operations.last().unwrap(), binding, edit)
+}
+
+// The document of the did:plc after `operation`, bound to it by `binding`,
+// with `edit` applied to its fields.
+pub(crate) fn bound_state_document(
+    operation: &[u8],
+    binding: &str,
+    edit: impl FnOnce(&mut Vec<(Value, Value)>),
+) -> Vec<u8> {
+    let mut fields = plc::decode_fields(operation).unwrap();
+    fields.retain(|(k, _)| {
+        [
+            text("verificationMethods"),
+            text("alsoKnownAs"),
+            text("services"),
+        ]
+        .contains(k)
+    });
+    fields.push((text("localIdBinding"), text(binding)));
+    edit(&mut fields);
+    plc::encode(&Value::Map(fields))
+}
+
+fn set(fields: &mut [(Value, Value)], name: &str, value: Value) {
+    for (k, v) in fields.iter_mut() {
+        if *k == text(name) {
+            *v = value.clone();
+        }
+    }
+}
+
+fn bound_mint(history: &History, document: &[u8]) -> TransactionView {
+    authorize_plc(mint_document(document, Some(&history.did())), history, 0)
+}
+
+// Updates the did:ckb minted by `minted` to `document`, without
+// re-authorization, with its resolved input.
+fn update(minted: &TransactionView, document: &[u8]) -> (TransactionView, Vec<ResolvedInput>) {
+    let input = OutPoint::new(minted.hash(), 0);
+    let did = identifiers::did_ckb(&identifiers::parse_out_point(INPUT).unwrap(), 0);
+    let DidCkbData::DidCkbDataV1(minted_data) =
+        DidCkbData::from_slice(&minted.outputs_data().get(0).unwrap().raw_data()).unwrap();
+    let data = data::encode(document, minted_data.local_id.as_deref()).unwrap();
+    let tx = tx::update(&deployment(), &input, &did, &cell(data)).unwrap();
+    let inputs = vec![ResolvedInput::new(
+        minted.output(0).unwrap(),
+        minted.outputs_data().get(0).unwrap().raw_data(),
+    )];
+    (tx, inputs)
+}
+
+// Moves the authorization of `tx` from `output_type` to `input_type`, the
+// witness of its input.
+fn in_input_type(tx: TransactionView) -> TransactionView {
+    let witness = WitnessArgs::from_slice(&tx.witnesses().get(0).unwrap().raw_data()).unwrap();
+    let witness = WitnessArgs {
+        input_type: witness.output_type,
+        ..Default::default()
+    };
+    tx.as_advanced_builder()
+        .set_witnesses(vec![Bytes::from(witness.to_vec()).pack()])
+        .build()
+}
+
+fn verify_update(tx: &TransactionView, inputs: &[ResolvedInput]) -> Result<(), i8> {
+    verify_transaction(tx, inputs, &hash(DID_CKB_CODE_HASH), ScriptHashType::Type)
+        .map_err(|(_, e)| e.error_code())
+}
+
+#[test]
+fn test_read_binding() {
+    let history = History::new(vec![Key::secp256k1(1)], 1);
+    for (binding, expected) in [("strict", Binding::Strict), ("superset", Binding::Superset)] {
+        let document = bound_document(&history, binding, |_| {});
+        assert_eq!(read_binding(&document).unwrap(), Some(expected));
+    }
+    let document = bound_document(&history, "strict", |fields| {
+        fields.retain(|(k, _)| *k != text("localIdBinding"))
+    });
+    assert_eq!(read_binding(&document).unwrap(), None);
+    let document = bound_document(&history, "loose", |_| {});
+    assert_eq!(read_binding(&document).unwrap_err().error_code(), 64);
+    let document = bound_document(&history, "strict", |fields| {
+        set(fields, "localIdBinding", Value::Bool(true))
+    });
+    assert_eq!(read_binding(&document).unwrap_err().error_code(), 64);
+}
+
+#[test]
+fn test_mint_bound_document() {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 3);
+    for binding in ["strict", "superset"] {
+        let tx = bound_mint(&history, &bound_document(&history, binding, |_| {}));
+        assert_eq!(verify(&tx), Ok(()), "{}", binding);
+    }
+
+    // more than the did:plc, only as a declared superset
+    let extended = |binding| {
+        bound_document(&history, binding, |fields| {
+            let handles = vec![
+                text("at://user2.example.com"),
+                text("at://alias.example.com"),
+            ];
+            set(fields, "alsoKnownAs", Value::Array(handles));
+        })
+    };
+    assert_eq!(verify(&bound_mint(&history, &extended("superset"))), Ok(()));
+    assert_eq!(verify(&bound_mint(&history, &extended("strict"))), Err(65));
+
+    // less than, or other than the did:plc
+    let other_key = Value::Map(vec![(text("atproto"), text(&Key::p256(3).did_key()))]);
+    for (name, value) in [
+        ("verificationMethods", other_key),
+        ("alsoKnownAs", Value::Array(vec![])),
+        ("services", Value::Map(vec![])),
+        ("services", Value::Integer(1)),
+    ] {
+        for binding in ["strict", "superset"] {
+            let document =
+                bound_document(&history, binding, |fields| set(fields, name, value.clone()));
+            let tx = bound_mint(&history, &document);
+            assert_eq!(verify(&tx), Err(65), "{} {}", binding, name);
+        }
+    }
+    // the state of an older operation
+    let older = History::new(history.keys.clone(), 2);
+    let tx = bound_mint(&history, &bound_document(&older, "strict", |_| {}));
+    assert_eq!(verify(&tx), Err(65));
+
+    // the authorization is checked as without a binding, here signed over
+    // another transaction
+    let tx = bound_mint(&history, &bound_document(&history, "strict", |_| {}));
+    let tx = tx::add_cell_deps(tx, &[deployment().cell_dep]);
+    assert_eq!(verify(&tx), Err(34));
+}
+
+#[test]
+fn test_mint_bound_document_without_did_plc() {
+    let history = History::new(vec![Key::secp256k1(1)], 1);
+    let document = bound_document(&history, "strict", |_| {});
+    assert_eq!(verify(&mint_document(&document, None)), Err(64));
+
+    let key = Key::secp256k1(1);
+    let tx = mint_document(&document, Some(&key.did_key()));
+    let sig = key.sign(tx.hash().as_slice());
+    let tx = tx::authorize_key(tx, KeyAuthorization { sig }).unwrap();
+    assert_eq!(verify(&tx), Err(64));
+
+    // the witness of a did:key for a did:plc
+    let tx = mint_document(&document, Some(&history.did()));
+    let sig = key.sign(tx.hash().as_slice());
+    let tx = tx::authorize_key(tx, KeyAuthorization { sig }).unwrap();
+    assert_eq!(verify(&tx), Err(58));
+}
+
+#[test]
+fn test_update_bound_document() {
+    let keys = vec![Key::secp256k1(1), Key::p256(2)];
+    let history = History::new(keys.clone(), 2);
+    let minted = bound_mint(&history, &bound_document(&history, "strict", |_| {}));
+    assert_eq!(verify(&minted), Ok(()));
+
+    // the did:plc changed its handle, the document follows with a new
+    // authorization
+    let updated = History::new(keys, 3);
+    let document = bound_document(&updated, "strict", |_| {});
+    let (tx, inputs) = update(&minted, &document);
+    assert_eq!(verify_update(&tx, &inputs), Err(54));
+    let tx = authorize_plc(tx, &updated, 1);
+    assert_eq!(verify_update(&tx, &inputs), Ok(()));
+    // or in input_type, next to the state it replaces
+    assert_eq!(verify_update(&in_input_type(tx), &inputs), Ok(()));
+
+    // a stale document against the latest state
+    let (tx, inputs) = update(&minted, &bound_document(&history, "strict", |_| {}));
+    assert_eq!(
+        verify_update(&authorize_plc(tx, &updated, 0), &inputs),
+        Err(65)
+    );
+
+    // dropping the binding needs the did:plc authorization too, but the
+    // unbound document is not compared to the did:plc
+    let unbound = bound_document(&history, "strict", |fields| {
+        fields.retain(|(k, _)| *k != text("localIdBinding"));
+        set(fields, "alsoKnownAs", Value::Array(vec![]));
+    });
+    let (tx, inputs) = update(&minted, &unbound);
+    assert_eq!(verify_update(&tx, &inputs), Err(54));
+    let tx = authorize_plc(tx, &updated, 1);
+    assert_eq!(verify_update(&tx, &inputs), Ok(()));
+    assert_eq!(verify_update(&in_input_type(tx), &inputs), Ok(()));
+    // by the did:plc of the local ID
+    let other = History::new(vec![Key::secp256k1(3)], 1);
+    let (tx, inputs) = update(&minted, &unbound);
+    assert_eq!(
+        verify_update(&authorize_plc(tx, &other, 0), &inputs),
+        Err(40)
+    );
+
+    // once unbound, updates need no authorization
+    let unbound_tx = authorize_plc(update(&minted, &unbound).0, &updated, 1);
+    let (tx, inputs) = update(&unbound_tx, &unbound);
+    assert_eq!(verify_update(&tx, &inputs), Ok(()));
+}
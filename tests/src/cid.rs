@@ -0,0 +1,143 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::plc::{self, Key, operation_fields, sign_operation, text};
+use crate::test_vectors::load_vector;
+use ckb_did_plc_utils::{
+    cbor4ii::core::Value,
+    cid::{Cid, Codec, Multihash},
+    error::Error,
+    operation::{validate_2_operations, validate_genesis_operation},
+};
+use std::str::FromStr;
+
+fn cid_of(op: &[u8]) -> Cid {
+    Cid::from_str(&plc::cid(op).unwrap()).unwrap()
+}
+
+// An operation with `prev` set to `prev`, signed by `key`, the only rotation
+// key of the history.
+fn next_operation(key: &Key, prev: Value) -> Vec<u8> {
+    let mut fields = operation_fields(&[key.clone()], None, "bob.test");
+    for (k, v) in fields.iter_mut() {
+        if *k == text("prev") {
+            *v = prev.clone();
+        }
+    }
+    sign_operation(fields, key)
+}
+
+#[test]
+fn test_cid_forms() {
+    let op = load_vector("1-did-creation.cbor");
+    let text = plc::cid(&op).unwrap();
+    let cid = Cid::from_str(&text).unwrap();
+    assert_eq!(cid.codec(), Codec::DagCbor);
+    assert_eq!(cid.hash().code(), 0x12);
+    assert_eq!(cid.to_string(), text);
+    assert_eq!(Cid::from_bytes(&cid.to_bytes()).unwrap(), cid);
+
+    // the same CID in other text forms
+    let upper = format!("B{}", text[1..].to_uppercase());
+    assert_eq!(Cid::from_str(&upper).unwrap(), cid);
+    let base58 = multibase::encode(multibase::Base::Base58Btc, cid.to_bytes());
+    assert_eq!(Cid::from_str(&base58).unwrap(), cid);
+
+    // the raw codec names the same bytes as another CID
+    let raw = Cid::new(Codec::Raw, Multihash::sha2_256(b"data"));
+    assert_eq!(Cid::from_str(&raw.to_string()).unwrap(), raw);
+    assert_eq!(raw.to_bytes()[..4], [0x01, 0x55, 0x12, 0x20]);
+    assert_ne!(raw, Cid::dag_cbor(b"data"));
+}
+
+#[test]
+fn test_invalid_cid() {
+    let cid = cid_of(&load_vector("1-did-creation.cbor"));
+    let bytes = cid.to_bytes();
+    let with = |edit: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = bytes.clone();
+        edit(&mut bytes);
+        bytes
+    };
+    for (name, bytes) in [
+        ("empty", vec![]),
+        ("CIDv0", with(&|b| b[0] = 0)),
+        ("dag-pb codec", with(&|b| b[1] = 0x70)),
+        ("sha2-512 multihash", with(&|b| b[2] = 0x13)),
+        ("short digest", with(&|b| b[3] = 0x1f)),
+        ("trailing byte", with(&|b| b.push(0))),
+        ("truncated", with(&|b| b.truncate(20))),
+        (
+            "non-minimal varint",
+            with(&|b| {
+                b.splice(1..2, [0xf1, 0x00]);
+            }),
+        ),
+    ] {
+        let e = Cid::from_bytes(&bytes).unwrap_err();
+        assert_eq!(e.error_code(), 70, "{}", name);
+    }
+
+    let text = cid.to_string();
+    for text in [
+        "",
+        "b",
+        // base64, not supported
+        "mAXESIA",
+        &format!("f{}", &text[1..]),
+        &format!("b{}!", &text[1..]),
+    ] {
+        let e = Cid::from_str(text).unwrap_err();
+        assert_eq!(e.error_code(), 70, "{}", text);
+    }
+}
+
+#[test]
+fn test_prev_forms() {
+    let key = Key::secp256k1(1);
+    let genesis = sign_operation(operation_fields(&[key.clone()], None, "alice.test"), &key);
+    let cid = cid_of(&genesis);
+
+    let mut link = vec![0];
+    link.extend(cid.to_bytes());
+    for prev in [
+        text(&cid.to_string()),
+        text(&format!("B{}", cid.to_string()[1..].to_uppercase())),
+        text(&multibase::encode(
+            multibase::Base::Base58Btc,
+            cid.to_bytes(),
+        )),
+        Value::Bytes(cid.to_bytes()),
+        Value::Tag(42, Box::new(Value::Bytes(link.clone()))),
+    ] {
+        let op = next_operation(&key, prev.clone());
+        assert!(
+            validate_2_operations(&genesis, &op, 0).is_ok(),
+            "{:?}",
+            prev
+        );
+        // still not a genesis operation
+        let result = validate_genesis_operation(&op, &plc::binary_did(&op).unwrap(), 0);
+        assert!(matches!(result, Err(Error::NotGenesisOperation)));
+    }
+
+    // another CID of the same bytes is another `prev`
+    let raw = Cid::new(Codec::Raw, cid.hash().clone());
+    let op = next_operation(&key, text(&raw.to_string()));
+    let result = validate_2_operations(&genesis, &op, 0);
+    assert!(matches!(result, Err(Error::InvalidPrev)));
+
+    // a malformed CID
+    for prev in [
+        text("bafyrei"),
+        Value::Bytes(vec![1, 0x71]),
+        Value::Tag(42, Box::new(Value::Bytes(cid.to_bytes()))),
+        Value::Tag(42, Box::new(text(&cid.to_string()))),
+    ] {
+        let op = next_operation(&key, prev.clone());
+        let e = validate_2_operations(&genesis, &op, 0).unwrap_err();
+        assert_eq!(e.error_code(), 70, "{:?}", prev);
+    }
+    let op = next_operation(&key, Value::Integer(1));
+    let result = validate_2_operations(&genesis, &op, 0);
+    assert!(matches!(result, Err(Error::InvalidOperation)));
+}
@@ -0,0 +1,201 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::plc::{History, Key};
+use crate::test_vectors::load_did;
+use crate::vectors;
+use crate::verifier::{DID_CKB_CODE_HASH, SPEC_DATA_0, bytes, hash};
+use ckb_types::{
+    bytes::Bytes,
+    core::{DepType, ScriptHashType, TransactionView},
+    packed::{CellDep, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use did_ckb_cli::{
+    data, identifiers,
+    tx::{self, Cell, Deployment},
+};
+use did_ckb_molecules::types::{DidCkbData, KeyAuthorization, PlcAuthorization};
+use did_ckb_verifier::{ResolvedInput, verify_authorization, verify_transaction};
+use serde_json::json;
+
+pub(crate) const INPUT: &str =
+    "0x1ecbf88d692a14d7cbc0bfd1a3d5019e4b613247ae438bad52f94148c6009559:3";
+
+pub(crate) fn deployment() -> Deployment {
+    Deployment {
+        code_hash: hash(DID_CKB_CODE_HASH),
+        cell_dep: CellDep::new_builder()
+            .out_point(OutPoint::new(hash(DID_CKB_CODE_HASH), 0))
+            .dep_type(DepType::Code.into())
+            .build(),
+    }
+}
+
+fn lock() -> Script {
+    Script::new_builder()
+        .code_hash([1u8; 32].pack())
+        .hash_type(ScriptHashType::Type.into())
+        .build()
+}
+
+pub(crate) fn cell(data: Vec<u8>) -> Cell {
+    Cell {
+        lock: lock(),
+        capacity: 1000 * 100_000_000,
+        data: data.into(),
+    }
+}
+
+fn inputs() -> Vec<ResolvedInput> {
+    let output = CellOutput::new_builder()
+        .capacity((2000 * 100_000_000u64).pack())
+        .lock(lock())
+        .build();
+    vec![ResolvedInput::new(output, Bytes::new())]
+}
+
+pub(crate) fn verify(tx: &TransactionView) -> Result<(), i8> {
+    verify_transaction(
+        tx,
+        &inputs(),
+        &hash(DID_CKB_CODE_HASH),
+        ScriptHashType::Type,
+    )
+    .map_err(|(_, e)| e.error_code())
+}
+
+// Mints a did:ckb bound to `local_id`, without its authorization.
+pub(crate) fn unauthorized_mint(local_id: &str) -> TransactionView {
+    let document = bytes(SPEC_DATA_0);
+    let DidCkbData::DidCkbDataV1(spec) = DidCkbData::from_slice(&document).unwrap();
+    mint_document(&spec.document, Some(local_id))
+}
+
+// Mints a did:ckb with `document`, bound to `local_id` if any, without its
+// authorization.
+pub(crate) fn mint_document(document: &[u8], local_id: Option<&str>) -> TransactionView {
+    let data = data::encode(document, local_id).unwrap();
+    let input = identifiers::parse_out_point(INPUT).unwrap();
+    tx::mint(&deployment(), &[input], &cell(data)).unwrap()
+}
+
+// Mints a did:ckb bound to `history` and authorizes it with the rotation key
+// at `index`.
+pub(crate) fn authorized_mint(history: &History, index: usize) -> TransactionView {
+    authorize_plc(unauthorized_mint(&history.did()), history, index)
+}
+
+// Authorizes `tx` with `history` and the rotation key at `index`.
+pub(crate) fn authorize_plc(
+    tx: TransactionView,
+    history: &History,
+    index: usize,
+) -> TransactionView {
+    let (rotation_key_indices, sig) = history.authorize(tx.hash().as_slice(), index);
+    let authorization = PlcAuthorization {
+        history: history.operations.clone(),
+        sig,
+        rotation_key_indices: rotation_key_indices.iter().map(|i| *i as u8).collect(),
+    };
+    tx::authorize(tx, authorization).unwrap()
+}
+
+// Mints a did:ckb bound to the did:key of `key`, authorized by `signer`.
+fn key_mint(key: &Key, signer: &Key) -> TransactionView {
+    let tx = unauthorized_mint(&key.did_key());
+    let sig = signer.sign(tx.hash().as_slice());
+    tx::authorize_key(tx, KeyAuthorization { sig }).unwrap()
+}
+
+fn witness(tx: &TransactionView) -> Vec<u8> {
+    tx.witnesses().get(0).unwrap().raw_data().to_vec()
+}
+
+#[test]
+fn test_mint_preflight() {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 3);
+    let tx = authorized_mint(&history, 1);
+    assert_eq!(verify(&tx), Ok(()));
+    verify_authorization(&witness(&tx), &history.did(), &tx.hash().unpack()).unwrap();
+
+    // the authorization signs the transaction hash, which the witness is not
+    // part of
+    let other = tx::add_cell_deps(tx.clone(), &[deployment().cell_dep]);
+    let e =
+        verify_authorization(&witness(&other), &history.did(), &other.hash().unpack()).unwrap_err();
+    assert_eq!(e.error_code(), 34);
+}
+
+#[test]
+fn test_mint_did_key_preflight() {
+    for key in [Key::secp256k1(1), Key::p256(2)] {
+        let tx = key_mint(&key, &key);
+        assert_eq!(verify(&tx), Ok(()));
+        verify_authorization(&witness(&tx), &key.did_key(), &tx.hash().unpack()).unwrap();
+
+        let tx = key_mint(&key, &Key::secp256k1(3));
+        assert_eq!(verify(&tx), Err(48));
+    }
+
+    // a did:plc authorization for a did:key local ID
+    let key = Key::secp256k1(1);
+    let history = History::new(vec![key.clone()], 1);
+    let tx = authorized_mint(&history, 0);
+    let e = verify_authorization(&witness(&tx), &key.did_key(), &tx.hash().unpack()).unwrap_err();
+    assert_eq!(e.error_code(), 58);
+}
+
+#[test]
+fn test_derived_did_ckb() {
+    let history = History::new(vec![Key::secp256k1(1)], 1);
+    let tx = authorized_mint(&history, 0);
+    let args = tx.output(0).unwrap().type_().to_opt().unwrap().args();
+    let input = identifiers::parse_out_point(INPUT).unwrap();
+    assert_eq!(
+        identifiers::did_ckb(&input, 0),
+        did_ckb_indexer::resolver::did_from_key(&args.raw_data()[..].try_into().unwrap())
+    );
+}
+
+#[test]
+fn test_derived_did_plc() {
+    let did = load_did("creation");
+    let genesis = vectors::get("1-did-creation.cbor");
+    assert_eq!(identifiers::did_plc(&genesis).unwrap(), did);
+}
+
+#[test]
+fn test_data_roundtrip() {
+    let DidCkbData::DidCkbDataV1(spec) = DidCkbData::from_slice(&bytes(SPEC_DATA_0)).unwrap();
+    let did = load_did("creation");
+    let data = data::encode(&spec.document, Some(&did)).unwrap();
+    let decoded = data::decode(&data).unwrap();
+    assert_eq!(decoded["localId"], json!(did));
+    assert_eq!(
+        decoded["document"]["alsoKnownAs"],
+        json!(["at://alice.test"])
+    );
+
+    assert!(data::encode(b"\xff", None).is_err());
+    assert!(data::encode(&spec.document, Some("did:ion:example")).is_err());
+}
+
+#[test]
+fn test_capacity_check() {
+    let mut small = cell(vec![0; 100]);
+    small.capacity = 100;
+    let input = identifiers::parse_out_point(INPUT).unwrap();
+    assert!(tx::mint(&deployment(), &[input], &small).is_err());
+}
+
+#[test]
+fn test_ckb_cli_json_roundtrip() {
+    let history = History::new(vec![Key::secp256k1(1)], 2);
+    let tx = authorized_mint(&history, 0);
+    let json = tx::to_ckb_cli_json(&tx);
+    assert_eq!(json["signatures"], json!({}));
+    let parsed = tx::from_ckb_cli_json(&json).unwrap();
+    assert_eq!(parsed.hash(), tx.hash());
+    let bare = tx::from_ckb_cli_json(&json["transaction"]).unwrap();
+    assert_eq!(bare.hash(), tx.hash());
+}
@@ -0,0 +1,126 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::audit_log::load;
+use crate::plc::{self, Key, encode, operation_fields, sign_operation, text};
+use crate::vectors;
+use ckb_did_plc_utils::{
+    cbor4ii::core::Value,
+    cid::{CID_TAG, Cid},
+    dag_json::{to_dag_cbor, to_dag_json},
+    error::Error,
+    operation::{Operation, validate_2_operations},
+};
+use serde_json::json;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[test]
+fn test_vectors_round_trip() {
+    let mut count = 0;
+    for (name, op) in vectors::generate() {
+        if !name.ends_with(".cbor") {
+            continue;
+        }
+        let json = to_dag_json(&op).unwrap();
+        assert_eq!(to_dag_cbor(&json).unwrap(), op, "{}", name);
+        assert_eq!(json["sig"], text_of(&op, "sig"), "{}", name);
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+fn text_of(op: &[u8], name: &str) -> String {
+    let fields = plc::decode_fields(op).unwrap();
+    match &fields.iter().find(|(k, _)| *k == text(name)).unwrap().1 {
+        Value::Text(s) => s.clone(),
+        _ => panic!("not a text"),
+    }
+}
+
+#[test]
+fn test_audit_log_round_trip() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/plc-audit/synthetic.json");
+    for (_, log) in load(&path).unwrap() {
+        for entry in log {
+            let op = to_dag_cbor(&entry.operation).unwrap();
+            let cid = Operation::from_slice(&op).unwrap().generate_cid().unwrap();
+            assert_eq!(cid, entry.cid);
+            assert_eq!(to_dag_json(&op).unwrap(), entry.operation);
+        }
+    }
+}
+
+#[test]
+fn test_links_and_bytes() {
+    let key = Key::secp256k1(1);
+    let genesis = sign_operation(operation_fields(&[key.clone()], None, "alice.test"), &key);
+    let cid = Operation::from_slice(&genesis).unwrap().cid().unwrap();
+
+    // `prev` as a link
+    let mut fields = operation_fields(&[key.clone()], None, "bob.test");
+    for (k, v) in fields.iter_mut() {
+        if *k == text("prev") {
+            *v = Value::Tag(CID_TAG, Box::new(Value::Bytes(cid.to_link())));
+        }
+    }
+    let op = sign_operation(fields, &key);
+    validate_2_operations(&genesis, &op, 0).unwrap();
+    let json = to_dag_json(&op).unwrap();
+    assert_eq!(json["prev"], json!({ "/": cid.to_string() }));
+    assert_eq!(to_dag_cbor(&json).unwrap(), op);
+
+    let json = json!({ "data": { "/": { "bytes": "AAEC/w" } } });
+    let cbor = to_dag_cbor(&json).unwrap();
+    assert_eq!(
+        plc::decode_fields(&cbor).unwrap(),
+        vec![(text("data"), Value::Bytes(vec![0, 1, 2, 0xff]))]
+    );
+    assert_eq!(to_dag_json(&cbor).unwrap(), json);
+}
+
+#[test]
+fn test_canonical_order() {
+    let json = json!({ "sig": "", "type": "", "prev": null, "rotationKeys": [], "a": 1 });
+    let cbor = to_dag_cbor(&json).unwrap();
+    let keys: Vec<Value> = plc::decode_fields(&cbor)
+        .unwrap()
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    assert_eq!(keys, ["a", "sig", "prev", "type", "rotationKeys"].map(text));
+}
+
+#[test]
+fn test_invalid_conversion() {
+    for json in [
+        json!({ "n": 1.5 }),
+        json!({ "/": 1 }),
+        json!({ "/": { "bytes": "!" } }),
+        json!({ "/": { "bytes": "AA", "more": "" } }),
+    ] {
+        let e = to_dag_cbor(&json).unwrap_err();
+        assert!(matches!(e.root(), Error::InvalidOperation), "{}", json);
+    }
+    let e = to_dag_cbor(&json!({ "/": "bafy" })).unwrap_err();
+    assert!(matches!(e.root(), Error::InvalidCid));
+
+    // the JSON of anything but canonical DAG-CBOR wouldn't give its bytes back
+    let key = Key::secp256k1(1);
+    let unsorted = encode(&Value::Map(operation_fields(&[key], None, "alice.test")));
+    let mut trailing = vectors::get("1-did-creation.cbor");
+    trailing.push(0);
+    for cbor in [
+        unsorted,
+        trailing,
+        encode(&Value::Float(1.0)),
+        encode(&Value::Map(vec![(Value::Integer(1), Value::Null)])),
+        encode(&Value::Tag(1, Box::new(Value::Null))),
+        encode(&Value::Map(vec![(
+            text("/"),
+            text(&Cid::dag_cbor(b"").to_string()),
+        )])),
+    ] {
+        let e = to_dag_json(&cbor).unwrap_err();
+        assert!(matches!(e.root(), Error::InvalidCbor), "{:?}", cbor);
+    }
+}
@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Errors with the `error_context` feature, run with
+//! `cargo test -p ckb-did-plc-utils-tests --features error_context error_context`.
+use crate::plc;
+use crate::test_vectors::{load_did, load_vector};
+use crate::vectors::{self, Variant};
+use ckb_did_plc_utils::{
+    error::{Context, Error},
+    inspect::{Inspection, inspect_operation_history},
+    operation::{
+        UNSPECIFIED_KEY_INDEX, parse_local_id, validate_2_operations, validate_operation_history,
+    },
+};
+use did_ckb_molecules::lazy_reader::new_cursor;
+
+fn history() -> Vec<Vec<u8>> {
+    [
+        "1-did-creation.cbor",
+        "2-update-handle.cbor",
+        "3-update-pds.cbor",
+        "4-update-atproto-key.cbor",
+        "5-update-rotation-keys.cbor",
+        "6-update-handle.cbor",
+    ]
+    .iter()
+    .map(|name| load_vector(name))
+    .collect()
+}
+
+fn validate_history(history: &[Vec<u8>], rotation_key_indices: Vec<usize>) -> Error {
+    let binary_did = parse_local_id(load_did("creation").as_bytes()).unwrap();
+    let msg = [0u8; 32];
+    let sig = vectors::p256_key().sign(&msg);
+    let cursors = history.iter().cloned().map(new_cursor).collect();
+    validate_operation_history(&binary_did, cursors, rotation_key_indices, &msg, &sig).unwrap_err()
+}
+
+fn inspect_history(history: &[Vec<u8>], rotation_key_indices: Vec<usize>) -> Inspection {
+    let binary_did = parse_local_id(load_did("creation").as_bytes()).unwrap();
+    let msg = [0u8; 32];
+    let sig = vectors::p256_key().sign(&msg);
+    let cursors = history.iter().cloned().map(new_cursor).collect();
+    inspect_operation_history(&binary_did, cursors, rotation_key_indices, &msg, &sig)
+}
+
+#[test]
+fn test_error_context_invalid_prev() {
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = vectors::variant(&load_vector("2-update-handle.cbor"), Variant::BadCid);
+    let e = validate_2_operations(&prev, &cur, 0).unwrap_err();
+    assert!(matches!(e.root(), Error::InvalidPrev));
+    assert_eq!(e.error_code(), 37);
+    let context = e.context().unwrap();
+    assert_eq!(context.field, Some("prev"));
+    assert_eq!(context.expected, plc::cid(&prev));
+    assert_eq!(
+        context.actual,
+        plc::cid(&load_vector("2-update-handle.cbor"))
+    );
+}
+
+#[test]
+fn test_error_context_history_index() {
+    let mut history = history();
+    history[3] = vectors::variant(&history[3], Variant::WrongType);
+    let e = validate_history(&history, vec![0, 0, 0, 0, 0, 1, 1]);
+    assert!(matches!(e.root(), Error::InvalidOperation));
+    assert_eq!(e.error_code(), 31);
+    assert_eq!(
+        e.context(),
+        Some(&Context {
+            history_index: Some(3),
+            field: Some("type"),
+            ..Default::default()
+        })
+    );
+    assert_eq!(e.to_string(), "InvalidOperation (operation 3, field type)");
+
+    let e = validate_history(&history[..2], vec![0, 0]);
+    assert_eq!(e.error_code(), 43);
+    let context = e.context().unwrap();
+    assert_eq!(context.expected.as_deref(), Some("3"));
+    assert_eq!(context.actual.as_deref(), Some("2"));
+}
+
+#[test]
+fn test_error_context_rotation_key() {
+    // operation 5 is signed by the p256 key at index 1
+    let e = validate_history(&history(), vec![0, 0, 0, 0, 0, 0, 1]);
+    assert!(matches!(e.root(), Error::VerifySignatureFailed));
+    let context = e.context().unwrap();
+    assert_eq!(context.history_index, Some(5));
+    assert_eq!(context.key_index, Some(0));
+    assert_eq!(context.key, Some(vectors::secp256k1_key(3).did_key()));
+
+    // the final authorization
+    let e = validate_history(&history(), vec![0, 0, 0, 0, 0, 1, 2]);
+    assert!(matches!(e.root(), Error::InvalidKeyIndex));
+    let context = e.context().unwrap();
+    assert_eq!(context.history_index, Some(6));
+    assert_eq!(context.key_index, Some(2));
+
+    // none of the keys tried for an unspecified index
+    let mut history = history();
+    history[5] = vectors::variant(&history[5], Variant::BadSignature);
+    let e = validate_history(&history, vec![0, 0, 0, 0, 0, UNSPECIFIED_KEY_INDEX, 1]);
+    assert!(matches!(e.root(), Error::VerifySignatureFailed));
+    let context = e.context().unwrap();
+    assert_eq!(context.history_index, Some(5));
+    assert_eq!(context.key_index, Some(UNSPECIFIED_KEY_INDEX));
+    assert_eq!(context.key, None);
+}
+
+#[test]
+fn test_error_context_inspect() {
+    // an inspection reports the errors of a validation, with their context
+    let mut wrong_type = history();
+    wrong_type[3] = vectors::variant(&wrong_type[3], Variant::WrongType);
+    for (history, rotation_key_indices) in [
+        (wrong_type, vec![0, 0, 0, 0, 0, 1, 1]),
+        (history(), vec![0, 0, 0, 0, 0, 0, 1]),
+        (history(), vec![0, 0, 0, 0, 0, 1, 2]),
+        (history()[..2].to_vec(), vec![0, 0]),
+    ] {
+        let expected = validate_history(&history, rotation_key_indices.clone());
+        let inspection = inspect_history(&history, rotation_key_indices);
+        let e = inspection.error().unwrap();
+        assert_eq!(e.error_code(), expected.error_code());
+        assert_eq!(e.context(), expected.context());
+        assert!(e.context().is_some());
+    }
+}
+
+#[test]
+fn test_error_context_genesis() {
+    let history = history();
+    let binary_did = parse_local_id(load_did("creation-legacy").as_bytes()).unwrap();
+    let cursors = vec![new_cursor(history[0].clone())];
+    let e =
+        validate_operation_history(&binary_did, cursors, vec![0, 0], &[0; 32], &[]).unwrap_err();
+    assert!(matches!(e.root(), Error::DidMismatched));
+    let context = e.context().unwrap();
+    assert_eq!(context.history_index, Some(0));
+    assert_eq!(context.expected, Some(load_did("creation-legacy")));
+    assert_eq!(context.actual, Some(load_did("creation")));
+
+    let e = parse_local_id(b"did:web:example.com").unwrap_err();
+    assert_eq!(e.error_code(), 46);
+    assert_eq!(
+        e.context().unwrap().actual.as_deref(),
+        Some("did:web:example.com")
+    );
+}
+
+#[test]
+fn test_error_context_verifier() {
+    let e = did_ckb_verifier::error::Error::from(validate_history(
+        &history(),
+        vec![0, 0, 0, 0, 0, 0, 1],
+    ));
+    assert_eq!(e.error_code(), 36);
+    assert!(e.to_string().ends_with(&format!(
+        "(operation 5, rotation key index 0, key {})",
+        vectors::secp256k1_key(3).did_key()
+    )));
+}
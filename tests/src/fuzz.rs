@@ -0,0 +1,408 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Properties of the parsers that read witness bytes. They are checked by the
+//! proptest generators below and by the cargo-fuzz targets in `fuzz/`, whose
+//! crash artifacts are replayed by `test_crash_corpora`.
+use crate::plc::{self, History, Key, text};
+use ckb_did_plc_utils::{
+    cbor4ii::core::{Value, dec::Decode, utils::SliceReader},
+    error::Error,
+    operation::{
+        Operation, parse_local_id, validate_2_operations, validate_genesis_operation,
+        validate_operation_history,
+    },
+    pubkey::PublicKey,
+};
+use did_ckb_molecules::lazy_reader::{self, Cursor, new_cursor};
+use did_ckb_molecules::types::PlcAuthorization;
+use proptest::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The fields of a `plc_operation`.
+pub const FIELDS: [&str; 7] = [
+    "type",
+    "rotationKeys",
+    "verificationMethods",
+    "alsoKnownAs",
+    "services",
+    "prev",
+    "sig",
+];
+
+/// A change to one field of an operation before it is re-signed.
+#[derive(Clone, Debug)]
+pub enum Mutation {
+    /// Replaces the field, or adds it when missing
+    Set(String, Value),
+    Remove(String),
+}
+
+impl Mutation {
+    pub fn apply(&self, fields: &mut Vec<(Value, Value)>) {
+        match self {
+            Mutation::Set(name, value) => match fields.iter_mut().find(|(k, _)| *k == text(name)) {
+                Some((_, v)) => *v = value.clone(),
+                None => fields.push((text(name), value.clone())),
+            },
+            Mutation::Remove(name) => fields.retain(|(k, _)| *k != text(name)),
+        }
+    }
+
+    // Some(true) when the re-signed operation must verify, Some(false) when it
+    // must be rejected
+    fn expected(&self) -> Option<bool> {
+        match self {
+            // re-signing replaces the signature
+            Mutation::Set(name, _) | Mutation::Remove(name) if name == "sig" => Some(true),
+            Mutation::Set(name, _) => (!FIELDS.contains(&name.as_str())).then_some(true),
+            Mutation::Remove(name) => Some(!FIELDS.contains(&name.as_str())),
+        }
+    }
+}
+
+pub fn cursors(operations: &[Vec<u8>]) -> Vec<Cursor> {
+    operations.iter().map(|op| new_cursor(op.clone())).collect()
+}
+
+fn assert_typed(result: Result<(), Error>) {
+    if let Err(e) = result {
+        // the operation errors, including the schema ones
+        assert!(
+            (31..=46).contains(&e.error_code()) || (66..=71).contains(&e.error_code()),
+            "{:?} has no utils error code",
+            e
+        );
+    }
+}
+
+pub fn check_operation(data: &[u8]) {
+    if let Ok(op) = Operation::from_slice(data) {
+        let _ = op.generate_cid();
+    }
+    assert_typed(validate_genesis_operation(data, &[0; 15], 0));
+    assert_typed(validate_2_operations(data, data, 0));
+}
+
+pub fn check_public_key(key: &str) {
+    match PublicKey::from_str(key) {
+        Ok(key) => assert_typed(key.verify(b"msg", &[1; 64])),
+        Err(e) => assert_typed(Err(e)),
+    }
+}
+
+pub fn check_local_id(id: &[u8]) {
+    match parse_local_id(id) {
+        Ok(_) => assert!(id.starts_with(b"did:plc:")),
+        Err(e) => assert_typed(Err(e)),
+    }
+}
+
+/// Reads a molecule `PlcAuthorization` the way the script does and validates
+/// it for the did:plc of its first operation.
+pub fn check_authorization(blob: &[u8]) {
+    let auth = lazy_reader::PlcAuthorization::from(new_cursor(blob.to_vec()));
+    if auth.verify(false).is_err() {
+        return;
+    }
+    let (Ok(history), Ok(sig), Ok(indices)) =
+        (auth.history(), auth.sig(), auth.rotation_key_indices())
+    else {
+        return;
+    };
+    let Ok(history) = (0..history.len().unwrap())
+        .map(|i| history.get(i))
+        .collect::<Result<Vec<Cursor>, _>>()
+    else {
+        return;
+    };
+    let (Ok(sig), Ok(indices)) = (Vec::<u8>::try_from(sig), Vec::<u8>::try_from(indices)) else {
+        return;
+    };
+    let binary_did = history
+        .first()
+        .and_then(|op| Vec::<u8>::try_from(op.clone()).ok())
+        .and_then(|op| plc::binary_did(&op))
+        .unwrap_or_default();
+    let indices = indices.into_iter().map(usize::from).collect();
+    assert_typed(
+        validate_operation_history(&binary_did, history, indices, &[0; 32], &sig).map(|_| ()),
+    );
+}
+
+/// Applies `mutation` to operation `target` of `history`, re-signs it with the
+/// same key and validates the history up to it.
+pub fn check_resigned(history: &History, target: usize, mutation: &Mutation) {
+    let mut fields = plc::decode_fields(&history.operations[target]).unwrap();
+    mutation.apply(&mut fields);
+    let signer = &history.keys[history.rotation_key_indices[target]];
+    let mut operations = history.operations[..target].to_vec();
+    operations.push(plc::sign_operation(fields, signer));
+    // a mutated genesis operation creates another did:plc
+    let binary_did = plc::binary_did(&operations[0]).unwrap();
+
+    let msg = [0u8; 32];
+    let mut indices = history.rotation_key_indices[..=target].to_vec();
+    indices.push(0);
+    let sig = history.keys[0].sign(&msg);
+    let result = validate_operation_history(&binary_did, cursors(&operations), indices, &msg, &sig);
+    match mutation.expected() {
+        Some(true) => assert!(result.is_ok(), "{:?} after {:?}", result, mutation),
+        Some(false) => assert!(result.is_err(), "{:?} verified", mutation),
+        None => assert_typed(result.map(|_| ())),
+    }
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(|i| Value::Integer(i.into())),
+        ".{0,16}".prop_map(Value::Text),
+        prop::collection::vec(any::<u8>(), 0..16).prop_map(Value::Bytes),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            prop::collection::vec((field(), inner), 0..4)
+                .prop_map(|m| Value::Map(m.into_iter().map(|(k, v)| (text(&k), v)).collect())),
+        ]
+    })
+}
+
+fn field() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(&FIELDS[..]).prop_map(str::to_owned),
+        "x-[a-z]{1,8}",
+    ]
+}
+
+fn key() -> impl Strategy<Value = Key> {
+    (any::<bool>(), 1u8..=255).prop_map(|(secp256k1, seed)| {
+        if secp256k1 {
+            Key::secp256k1(seed)
+        } else {
+            Key::p256(seed)
+        }
+    })
+}
+
+/// CBOR maps made of operation fields, and arbitrary bytes.
+pub fn operation_bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec((field(), value()), 0..8).prop_map(|fields| {
+            plc::encode(&Value::Map(
+                fields.into_iter().map(|(k, v)| (text(&k), v)).collect(),
+            ))
+        }),
+        prop::collection::vec(any::<u8>(), 0..128),
+    ]
+}
+
+/// Valid did:keys, did:keys with any multicodec prefix and length, and
+/// arbitrary strings.
+pub fn did_key() -> impl Strategy<Value = String> {
+    prop_oneof![
+        key().prop_map(|k| k.did_key()),
+        (
+            prop::sample::select(vec![vec![0xe7, 0x01], vec![0x80, 0x24], vec![0xe7]]),
+            prop::collection::vec(any::<u8>(), 0..40)
+        )
+            .prop_map(|(mut prefix, key)| {
+                prefix.extend(key);
+                format!(
+                    "did:key:{}",
+                    multibase::encode(multibase::Base::Base58Btc, prefix)
+                )
+            }),
+        "did:key:z[1-9A-HJ-NP-Za-km-z]{0,8}",
+        ".{0,64}",
+    ]
+}
+
+pub fn local_id() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        "did:plc:[a-z2-7]{0,30}".prop_map(String::into_bytes),
+        prop::collection::vec(any::<u8>(), 0..40),
+    ]
+}
+
+/// `PlcAuthorization`s with valid and arbitrary operations, some of them
+/// corrupted by overwriting bytes or truncating.
+pub fn authorization() -> impl Strategy<Value = Vec<u8>> {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 3);
+    let operation = prop_oneof![prop::sample::select(history.operations), operation_bytes(),];
+    (
+        prop::collection::vec(operation, 0..4),
+        prop::collection::vec(any::<u8>(), 0..72),
+        prop::collection::vec(0u8..4, 0..6),
+        prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 0..3),
+        prop::option::of(any::<prop::sample::Index>()),
+    )
+        .prop_map(|(history, sig, rotation_key_indices, writes, truncate)| {
+            let mut blob = PlcAuthorization {
+                history,
+                sig,
+                rotation_key_indices,
+            }
+            .to_vec();
+            for (index, byte) in writes {
+                let index = index.index(blob.len());
+                blob[index] = byte;
+            }
+            if let Some(index) = truncate {
+                blob.truncate(index.index(blob.len()));
+            }
+            blob
+        })
+}
+
+pub fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (field(), value()).prop_map(|(name, value)| Mutation::Set(name, value)),
+        (
+            prop::sample::select(&FIELDS[..]).prop_map(str::to_owned),
+            prop::collection::vec(key(), 0..3)
+        )
+            .prop_map(|(name, keys)| Mutation::Set(
+                name,
+                Value::Array(keys.iter().map(|k| text(&k.did_key())).collect())
+            )),
+        field().prop_map(Mutation::Remove),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn prop_operation(data in operation_bytes()) {
+        check_operation(&data);
+    }
+
+    #[test]
+    fn prop_public_key(key in did_key()) {
+        check_public_key(&key);
+    }
+
+    #[test]
+    fn prop_valid_public_key(key in key(), msg in prop::collection::vec(any::<u8>(), 0..64)) {
+        let public_key = PublicKey::from_str(&key.did_key()).unwrap();
+        prop_assert!(public_key.verify(&msg, &key.sign(&msg)).is_ok());
+    }
+
+    #[test]
+    fn prop_local_id(id in local_id()) {
+        check_local_id(&id);
+    }
+
+    #[test]
+    fn prop_authorization(blob in authorization()) {
+        check_authorization(&blob);
+    }
+}
+
+proptest! {
+    // every case signs and verifies a whole history
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn prop_resigned(
+        keys in prop::collection::vec(key(), 1..4),
+        len in 1usize..4,
+        target in any::<prop::sample::Index>(),
+        mutation in mutation(),
+    ) {
+        let history = History::new(keys, len);
+        check_resigned(&history, target.index(len), &mutation);
+    }
+}
+
+#[test]
+fn test_history() {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 4);
+    for index in [0, 1] {
+        let msg = [index as u8; 32];
+        let (indices, sig) = history.authorize(&msg, index);
+        let result = validate_operation_history(
+            &history.binary_did(),
+            cursors(&history.operations),
+            indices,
+            &msg,
+            &sig,
+        );
+        assert!(result.is_ok());
+    }
+    // the final signature by a rotation key that does not exist
+    let (mut indices, sig) = history.authorize(&[0; 32], 0);
+    *indices.last_mut().unwrap() = 2;
+    let result = validate_operation_history(
+        &history.binary_did(),
+        cursors(&history.operations),
+        indices,
+        &[0; 32],
+        &sig,
+    );
+    assert!(matches!(result, Err(Error::InvalidKeyIndex)));
+}
+
+// Replays the inputs that crashed the fuzz targets
+#[test]
+fn test_crash_corpora() {
+    let artifacts = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../fuzz/artifacts");
+    for target in [
+        "operation",
+        "public_key",
+        "local_id",
+        "authorization",
+        "resign",
+    ] {
+        let Ok(entries) = fs::read_dir(artifacts.join(target)) else {
+            continue;
+        };
+        for entry in entries {
+            let path = entry.unwrap().path();
+            println!("replaying {:?}", path);
+            let data = fs::read(&path).unwrap();
+            match target {
+                "operation" => check_operation(&data),
+                "public_key" => check_public_key(&String::from_utf8_lossy(&data)),
+                "local_id" => check_local_id(&data),
+                "authorization" => check_authorization(&data),
+                _ => check_resign_input(&data),
+            }
+        }
+    }
+}
+
+/// Decodes the input of the `resign` fuzz target: the history length, the
+/// rotation key types, the mutated operation, the mutated field, whether it is
+/// removed, then the CBOR value it is set to.
+pub fn check_resign_input(data: &[u8]) {
+    let [len, key_types, target, field, remove, value @ ..] = data else {
+        return;
+    };
+    let len = usize::from(len % 4) + 1;
+    let keys = (0..(key_types % 3) + 1)
+        .map(|i| {
+            if key_types & (0x10 << i) == 0 {
+                Key::secp256k1(i + 1)
+            } else {
+                Key::p256(i + 1)
+            }
+        })
+        .collect();
+    let history = History::new(keys, len);
+    let name = match FIELDS.get(usize::from(*field)) {
+        Some(name) => name.to_string(),
+        None => format!("x-{}", field),
+    };
+    let mutation = if remove % 2 == 1 {
+        Mutation::Remove(name)
+    } else {
+        let Ok(value) = Value::decode(&mut SliceReader::new(value)) else {
+            return;
+        };
+        Mutation::Set(name, value)
+    };
+    check_resigned(&history, usize::from(*target) % len, &mutation);
+}
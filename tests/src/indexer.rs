@@ -0,0 +1,376 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::plc::Key;
+use crate::verifier::{
+    DID_CKB_CODE_HASH, MIGRATION_DATA, SPEC_DATA_0, SPEC_DATA_1, bytes, did_ckb_output, hash,
+};
+use ckb_types::{
+    H256,
+    bytes::Bytes,
+    core::{
+        BlockBuilder, BlockView, EpochNumberWithFraction, ScriptHashType, TransactionBuilder,
+        TransactionView,
+    },
+    packed::{CellInput, OutPoint},
+    prelude::*,
+};
+use did_ckb_indexer::{
+    DumpSource, Indexer, Resolver, Store, deployment, resolver, store::Operation,
+};
+use did_ckb_molecules::types::{DidCkbData, DidCkbDataV1};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const TYPE_ID: &str = "8434cfe81aa825c275d513eee20e4235294e3420";
+const DID: &str = "did:ckb:qq2m72a2vas4e5ovcpxoedscguuu4nba";
+const OTHER_TYPE_ID: &str = "0000000000000000000000000000000000000001";
+const OTHER_DID: &str = "did:ckb:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab";
+const LOCAL_ID: &str = "did:plc:bxvfvvygwbcnbmknn73t6pbu";
+
+struct Chain {
+    dir: TempDir,
+    blocks: Vec<BlockView>,
+}
+
+impl Chain {
+    fn new() -> Self {
+        let mut chain = Self {
+            dir: TempDir::new().unwrap(),
+            blocks: vec![],
+        };
+        chain.push(vec![], 0);
+        chain
+    }
+
+    // `nonce` tells apart blocks of the same height on different forks
+    fn push(&mut self, txs: Vec<TransactionView>, nonce: u128) {
+        let number = self.blocks.len() as u64;
+        let mut builder = BlockBuilder::default()
+            .number(number.pack())
+            .epoch(EpochNumberWithFraction::new(0, number, 1000).pack())
+            .timestamp((1_700_000_000_000 + number).pack())
+            .nonce(nonce.pack())
+            .transactions(txs);
+        if let Some(parent) = self.blocks.last() {
+            builder = builder.parent_hash(parent.hash());
+        }
+        let block = builder.build();
+        let json = ckb_jsonrpc_types::BlockView::from(block.clone());
+        fs::write(
+            self.dir.path().join(format!("{}.json", number)),
+            serde_json::to_vec(&json).unwrap(),
+        )
+        .unwrap();
+        self.blocks.push(block);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        for number in len..self.blocks.len() {
+            fs::remove_file(self.dir.path().join(format!("{}.json", number))).unwrap();
+        }
+        self.blocks.truncate(len);
+    }
+
+    fn source(&self) -> DumpSource {
+        DumpSource::new(self.dir.path())
+    }
+}
+
+fn new_indexer(dir: &Path) -> Indexer {
+    let store = Store::open(&dir.join("index.redb")).unwrap();
+    Indexer::new(store, hash(DID_CKB_CODE_HASH), ScriptHashType::Type, 0)
+}
+
+fn mint_tx() -> TransactionView {
+    mint_tx_with(TYPE_ID, SPEC_DATA_0)
+}
+
+fn mint_tx_with(type_id: &str, data: &str) -> TransactionView {
+    TransactionBuilder::default()
+        .output(did_ckb_output(600, type_id))
+        .output_data(bytes(data).pack())
+        .build()
+}
+
+// Mints a did:ckb with the spec example document, bound to `local_id`.
+fn mint_tx_bound_to(type_id: &str, local_id: &str) -> TransactionView {
+    let DidCkbData::DidCkbDataV1(spec) = DidCkbData::from_slice(&bytes(SPEC_DATA_0)).unwrap();
+    let data = DidCkbData::DidCkbDataV1(DidCkbDataV1 {
+        document: spec.document,
+        local_id: Some(local_id.to_owned()),
+    });
+    TransactionBuilder::default()
+        .output(did_ckb_output(600, type_id))
+        .output_data(Bytes::from(data.to_vec()).pack())
+        .build()
+}
+
+fn update_tx(previous: &TransactionView) -> TransactionView {
+    update_tx_with(previous, TYPE_ID, SPEC_DATA_1)
+}
+
+fn update_tx_with(previous: &TransactionView, type_id: &str, data: &str) -> TransactionView {
+    TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(previous.hash(), 0), 0))
+        .output(did_ckb_output(600, type_id))
+        .output_data(bytes(data).pack())
+        .build()
+}
+
+fn burn_tx(previous: &TransactionView) -> TransactionView {
+    TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(previous.hash(), 0), 0))
+        .build()
+}
+
+fn operations(indexer: &Indexer) -> Vec<Operation> {
+    Resolver::new(indexer.store())
+        .resolve(DID)
+        .unwrap()
+        .map(|r| r.versions.iter().map(|v| v.operation).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_did_ckb_identifier() {
+    let key = resolver::parse_did(DID).unwrap();
+    assert_eq!(hex::encode(key), TYPE_ID);
+    assert_eq!(resolver::did_from_key(&key), DID);
+    assert!(resolver::parse_did("did:plc:bxvfvvygwbcnbmknn73t6pbu").is_err());
+    assert!(resolver::parse_did("did:ckb:qq2m72a2").is_err());
+}
+
+#[test]
+fn test_load_code_hash() {
+    let mainnet = deployment::load_code_hash(Path::new("../deployment/mainnet")).unwrap();
+    let testnet = deployment::load_code_hash(Path::new("../deployment/testnet")).unwrap();
+    assert_eq!(
+        mainnet,
+        H256::from_trimmed_str("4a06164dc34dccade5afe3e847a97b6db743e79f5477fa3295acf02849c5984a")
+            .unwrap()
+    );
+    assert_eq!(
+        testnet,
+        H256::from_trimmed_str("510150477b10d6ab551a509b71265f3164e9fd4137fcb5a4322f49f03092c7c5")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_index_mint_update_burn() {
+    let mut chain = Chain::new();
+    let mint = mint_tx();
+    let update = update_tx(&mint);
+    chain.push(vec![mint.clone()], 0);
+    chain.push(vec![update.clone()], 0);
+
+    let indexer = new_indexer(chain.dir.path());
+    assert_eq!(indexer.sync(&chain.source()).unwrap(), Some(2));
+    let resolution = Resolver::new(indexer.store())
+        .resolve(DID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolution.did, DID);
+    assert!(!resolution.deactivated);
+    assert_eq!(resolution.local_id, None);
+    assert_eq!(
+        resolution.document.unwrap()["alsoKnownAs"],
+        serde_json::json!(["at://bob.test"])
+    );
+    let versions = &resolution.versions;
+    assert_eq!(versions[0].operation, Operation::Mint);
+    assert_eq!(versions[0].block_number, 1);
+    assert_eq!(versions[0].tx_hash, mint.hash().unpack());
+    assert_eq!(versions[1].operation, Operation::Update);
+    assert_eq!(versions[1].tx_hash, update.hash().unpack());
+
+    chain.push(vec![burn_tx(&update)], 0);
+    assert_eq!(indexer.sync(&chain.source()).unwrap(), Some(3));
+    let resolution = Resolver::new(indexer.store())
+        .resolve(DID)
+        .unwrap()
+        .unwrap();
+    assert!(resolution.deactivated);
+    assert_eq!(resolution.document, None);
+    assert_eq!(
+        operations(&indexer),
+        vec![Operation::Mint, Operation::Update, Operation::Burn]
+    );
+}
+
+#[test]
+fn test_resolve_unknown_did() {
+    let chain = Chain::new();
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+    assert_eq!(Resolver::new(indexer.store()).resolve(DID).unwrap(), None);
+}
+
+#[test]
+fn test_index_reorg() {
+    let mut chain = Chain::new();
+    let mint = mint_tx();
+    let update = update_tx(&mint);
+    chain.push(vec![mint.clone()], 0);
+    chain.push(vec![update.clone()], 0);
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+    assert_eq!(
+        operations(&indexer),
+        vec![Operation::Mint, Operation::Update]
+    );
+
+    // the update is dropped from the new fork and the cell is burned instead
+    chain.truncate(2);
+    chain.push(vec![], 1);
+    chain.push(vec![burn_tx(&mint)], 1);
+    assert_eq!(indexer.sync(&chain.source()).unwrap(), Some(3));
+    assert_eq!(
+        indexer.store().tip().unwrap(),
+        Some((3, chain.blocks[3].hash().unpack()))
+    );
+    assert_eq!(operations(&indexer), vec![Operation::Mint, Operation::Burn]);
+    let versions = indexer
+        .store()
+        .history(&resolver::parse_did(DID).unwrap())
+        .unwrap();
+    assert_eq!(versions[1].block_number, 3);
+}
+
+#[test]
+fn test_resolve_local_id() {
+    let mut chain = Chain::new();
+    let mint = mint_tx_with(TYPE_ID, MIGRATION_DATA);
+    // updates keep the local ID and don't create a new binding
+    let update = update_tx_with(&mint, TYPE_ID, MIGRATION_DATA);
+    chain.push(
+        vec![mint.clone(), mint_tx_with(OTHER_TYPE_ID, SPEC_DATA_0)],
+        0,
+    );
+    chain.push(vec![update.clone()], 0);
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+
+    let resolver = Resolver::new(indexer.store());
+    let resolution = resolver.resolve_local_id(LOCAL_ID).unwrap().unwrap();
+    assert_eq!(resolution.did.as_deref(), Some(DID));
+    assert!(resolution.conflicts.is_empty());
+    assert_eq!(resolution.bindings.len(), 1);
+    assert_eq!(resolution.bindings[0].local_id, LOCAL_ID);
+    assert_eq!(resolution.bindings[0].bound_block_number, 1);
+    assert_eq!(resolution.bindings[0].bound_tx_hash, mint.hash().unpack());
+    assert_eq!(
+        resolver.resolve(DID).unwrap().unwrap().local_id.as_deref(),
+        Some(LOCAL_ID)
+    );
+
+    assert_eq!(
+        resolver
+            .resolve_local_id("did:plc:aaaaaaaaaaaaaaaaaaaaaaaa")
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        resolver.resolve_local_id("did:web:example.com").unwrap(),
+        None
+    );
+    assert!(resolver.resolve_local_id("did:example:alice").is_err());
+}
+
+#[test]
+fn test_resolve_other_local_ids() {
+    let did_key = Key::secp256k1(1).did_key();
+    let mut chain = Chain::new();
+    let mint = mint_tx_bound_to(TYPE_ID, &did_key);
+    chain.push(
+        vec![
+            mint.clone(),
+            mint_tx_bound_to(OTHER_TYPE_ID, &did_key),
+            mint_tx_bound_to(
+                "0000000000000000000000000000000000000002",
+                "did:web:example.com",
+            ),
+        ],
+        0,
+    );
+    chain.push(vec![burn_tx(&mint)], 0);
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+
+    // did:key and did:web get the same reverse lookup and conflict detection
+    let resolver = Resolver::new(indexer.store());
+    let resolution = resolver.resolve_local_id(&did_key).unwrap().unwrap();
+    assert_eq!(resolution.did.as_deref(), Some(OTHER_DID));
+    assert!(resolution.conflicts.is_empty());
+    assert_eq!(resolution.bindings.len(), 2);
+    assert_eq!(resolution.bindings[0].did, DID);
+    assert_eq!(resolution.bindings[0].unbound_block_number, Some(2));
+    let resolution = resolver
+        .resolve_local_id("did:web:example.com")
+        .unwrap()
+        .unwrap();
+    assert!(resolution.did.is_some());
+    assert_eq!(resolution.bindings[0].local_id, "did:web:example.com");
+}
+
+#[test]
+fn test_non_canonical_local_id() {
+    // an extra base32 character decodes to the binary DID of `LOCAL_ID`
+    let non_canonical = format!("{}a", LOCAL_ID);
+    let mut chain = Chain::new();
+    chain.push(vec![mint_tx_bound_to(TYPE_ID, &non_canonical)], 0);
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+
+    let resolver = Resolver::new(indexer.store());
+    assert!(resolver.resolve_local_id(&non_canonical).is_err());
+    assert_eq!(resolver.resolve_local_id(LOCAL_ID).unwrap(), None);
+}
+
+#[test]
+fn test_local_id_conflict() {
+    let mut chain = Chain::new();
+    let mint = mint_tx_with(TYPE_ID, MIGRATION_DATA);
+    chain.push(vec![mint.clone()], 0);
+    chain.push(vec![mint_tx_with(OTHER_TYPE_ID, MIGRATION_DATA)], 0);
+    let indexer = new_indexer(chain.dir.path());
+    indexer.sync(&chain.source()).unwrap();
+
+    let resolution = Resolver::new(indexer.store())
+        .resolve_local_id(LOCAL_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolution.did.as_deref(), Some(DID));
+    assert_eq!(resolution.conflicts, vec![OTHER_DID.to_owned()]);
+
+    // burning the first binding leaves the other one live
+    let burn = burn_tx(&mint);
+    chain.push(vec![burn.clone()], 0);
+    indexer.sync(&chain.source()).unwrap();
+    let resolution = Resolver::new(indexer.store())
+        .resolve_local_id(LOCAL_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolution.did.as_deref(), Some(OTHER_DID));
+    assert!(resolution.conflicts.is_empty());
+    let bindings = &resolution.bindings;
+    assert_eq!(bindings.len(), 2);
+    assert_eq!(bindings[0].did, DID);
+    assert_eq!(bindings[0].unbound_block_number, Some(3));
+    assert_eq!(bindings[0].unbound_tx_hash, Some(burn.hash().unpack()));
+    assert!(bindings[1].is_live());
+
+    // the burn is reverted by a reorg
+    chain.truncate(3);
+    chain.push(vec![], 1);
+    chain.push(vec![], 1);
+    indexer.sync(&chain.source()).unwrap();
+    let resolution = Resolver::new(indexer.store())
+        .resolve_local_id(LOCAL_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolution.did.as_deref(), Some(DID));
+    assert_eq!(resolution.conflicts, vec![OTHER_DID.to_owned()]);
+    assert!(resolution.bindings.iter().all(|b| b.is_live()));
+}
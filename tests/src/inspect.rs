@@ -0,0 +1,146 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::cli::authorized_mint;
+use crate::plc::{History, Key};
+use crate::test_vectors::{load_did, load_vector};
+use crate::vectors::{self, Variant};
+use ckb_did_plc_utils::{
+    error::Error,
+    inspect::{Inspection, StepKind, inspect_operation_history},
+    operation::{parse_local_id, validate_operation_history},
+};
+use ckb_types::prelude::*;
+use did_ckb_molecules::lazy_reader::new_cursor;
+use did_ckb_verifier::inspect_authorization;
+
+const MSG: [u8; 32] = [0; 32];
+
+fn history() -> Vec<Vec<u8>> {
+    [
+        "1-did-creation.cbor",
+        "2-update-handle.cbor",
+        "3-update-pds.cbor",
+        "4-update-atproto-key.cbor",
+        "5-update-rotation-keys.cbor",
+        "6-update-handle.cbor",
+    ]
+    .iter()
+    .map(|name| load_vector(name))
+    .collect()
+}
+
+// Inspects `history` and checks the error against `validate_operation_history`
+fn inspect(history: &[Vec<u8>], rotation_key_indices: &[usize], sig: &[u8]) -> Inspection {
+    let binary_did = parse_local_id(load_did("creation").as_bytes()).unwrap();
+    let cursors = || history.iter().cloned().map(new_cursor).collect();
+    let inspection = inspect_operation_history(
+        &binary_did,
+        cursors(),
+        rotation_key_indices.to_vec(),
+        &MSG,
+        sig,
+    );
+    let result = validate_operation_history(
+        &binary_did,
+        cursors(),
+        rotation_key_indices.to_vec(),
+        &MSG,
+        sig,
+    );
+    assert_eq!(
+        inspection.error().map(Error::error_code),
+        result.err().as_ref().map(Error::error_code)
+    );
+    inspection
+}
+
+#[test]
+fn test_inspect_valid_history() {
+    let sig = vectors::p256_key().sign(&MSG);
+    let inspection = inspect(&history(), &[0, 0, 0, 0, 0, 1, 1], &sig);
+    assert!(inspection.error().is_none());
+    assert_eq!(inspection.did, load_did("creation"));
+    assert_eq!(inspection.steps.len(), 7);
+    assert_eq!(inspection.steps[0].kind, StepKind::Genesis);
+    assert_eq!(inspection.steps[0].did.as_deref(), Some(&*inspection.did));
+    assert_eq!(inspection.steps[6].kind, StepKind::Authorization);
+    assert_eq!(inspection.steps[6].verifying_key_indices, vec![1]);
+    for step in &inspection.steps[1..6] {
+        assert_eq!(step.kind, StepKind::Operation);
+        assert_eq!(step.prev, step.expected_prev);
+    }
+}
+
+#[test]
+fn test_inspect_wrong_rotation_key_index() {
+    let sig = vectors::p256_key().sign(&MSG);
+    // operation 5 is signed by the p256 key at index 1
+    let inspection = inspect(&history(), &[0, 0, 0, 0, 0, 0, 1], &sig);
+    let failure = inspection.failure().unwrap();
+    assert_eq!(failure.index, 5);
+    assert_eq!(failure.error.as_ref().unwrap().error_code(), 36);
+    assert_eq!(
+        failure.rotation_key(),
+        Some(&*vectors::secp256k1_key(3).did_key())
+    );
+    assert_eq!(failure.verifying_key_indices, vec![1]);
+
+    let inspection = inspect(&history(), &[0, 0, 0, 0, 0, 1, 0], &sig);
+    let failure = inspection.failure().unwrap();
+    assert_eq!(failure.kind, StepKind::Authorization);
+    assert_eq!(failure.verifying_key_indices, vec![1]);
+
+    let inspection = inspect(&history(), &[0, 0, 0, 0, 0, 1, 2], &sig);
+    let failure = inspection.failure().unwrap();
+    assert_eq!(failure.error.as_ref().unwrap().error_code(), 42);
+    assert_eq!(failure.rotation_key(), None);
+}
+
+#[test]
+fn test_inspect_cid_mismatch() {
+    let sig = vectors::p256_key().sign(&MSG);
+    let mut history = history();
+    history[3] = vectors::variant(&history[3], Variant::BadCid);
+    let inspection = inspect(&history, &[0, 0, 0, 0, 0, 1, 1], &sig);
+    let failure = inspection.failure().unwrap();
+    assert_eq!(failure.index, 3);
+    assert_eq!(failure.error.as_ref().unwrap().error_code(), 37);
+    assert_ne!(failure.prev, failure.expected_prev);
+    assert!(failure.expected_prev.is_some());
+}
+
+#[test]
+fn test_inspect_variants() {
+    let sig = vectors::p256_key().sign(&MSG);
+    for variant in [Variant::BadSignature, Variant::BadCid, Variant::WrongType] {
+        for index in 0..6 {
+            let mut history = history();
+            history[index] = vectors::variant(&history[index], variant);
+            let inspection = inspect(&history, &[0, 0, 0, 0, 0, 1, 1], &sig);
+            let failure = inspection.failure().unwrap();
+            assert!(failure.index <= index, "{:?} at {}", variant, index);
+        }
+    }
+
+    let inspection = inspect(&history(), &[0, 0, 1], &sig);
+    assert_eq!(inspection.error().unwrap().error_code(), 43);
+    assert!(inspection.steps.is_empty());
+}
+
+#[test]
+fn test_inspect_authorization() {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 3);
+    let tx = authorized_mint(&history, 1);
+    let witness = tx.witnesses().get(0).unwrap().raw_data();
+    let inspection = inspect_authorization(&witness, &history.did(), &tx.hash().unpack()).unwrap();
+    assert!(inspection.error().is_none());
+
+    // signed over another transaction
+    let inspection = inspect_authorization(&witness, &history.did(), &[0; 32]).unwrap();
+    let failure = inspection.failure().unwrap();
+    assert_eq!(failure.kind, StepKind::Authorization);
+    assert!(failure.verifying_key_indices.is_empty());
+
+    let e = inspect_authorization(&[], &history.did(), &[0; 32]).unwrap_err();
+    assert_eq!(e.error_code(), 54);
+}
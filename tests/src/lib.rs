@@ -1 +1,57 @@
+use ckb_testtool::ckb_types::bytes::Bytes;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub mod audit_log;
+pub mod bench;
+pub mod binding;
+pub mod cid;
+pub mod cli;
+pub mod dag_json;
+#[cfg(feature = "error_context")]
+pub mod error_context;
+pub mod fuzz;
+pub mod indexer;
+pub mod inspect;
+pub mod local_id;
+pub mod molecules;
+pub mod plc;
+pub mod replay;
+pub mod reproducible;
+pub mod state;
 pub mod test_vectors;
+pub mod tests;
+pub mod vectors;
+pub mod verifier;
+pub mod web;
+
+/// Loads contract binaries from `build/debug` or `build/release`, depending
+/// on the `MODE` environment variable (release by default). `TOP` overrides
+/// the repository root.
+pub struct Loader(PathBuf);
+
+impl Default for Loader {
+    fn default() -> Self {
+        let mode = match env::var("MODE").as_deref() {
+            Ok("debug") => "debug",
+            _ => "release",
+        };
+        let mut path = match env::var("TOP") {
+            Ok(top) => PathBuf::from(top),
+            Err(_) => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(".."),
+        };
+        path.push("build");
+        path.push(mode);
+        Loader(path)
+    }
+}
+
+impl Loader {
+    pub fn load_binary(&self, name: &str) -> Bytes {
+        let path = self.0.join(name);
+        fs::read(&path)
+            .unwrap_or_else(|_| panic!("Binary {:?} is missing!", path))
+            .into()
+    }
+}
@@ -0,0 +1,207 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::cli::authorized_mint;
+use crate::plc::{History, Key};
+use crate::test_vectors::{load_did, load_vector};
+use crate::vectors;
+use ckb_did_plc_utils::{
+    error::Error,
+    local_id::{
+        Authorization, AuthorizationKind, Key as KeyVerifier, LocalIdVerifier, Plc, Registry,
+    },
+};
+use ckb_types::prelude::*;
+use did_ckb_molecules::lazy_reader::new_cursor;
+use did_ckb_verifier::verify_authorization;
+
+const MSG: [u8; 32] = [0; 32];
+
+fn plc_authorization(sig: Vec<u8>) -> Authorization {
+    Authorization::Plc {
+        history: [
+            "1-did-creation.cbor",
+            "2-update-handle.cbor",
+            "3-update-pds.cbor",
+            "4-update-atproto-key.cbor",
+            "5-update-rotation-keys.cbor",
+            "6-update-handle.cbor",
+        ]
+        .iter()
+        .map(|name| new_cursor(load_vector(name)))
+        .collect(),
+        rotation_key_indices: vec![0, 0, 0, 0, 0, 1, 1],
+        sig,
+    }
+}
+
+// Accepts any authorization of a `did:example:` local ID
+struct Example;
+
+impl LocalIdVerifier for Example {
+    fn prefix(&self) -> &'static str {
+        "did:example:"
+    }
+
+    fn kind(&self) -> AuthorizationKind {
+        AuthorizationKind::Key
+    }
+
+    fn check_local_id(&self, local_id: &[u8]) -> Result<(), Error> {
+        match local_id.len() > self.prefix().len() {
+            true => Ok(()),
+            false => Err(Error::InvalidDidFormat),
+        }
+    }
+
+    fn verify(&self, local_id: &[u8], _: Authorization, _: &[u8]) -> Result<(), Error> {
+        self.check_local_id(local_id)
+    }
+}
+
+#[test]
+fn test_registry_plc() {
+    let registry = Registry::default();
+    assert_eq!(
+        registry.prefixes().collect::<Vec<_>>(),
+        vec!["did:plc:", "did:key:", "did:web:"]
+    );
+    let did = load_did("creation");
+    let verifier = registry.find(did.as_bytes()).unwrap();
+    assert_eq!(verifier.prefix(), "did:plc:");
+    assert_eq!(verifier.kind(), AuthorizationKind::Plc);
+    registry.check_local_id(did.as_bytes()).unwrap();
+
+    let sig = vectors::p256_key().sign(&MSG);
+    verifier
+        .verify(did.as_bytes(), plc_authorization(sig), &MSG)
+        .unwrap();
+    let sig = vectors::p256_key().sign(&[1; 32]);
+    let e = verifier
+        .verify(did.as_bytes(), plc_authorization(sig), &MSG)
+        .unwrap_err();
+    assert_eq!(e.error_code(), 34);
+    // the DID is checked before the history
+    let e = Plc
+        .verify(b"did:plc:!", plc_authorization(vec![]), &MSG)
+        .unwrap_err();
+    assert_eq!(e.error_code(), 46);
+}
+
+#[test]
+fn test_registry_unknown_method() {
+    let registry = Registry::default();
+    for local_id in [&b"did:ion:example"[..], b"did:plc", b"", b"\xff"] {
+        assert_eq!(registry.find(local_id).err().unwrap().error_code(), 46);
+        assert_eq!(
+            registry.check_local_id(local_id).unwrap_err().error_code(),
+            46
+        );
+    }
+    assert_eq!(
+        registry
+            .check_local_id(b"did:plc:1")
+            .unwrap_err()
+            .error_code(),
+        46
+    );
+    assert!(
+        Registry::new()
+            .find(load_did("creation").as_bytes())
+            .is_err()
+    );
+}
+
+#[test]
+fn test_registry_register() {
+    let mut registry = Registry::default();
+    registry.register(Example);
+    assert_eq!(
+        registry.prefixes().collect::<Vec<_>>(),
+        vec!["did:plc:", "did:key:", "did:web:", "did:example:"]
+    );
+    let verifier = registry.find(b"did:example:alice").unwrap();
+    assert_eq!(verifier.prefix(), "did:example:");
+    verifier
+        .verify(b"did:example:alice", plc_authorization(vec![]), &MSG)
+        .unwrap();
+    assert!(registry.check_local_id(b"did:example:").is_err());
+
+    // replaces the verifier of the same prefix
+    registry.register(Example);
+    assert_eq!(registry.prefixes().count(), 4);
+}
+
+#[test]
+fn test_registry_verifier_crate() {
+    let history = History::new(vec![Key::secp256k1(1), Key::p256(2)], 2);
+    let tx = authorized_mint(&history, 1);
+    let witness = tx.witnesses().get(0).unwrap().raw_data();
+    verify_authorization(&witness, &history.did(), &tx.hash().unpack()).unwrap();
+    let e = verify_authorization(&witness, "did:ion:example", &tx.hash().unpack()).unwrap_err();
+    assert_eq!(e.error_code(), 46);
+}
+
+#[test]
+fn test_registry_did_key() {
+    let registry = Registry::default();
+    for key in [Key::secp256k1(1), Key::p256(2)] {
+        let did = key.did_key();
+        let verifier = registry.find(did.as_bytes()).unwrap();
+        assert_eq!(verifier.kind(), AuthorizationKind::Key);
+        registry.check_local_id(did.as_bytes()).unwrap();
+        let sig = key.sign(&MSG);
+        verifier
+            .verify(did.as_bytes(), Authorization::Key { sig }, &MSG)
+            .unwrap();
+
+        // signed over another message or by another key
+        let sig = key.sign(&[1; 32]);
+        let e = verifier
+            .verify(did.as_bytes(), Authorization::Key { sig }, &MSG)
+            .unwrap_err();
+        assert_eq!(e.error_code(), 48);
+        let sig = Key::secp256k1(3).sign(&MSG);
+        let e = verifier
+            .verify(did.as_bytes(), Authorization::Key { sig }, &MSG)
+            .unwrap_err();
+        assert_eq!(e.error_code(), 48);
+        let e = verifier
+            .verify(did.as_bytes(), Authorization::Key { sig: vec![] }, &MSG)
+            .unwrap_err();
+        assert_eq!(e.error_code(), 48);
+
+        // the witness variant of did:plc
+        let e = verifier
+            .verify(did.as_bytes(), plc_authorization(vec![]), &MSG)
+            .unwrap_err();
+        assert_eq!(e.error_code(), 49);
+    }
+
+    let e = Plc
+        .verify(
+            load_did("creation").as_bytes(),
+            Authorization::Key { sig: vec![] },
+            &MSG,
+        )
+        .unwrap_err();
+    assert_eq!(e.error_code(), 49);
+}
+
+#[test]
+fn test_registry_invalid_did_key() {
+    let registry = Registry::default();
+    // an ed25519 key, a truncated key and a base58btc typo
+    for did in [
+        "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+        "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQi",
+        "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBm0",
+        "did:key:",
+    ] {
+        let e = registry.check_local_id(did.as_bytes()).unwrap_err();
+        assert_eq!(e.error_code(), 47, "{}", did);
+        let e = KeyVerifier
+            .verify(did.as_bytes(), Authorization::Key { sig: vec![] }, &MSG)
+            .unwrap_err();
+        assert_eq!(e.error_code(), 47, "{}", did);
+    }
+}
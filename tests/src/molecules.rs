@@ -0,0 +1,171 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::verifier::{MIGRATION_DATA, MIGRATION_WITNESS, SPEC_DATA_0};
+use did_ckb_molecules::lazy_reader::{self, new_cursor};
+use did_ckb_molecules::packed::{self, Entity};
+use did_ckb_molecules::types::{
+    DidCkbData, DidCkbDataV1, DidCkbWitness, Error, PlcAuthorization, WitnessArgs,
+};
+
+fn read_data(data: &[u8]) -> DidCkbData {
+    let reader = lazy_reader::DidCkbData::try_from(new_cursor(data.to_vec())).unwrap();
+    DidCkbData::try_from(reader).unwrap()
+}
+
+fn read_witness(witness: &[u8]) -> DidCkbWitness {
+    let args = lazy_reader::WitnessArgs::from(new_cursor(witness.to_vec()));
+    let output_type = args.output_type().unwrap().unwrap();
+    DidCkbWitness::try_from(lazy_reader::DidCkbWitness::from(output_type)).unwrap()
+}
+
+fn example_authorization() -> PlcAuthorization {
+    PlcAuthorization {
+        history: vec![vec![0xa1, 0x61, 0x61, 0x01], vec![], vec![0xf6]],
+        sig: vec![7; 64],
+        rotation_key_indices: vec![0, 4],
+    }
+}
+
+#[test]
+fn test_cell_data_from_contract() {
+    let bytes = hex::decode(MIGRATION_DATA).unwrap();
+    let data = read_data(&bytes);
+    assert_eq!(DidCkbData::from_slice(&bytes).unwrap(), data);
+    assert_eq!(data.to_vec(), bytes);
+    let DidCkbData::DidCkbDataV1(v1) = &data;
+    assert_eq!(
+        v1.local_id.as_deref(),
+        Some("did:plc:bxvfvvygwbcnbmknn73t6pbu")
+    );
+
+    let bytes = hex::decode(SPEC_DATA_0).unwrap();
+    let DidCkbData::DidCkbDataV1(v1) = read_data(&bytes);
+    assert_eq!(v1.local_id, None);
+    assert_eq!(DidCkbData::DidCkbDataV1(v1).to_vec(), bytes);
+}
+
+#[test]
+fn test_witness_from_contract() {
+    let bytes = hex::decode(MIGRATION_WITNESS).unwrap();
+    let witness = read_witness(&bytes);
+    let auth = &witness.local_id_authorization;
+    assert_eq!(auth.history.len(), 1);
+    assert_eq!(auth.sig.len(), 64);
+    assert_eq!(auth.rotation_key_indices, vec![0, 0]);
+
+    let args = WitnessArgs::from_slice(&bytes).unwrap();
+    assert_eq!(args.lock, None);
+    assert_eq!(args.input_type, None);
+    assert_eq!(
+        DidCkbWitness::from_slice(args.output_type.as_ref().unwrap()).unwrap(),
+        witness
+    );
+    let rebuilt = WitnessArgs {
+        output_type: Some(witness.to_vec()),
+        ..Default::default()
+    };
+    assert_eq!(rebuilt.to_vec(), bytes);
+}
+
+#[test]
+fn test_build_and_read() {
+    let data = DidCkbData::DidCkbDataV1(DidCkbDataV1 {
+        document: vec![0xa0],
+        local_id: Some("did:plc:aaaaaaaaaaaaaaaaaaaaaaaa".to_owned()),
+    });
+    assert_eq!(read_data(&data.to_vec()), data);
+
+    let witness = DidCkbWitness {
+        local_id_authorization: example_authorization(),
+    };
+    let args = WitnessArgs {
+        lock: Some(vec![1; 65]),
+        input_type: Some(vec![]),
+        output_type: Some(witness.to_vec()),
+    };
+    let bytes = args.to_vec();
+    assert_eq!(read_witness(&bytes), witness);
+    let reader = lazy_reader::WitnessArgs::from(new_cursor(bytes.clone()));
+    assert_eq!(WitnessArgs::try_from(reader).unwrap(), args);
+    assert_eq!(
+        packed::WitnessArgs::from_slice(&bytes).unwrap().as_slice(),
+        &bytes[..]
+    );
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let data = DidCkbData::from_slice(&hex::decode(MIGRATION_DATA).unwrap()).unwrap();
+    let json = serde_json::to_value(&data).unwrap();
+    assert_eq!(
+        json["DidCkbDataV1"]["local_id"],
+        "did:plc:bxvfvvygwbcnbmknn73t6pbu"
+    );
+    assert!(
+        json["DidCkbDataV1"]["document"]
+            .as_str()
+            .unwrap()
+            .starts_with("0xa3")
+    );
+    assert_eq!(serde_json::from_value::<DidCkbData>(json).unwrap(), data);
+
+    let args = WitnessArgs {
+        lock: None,
+        input_type: Some(vec![0xab]),
+        output_type: Some(
+            DidCkbWitness {
+                local_id_authorization: example_authorization(),
+            }
+            .to_vec(),
+        ),
+    };
+    let json = serde_json::to_value(&args).unwrap();
+    assert_eq!(json["lock"], serde_json::Value::Null);
+    assert_eq!(json["input_type"], "0xab");
+    assert_eq!(serde_json::from_value::<WitnessArgs>(json).unwrap(), args);
+
+    let json = serde_json::to_value(example_authorization()).unwrap();
+    assert_eq!(
+        json["history"],
+        serde_json::json!(["0xa1616101", "0x", "0xf6"])
+    );
+    assert_eq!(json["rotation_key_indices"], serde_json::json!([0, 4]));
+    assert_eq!(
+        serde_json::from_value::<PlcAuthorization>(json).unwrap(),
+        example_authorization()
+    );
+    assert!(
+        serde_json::from_value::<PlcAuthorization>(serde_json::json!({
+            "history": ["a1"],
+            "sig": "0x",
+            "rotation_key_indices": [],
+        }))
+        .is_err()
+    );
+}
+
+#[test]
+fn test_invalid_encoding() {
+    let bytes = hex::decode(MIGRATION_DATA).unwrap();
+    assert!(matches!(
+        DidCkbData::from_slice(&bytes[..bytes.len() - 1]),
+        Err(Error::Verification(_))
+    ));
+    let reader =
+        lazy_reader::DidCkbData::try_from(new_cursor(bytes[..bytes.len() - 1].to_vec())).unwrap();
+    assert!(matches!(
+        DidCkbData::try_from(reader),
+        Err(Error::Molecule(_))
+    ));
+
+    let data = DidCkbData::DidCkbDataV1(DidCkbDataV1 {
+        document: vec![0xa0],
+        local_id: Some("x".to_owned()),
+    });
+    let mut bytes = data.to_vec();
+    *bytes.last_mut().unwrap() = 0xff;
+    assert!(matches!(
+        DidCkbData::from_slice(&bytes),
+        Err(Error::InvalidUtf8)
+    ));
+}
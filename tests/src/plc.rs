@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Builds and signs did:plc operations, so tests don't depend on the vectors
+//! generated by `tools/gen-test-vectors`.
+use ckb_did_plc_utils::{
+    base32::{self, Alphabet},
+    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    cbor4ii::core::{Value, enc::Encode, utils::BufWriter},
+    operation::Operation,
+};
+use k256::ecdsa::signature::Signer;
+
+/// A rotation key of a did:plc.
+#[derive(Clone, Debug)]
+pub enum Key {
+    Secp256k1(k256::ecdsa::SigningKey),
+    P256(p256::ecdsa::SigningKey),
+}
+
+impl Key {
+    // A valid private key for both curves, distinct for every seed
+    fn private_key(seed: u8) -> [u8; 32] {
+        let mut key = [0x11; 32];
+        key[31] = seed;
+        key
+    }
+
+    pub fn secp256k1(seed: u8) -> Self {
+        Key::Secp256k1(k256::ecdsa::SigningKey::from_slice(&Self::private_key(seed)).unwrap())
+    }
+
+    pub fn p256(seed: u8) -> Self {
+        Key::P256(p256::ecdsa::SigningKey::from_slice(&Self::private_key(seed)).unwrap())
+    }
+
+    pub fn secp256k1_from_slice(private_key: &[u8]) -> Self {
+        Key::Secp256k1(k256::ecdsa::SigningKey::from_slice(private_key).unwrap())
+    }
+
+    pub fn p256_from_slice(private_key: &[u8]) -> Self {
+        Key::P256(p256::ecdsa::SigningKey::from_slice(private_key).unwrap())
+    }
+
+    pub fn did_key(&self) -> String {
+        let mut raw = match self {
+            Key::Secp256k1(_) => vec![0xe7, 0x01],
+            Key::P256(_) => vec![0x80, 0x24],
+        };
+        match self {
+            Key::Secp256k1(key) => {
+                raw.extend_from_slice(key.verifying_key().to_encoded_point(true).as_bytes())
+            }
+            Key::P256(key) => {
+                raw.extend_from_slice(key.verifying_key().to_encoded_point(true).as_bytes())
+            }
+        }
+        format!(
+            "did:key:{}",
+            multibase::encode(multibase::Base::Base58Btc, raw)
+        )
+    }
+
+    /// A 64-byte compact signature over the SHA-256 of `msg`, with a low S
+    /// like @atproto/crypto makes.
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            Key::Secp256k1(key) => {
+                let sig: k256::ecdsa::Signature = key.sign(msg);
+                sig.to_vec()
+            }
+            Key::P256(key) => {
+                let sig: p256::ecdsa::Signature = key.sign(msg);
+                sig.normalize_s().unwrap_or(sig).to_vec()
+            }
+        }
+    }
+}
+
+pub fn text(s: &str) -> Value {
+    Value::Text(s.to_owned())
+}
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut writer = BufWriter::new(Vec::new());
+    value.encode(&mut writer).unwrap();
+    writer.into_inner()
+}
+
+/// The fields of a `plc_operation` with `rotation_keys`, following `prev`.
+pub fn operation_fields(
+    rotation_keys: &[Key],
+    prev: Option<String>,
+    handle: &str,
+) -> Vec<(Value, Value)> {
+    vec![
+        (text("type"), text("plc_operation")),
+        (
+            text("rotationKeys"),
+            Value::Array(rotation_keys.iter().map(|k| text(&k.did_key())).collect()),
+        ),
+        (
+            text("verificationMethods"),
+            Value::Map(vec![(text("atproto"), text(&rotation_keys[0].did_key()))]),
+        ),
+        (
+            text("alsoKnownAs"),
+            Value::Array(vec![text(&format!("at://{}", handle))]),
+        ),
+        (
+            text("services"),
+            Value::Map(vec![(
+                text("atproto_pds"),
+                Value::Map(vec![
+                    (text("type"), text("AtprotoPersonalDataServer")),
+                    (text("endpoint"), text("https://pds.example.com")),
+                ]),
+            )]),
+        ),
+        (text("prev"), prev.map_or(Value::Null, |prev| text(&prev))),
+    ]
+}
+
+/// Sorts map keys recursively in the DAG-CBOR order: shorter encodings first,
+/// then bytewise.
+pub fn canonical(value: Value) -> Value {
+    match value {
+        Value::Map(fields) => {
+            let mut fields: Vec<(Value, Value)> = fields
+                .into_iter()
+                .map(|(k, v)| (canonical(k), canonical(v)))
+                .collect();
+            fields.sort_by_cached_key(|(k, _)| {
+                let k = encode(k);
+                (k.len(), k)
+            });
+            Value::Map(fields)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical).collect()),
+        value => value,
+    }
+}
+
+/// Signs `fields` with `key`, replacing any `sig`, and encodes the operation
+/// as canonical DAG-CBOR.
+pub fn sign_operation(mut fields: Vec<(Value, Value)>, key: &Key) -> Vec<u8> {
+    fields.retain(|(k, _)| *k != text("sig"));
+    let Value::Map(mut fields) = canonical(Value::Map(fields)) else {
+        unreachable!()
+    };
+    let sig = key.sign(&encode(&Value::Map(fields.clone())));
+    fields.push((text("sig"), text(&URL_SAFE_NO_PAD.encode(sig))));
+    encode(&canonical(Value::Map(fields)))
+}
+
+/// The fields of an encoded operation, in order.
+pub fn decode_fields(op: &[u8]) -> Option<Vec<(Value, Value)>> {
+    use ckb_did_plc_utils::cbor4ii::core::{dec::Decode, utils::SliceReader};
+    match Value::decode(&mut SliceReader::new(op)).ok()? {
+        Value::Map(fields) => Some(fields),
+        _ => None,
+    }
+}
+
+pub fn cid(op: &[u8]) -> Option<String> {
+    Operation::from_slice(op).ok()?.generate_cid().ok()
+}
+
+/// The binary did:plc created by the genesis operation `op`: the first 15
+/// bytes of the SHA-256 hash in its CID.
+pub fn binary_did(op: &[u8]) -> Option<Vec<u8>> {
+    let cid = cid(op)?;
+    let raw = base32::decode(Alphabet::Rfc4648Lower { padding: false }, &cid[1..])?;
+    Some(raw[4..19].to_vec())
+}
+
+pub fn did(binary_did: &[u8]) -> String {
+    format!(
+        "did:plc:{}",
+        base32::encode(Alphabet::Rfc4648Lower { padding: false }, binary_did)
+    )
+}
+
+/// A chain of operations that all keep the same rotation keys. Operation `i`
+/// is signed by the key at `rotation_key_indices[i]`.
+pub struct History {
+    pub keys: Vec<Key>,
+    pub operations: Vec<Vec<u8>>,
+    pub rotation_key_indices: Vec<usize>,
+}
+
+impl History {
+    /// `len` operations, signed by the rotation keys in turn.
+    pub fn new(keys: Vec<Key>, len: usize) -> Self {
+        let mut operations: Vec<Vec<u8>> = vec![];
+        let mut rotation_key_indices = vec![];
+        for i in 0..len {
+            let index = i % keys.len();
+            let prev = operations.last().map(|op| cid(op).unwrap());
+            let fields = operation_fields(&keys, prev, &format!("user{}.example.com", i));
+            operations.push(sign_operation(fields, &keys[index]));
+            rotation_key_indices.push(index);
+        }
+        Self {
+            keys,
+            operations,
+            rotation_key_indices,
+        }
+    }
+
+    pub fn binary_did(&self) -> Vec<u8> {
+        binary_did(&self.operations[0]).unwrap()
+    }
+
+    pub fn did(&self) -> String {
+        did(&self.binary_did())
+    }
+
+    /// The rotation key indices and the final signature authorizing `msg` with
+    /// the key at `index`.
+    pub fn authorize(&self, msg: &[u8], index: usize) -> (Vec<usize>, Vec<u8>) {
+        let mut rotation_key_indices = self.rotation_key_indices.clone();
+        rotation_key_indices.push(index);
+        (rotation_key_indices, self.keys[index].sign(msg))
+    }
+}
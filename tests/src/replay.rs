@@ -0,0 +1,114 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::reproducible::deployed_binary;
+use crate::test_vectors::load_did;
+use crate::tests::{Env, authorization_witness, cell_data, mint_tx, sign_p256, with_witness};
+use ckb_testtool::builtin::ALWAYS_SUCCESS;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{TransactionBuilder, TransactionView},
+    packed::Byte32,
+    prelude::*,
+};
+use did_ckb_cli::replay::{self, Verdict};
+use std::fs;
+use std::path::Path;
+
+// {"a": 1}
+const DOCUMENT: [u8; 4] = [0xa1, 0x61, 0x61, 0x01];
+
+// Exports `tx` as `ckb-cli mock-tx dump` does
+fn export(env: &Env, tx: &TransactionView, dir: &Path, name: &str) {
+    let mock_tx = env.context.dump_tx(tx).unwrap();
+    fs::write(
+        dir.join(format!("{}.json", name)),
+        serde_json::to_vec_pretty(&mock_tx).unwrap(),
+    )
+    .unwrap();
+}
+
+fn code_hash(tx: &TransactionView) -> Byte32 {
+    tx.output(0).unwrap().type_().to_opt().unwrap().code_hash()
+}
+
+// A corpus of a mint, an authorized mint, a mint with a wrong signature and a
+// transfer without did:ckb, committed with the deployed binary
+fn corpus(dir: &Path) -> Byte32 {
+    let mut env = Env::with_binary(deployed_binary().into());
+    let mint = mint_tx(&mut env, cell_data(&DOCUMENT, None));
+    export(&env, &mint, dir, "1-mint");
+
+    let local_id = load_did("creation");
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+    let sig = sign_p256(tx.hash().as_slice());
+    let authorized = with_witness(tx, authorization_witness(sig, 1));
+    export(&env, &authorized, dir, "2-authorized-mint");
+
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+    let sig = sign_p256(&[0; 32]);
+    let rejected = with_witness(tx, authorization_witness(sig, 1));
+    export(&env, &rejected, dir, "3-rejected-mint");
+
+    let input = env.plain_input();
+    let transfer = TransactionBuilder::default()
+        .input(input)
+        .output(env.plain_output())
+        .output_data(Bytes::new().pack())
+        .build();
+    let transfer = env.context.complete_tx(transfer);
+    export(&env, &transfer, dir, "4-transfer");
+    code_hash(&mint)
+}
+
+#[test]
+fn test_replay_same_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let code_hash = corpus(dir.path());
+    let corpus = replay::load_corpus(dir.path()).unwrap();
+    assert_eq!(corpus.len(), 4);
+
+    let comparison = replay::compare(&corpus, &code_hash, None, &deployed_binary().into());
+    assert!(comparison.compatible(), "{}", comparison.to_markdown());
+    let verdicts: Vec<&Verdict> = comparison.replays.iter().map(|r| &r.old).collect();
+    assert!(matches!(verdicts[0], Verdict::Pass(_)));
+    assert!(matches!(verdicts[1], Verdict::Pass(_)));
+    assert!(matches!(verdicts[2], Verdict::Fail(_)));
+    assert!(matches!(verdicts[3], Verdict::Error(_)));
+    assert!(
+        comparison
+            .to_markdown()
+            .contains("4 transactions replayed, 0 changed.")
+    );
+}
+
+#[test]
+fn test_replay_changed_verdicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let code_hash = corpus(dir.path());
+    let corpus = replay::load_corpus(dir.path()).unwrap();
+
+    // accepts the rejected mint
+    let comparison = replay::compare(&corpus, &code_hash, None, &ALWAYS_SUCCESS);
+    let changed: Vec<_> = comparison.changed().collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].tx_hash, corpus[2].hash());
+    assert!(matches!(changed[0].new, Verdict::Pass(_)));
+
+    // rejects every mint
+    let broken = Bytes::from_static(b"not an ELF");
+    let comparison = replay::compare(&corpus, &code_hash, None, &broken);
+    assert_eq!(comparison.changed().count(), 3);
+    assert!(!comparison.compatible());
+    let report = comparison.to_markdown();
+    assert!(report.contains("4 transactions replayed, 3 changed."));
+    assert!(report.contains(&format!("`{:#x}`", corpus[0].hash())));
+
+    // an explicit old binary
+    let comparison = replay::compare(
+        &corpus,
+        &code_hash,
+        Some(&ALWAYS_SUCCESS),
+        &deployed_binary().into(),
+    );
+    assert_eq!(comparison.changed().count(), 1);
+}
@@ -0,0 +1,100 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use did_ckb_cli::reproducible::{self, Sources, checksum, readme_parameter};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+// The deployed binary, taken from the mainnet deployment transaction so the
+// checks don't need a reproducible build
+pub(crate) fn deployed_binary() -> Vec<u8> {
+    let info: Value =
+        serde_json::from_slice(&fs::read(root().join("deployment/mainnet/info.json")).unwrap())
+            .unwrap();
+    let data = info["cell_tx"]["outputs_data"][0].as_str().unwrap();
+    hex::decode(&data[2..]).unwrap()
+}
+
+fn sources(network: &str, binary: &[u8]) -> (tempfile::TempDir, Sources) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("did-ckb-ts");
+    fs::write(&path, binary).unwrap();
+    let mut sources = reproducible::sources(&root(), network);
+    sources.binary = path;
+    (dir, sources)
+}
+
+#[test]
+fn test_verify_mainnet() {
+    let (_dir, sources) = sources("mainnet", &deployed_binary());
+    let report = reproducible::verify(&sources).unwrap();
+    let failed: Vec<_> = report.checks.iter().filter(|c| !c.passed()).collect();
+    assert!(failed.is_empty(), "{:?}", failed);
+    // checked against info.json, including the type ID args
+    assert!(report.tx_hash.is_some());
+    assert!(
+        report
+            .checks
+            .iter()
+            .any(|c| c.name == "type ID args of the first deployment")
+    );
+    let markdown = report.to_markdown(Some("Alice <ckb1qyq>"));
+    assert!(markdown.contains("All checks passed."));
+    assert!(markdown.ends_with("Signed-off-by: Alice <ckb1qyq>\n"));
+}
+
+#[test]
+fn test_verify_testnet() {
+    // testnet has no info.json
+    let (_dir, sources) = sources("testnet", &deployed_binary());
+    let report = reproducible::verify(&sources).unwrap();
+    assert!(report.passed());
+    assert!(report.tx_hash.is_none());
+}
+
+#[test]
+fn test_verify_other_binary() {
+    let mut binary = deployed_binary();
+    binary[1000] ^= 1;
+    let (_dir, sources) = sources("mainnet", &binary);
+    let report = reproducible::verify(&sources).unwrap();
+    assert!(!report.passed());
+    let failed: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|c| !c.passed())
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(
+        failed,
+        vec![
+            "sha256 in checksums.txt",
+            "data_hash of the latest migration",
+            "data_hash of the info.json recipe",
+            "data_hash of the info.json cell",
+        ]
+    );
+    assert!(report.to_markdown(None).contains("Verification FAILED."));
+}
+
+#[test]
+fn test_parse_checksums_and_readme() {
+    let checksums = "aa  build/release/other\nbb  build/release/did-ckb-ts\n";
+    assert_eq!(checksum(checksums).as_deref(), Some("bb"));
+    assert_eq!(checksum("aa  build/release/other"), None);
+
+    let readme = "### Mainnet\n\n| Parameter | Value |\n| --- | --- |\n| `code_hash` | `0x01` |\n\n### Testnet\n\n| `code_hash` | `0x02` |\n";
+    assert_eq!(
+        readme_parameter(readme, "mainnet", "code_hash").as_deref(),
+        Some("0x01")
+    );
+    assert_eq!(
+        readme_parameter(readme, "testnet", "code_hash").as_deref(),
+        Some("0x02")
+    );
+    assert_eq!(readme_parameter(readme, "mainnet", "tx_hash"), None);
+}
@@ -0,0 +1,261 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::cli::authorized_mint;
+use crate::plc::{self, History, Key, operation_fields, sign_operation, text};
+use crate::test_vectors::{load_did, load_vector};
+use crate::vectors;
+use ckb_did_plc_utils::{
+    cbor4ii::core::Value,
+    error::Error,
+    operation::{parse_local_id, resolve_operation_history, validate_operation_history},
+    state::{PlcState, Service},
+};
+use ckb_types::prelude::*;
+use did_ckb_cli::resolve;
+use did_ckb_molecules::lazy_reader::new_cursor;
+use did_ckb_verifier::resolve_authorization;
+use serde_json::json;
+
+const MSG: [u8; 32] = [0; 32];
+
+fn resolve_history(
+    binary_did: &[u8],
+    history: &[Vec<u8>],
+    rotation_key_indices: &[usize],
+    sig: &[u8],
+) -> Result<PlcState, Error> {
+    let cursors = || history.iter().cloned().map(new_cursor).collect();
+    let state = resolve_operation_history(
+        binary_did,
+        cursors(),
+        rotation_key_indices.to_vec(),
+        &MSG,
+        sig,
+    );
+    // validates the same way
+    let result = validate_operation_history(
+        binary_did,
+        cursors(),
+        rotation_key_indices.to_vec(),
+        &MSG,
+        sig,
+    );
+    if let Err(e) = &state {
+        if let Err(expected) = result {
+            assert_eq!(e.error_code(), expected.error_code());
+        }
+    }
+    state
+}
+
+fn field<'a>(op: &'a [(Value, Value)], name: &str) -> &'a Value {
+    &op.iter().find(|(k, _)| *k == text(name)).unwrap().1
+}
+
+fn texts(value: &Value) -> Vec<String> {
+    let Value::Array(items) = value else {
+        panic!("not an array")
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Value::Text(s) => s.clone(),
+            _ => panic!("not a text"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_resolve_test_vectors() {
+    let history: Vec<Vec<u8>> = [
+        "1-did-creation.cbor",
+        "2-update-handle.cbor",
+        "3-update-pds.cbor",
+        "4-update-atproto-key.cbor",
+        "5-update-rotation-keys.cbor",
+        "6-update-handle.cbor",
+    ]
+    .iter()
+    .map(|name| load_vector(name))
+    .collect();
+    let did = load_did("creation");
+    let binary_did = parse_local_id(did.as_bytes()).unwrap();
+    let sig = vectors::p256_key().sign(&MSG);
+    let state = resolve_history(&binary_did, &history, &[0, 0, 0, 0, 0, 1, 1], &sig).unwrap();
+
+    let latest = plc::decode_fields(&history[5]).unwrap();
+    assert_eq!(state.did, did);
+    assert_eq!(state.head, plc::cid(&history[5]).unwrap());
+    assert_eq!(state.rotation_keys, texts(field(&latest, "rotationKeys")));
+    assert_eq!(state.also_known_as, texts(field(&latest, "alsoKnownAs")));
+    // the atproto key of operation 4, still in place
+    let atproto = plc::decode_fields(&history[3]).unwrap();
+    let Value::Map(methods) = field(&atproto, "verificationMethods") else {
+        panic!("not a map")
+    };
+    assert_eq!(
+        state
+            .verification_methods
+            .iter()
+            .map(|(id, key)| (text(id), text(key)))
+            .collect::<Vec<_>>(),
+        *methods
+    );
+    assert_eq!(state.services.len(), 1);
+    assert_eq!(
+        state.services["atproto_pds"].r#type,
+        "AtprotoPersonalDataServer"
+    );
+
+    // the same errors as `validate_operation_history`
+    let e = resolve_history(&binary_did, &history, &[0, 0, 0, 0, 0, 1, 0], &sig).unwrap_err();
+    assert_eq!(e.error_code(), 34);
+    let e = resolve_history(&binary_did, &history, &[0, 0, 0, 0, 0, 1, 9], &sig).unwrap_err();
+    assert_eq!(e.error_code(), 42);
+}
+
+#[test]
+fn test_resolve_legacy_history() {
+    let genesis = load_vector("1-did-creation-legacy.cbor");
+    let did = load_did("creation-legacy");
+    let binary_did = parse_local_id(did.as_bytes()).unwrap();
+    // the keys of the generator after operation 4
+    let signing_key = vectors::secp256k1_key(2);
+    let recovery_key = vectors::p256_key();
+
+    // a legacy `create` as the latest operation, normalized like any other
+    let sig = signing_key.sign(&MSG);
+    let state = resolve_history(&binary_did, &[genesis.clone()], &[0, 0], &sig).unwrap();
+    assert_eq!(
+        state,
+        PlcState {
+            did: did.clone(),
+            rotation_keys: vec![signing_key.did_key(), recovery_key.did_key()],
+            verification_methods: [("atproto".to_owned(), signing_key.did_key())].into(),
+            also_known_as: vec!["at://ali.example.com".to_owned()],
+            services: [(
+                "atproto_pds".to_owned(),
+                Service {
+                    r#type: "AtprotoPersonalDataServer".to_owned(),
+                    endpoint: "https://example2.com".to_owned(),
+                }
+            )]
+            .into(),
+            head: plc::cid(&genesis).unwrap(),
+        }
+    );
+    let sig = recovery_key.sign(&MSG);
+    resolve_history(&binary_did, &[genesis.clone()], &[0, 1], &sig).unwrap();
+
+    let update = load_vector("2-update-rotation-keys-legacy.cbor");
+    let state = resolve_history(&binary_did, &[genesis, update], &[0, 1, 1], &sig).unwrap();
+    assert_eq!(state.rotation_keys[1], recovery_key.did_key());
+
+    // a bare handle and service get their prefixes
+    let key = Key::secp256k1(1);
+    let create = vectors::legacy_create(&key, &Key::p256(2), "alice.test", "pds.example.com");
+    let binary_did = plc::binary_did(&create).unwrap();
+    let state = resolve_history(&binary_did, &[create], &[0, 0], &key.sign(&MSG)).unwrap();
+    assert_eq!(state.also_known_as, ["at://alice.test"]);
+    assert_eq!(
+        state.services["atproto_pds"].endpoint,
+        "https://pds.example.com"
+    );
+}
+
+#[test]
+fn test_resolve_generated_history() {
+    let keys = vec![Key::secp256k1(1), Key::p256(2)];
+    let history = History::new(keys.clone(), 3);
+    let (indices, sig) = history.authorize(&MSG, 1);
+    let state =
+        resolve_history(&history.binary_did(), &history.operations, &indices, &sig).unwrap();
+    assert_eq!(
+        state,
+        PlcState {
+            did: history.did(),
+            rotation_keys: keys.iter().map(Key::did_key).collect(),
+            verification_methods: [("atproto".to_owned(), keys[0].did_key())].into(),
+            also_known_as: vec!["at://user2.example.com".to_owned()],
+            services: [(
+                "atproto_pds".to_owned(),
+                Service {
+                    r#type: "AtprotoPersonalDataServer".to_owned(),
+                    endpoint: "https://pds.example.com".to_owned(),
+                }
+            )]
+            .into(),
+            head: plc::cid(&history.operations[2]).unwrap(),
+        }
+    );
+}
+
+#[test]
+fn test_resolve_malformed_state() {
+    // rejected by the validation before the state is read
+    let key = Key::secp256k1(1);
+    for (name, value, code) in [
+        ("alsoKnownAs", text("at://alice.test"), 68),
+        ("verificationMethods", Value::Array(vec![]), 67),
+        ("services", Value::Integer(5), 69),
+        (
+            "services",
+            Value::Map(vec![(text("atproto_pds"), Value::Map(vec![]))]),
+            69,
+        ),
+    ] {
+        let mut fields = operation_fields(&[key.clone()], None, "alice.test");
+        for (k, v) in fields.iter_mut() {
+            if *k == text(name) {
+                *v = value.clone();
+            }
+        }
+        let genesis = sign_operation(fields, &key);
+        let binary_did = plc::binary_did(&genesis).unwrap();
+        let sig = key.sign(&MSG);
+        let e = resolve_history(&binary_did, &[genesis], &[0, 0], &sig).unwrap_err();
+        assert_eq!(e.error_code(), code, "{}", name);
+    }
+}
+
+#[test]
+fn test_resolve_authorization() {
+    let keys = vec![Key::secp256k1(1), Key::p256(2)];
+    let history = History::new(keys.clone(), 2);
+    let tx = authorized_mint(&history, 0);
+    let witness = tx.witnesses().get(0).unwrap().raw_data();
+    let state = resolve_authorization(&witness, &history.did(), &tx.hash().unpack()).unwrap();
+    assert_eq!(state.head, plc::cid(&history.operations[1]).unwrap());
+
+    let did = history.did();
+    let multibase = &keys[0].did_key()["did:key:".len()..];
+    assert_eq!(
+        resolve::to_document(&state),
+        json!({
+            "@context": [
+                "https://www.w3.org/ns/did/v1",
+                "https://w3id.org/security/multikey/v1",
+            ],
+            "id": did,
+            "alsoKnownAs": ["at://user1.example.com"],
+            "verificationMethod": [{
+                "id": format!("{}#atproto", did),
+                "type": "Multikey",
+                "controller": did,
+                "publicKeyMultibase": multibase,
+            }],
+            "service": [{
+                "id": "#atproto_pds",
+                "type": "AtprotoPersonalDataServer",
+                "serviceEndpoint": "https://pds.example.com",
+            }],
+        })
+    );
+    assert_eq!(
+        resolve::to_json(&state)["rotationKeys"],
+        json!([keys[0].did_key(), keys[1].did_key()])
+    );
+
+    let e = resolve_authorization(&witness, &history.did(), &[1; 32]).unwrap_err();
+    assert_eq!(e.error_code(), 34);
+}
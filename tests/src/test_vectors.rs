@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
+use crate::vectors::{self, Variant};
 use ckb_did_plc_utils::{
     base32::{self, Alphabet},
     base64::{
@@ -20,25 +21,28 @@ use ckb_did_plc_utils::{
     pubkey::decode_base58btc,
     reader::validate_cbor_format,
 };
+use did_ckb_molecules::lazy_reader::new_cursor;
 use molecule::lazy_reader::{Cursor, Error as MoleculeError, Read};
 use std::{boxed::Box, fs::read, vec::Vec};
 
 fn test_one_vector(prev_file: &str, cur_file: &str, rotation_key_index: usize) {
-    let prev_path = get_test_vector_path(prev_file);
-    let cur_path = get_test_vector_path(cur_file);
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector(prev_file);
+    let cur_buf = load_vector(cur_file);
     let result = validate_2_operations(&prev_buf, &cur_buf, rotation_key_index);
     assert!(result.is_ok());
 }
 
-fn load_did(name: &str) -> String {
+pub(crate) fn load_did(name: &str) -> String {
     let path = format!("{}.did", name);
-    let full_path = get_test_vector_path(&path);
-    let did = read(&full_path).unwrap_or_else(|_| panic!("Failed to read {}", full_path));
-    String::from_utf8(did).unwrap_or_else(|_| panic!("Failed to parse DID from {}", full_path))
+    let did = load_vector(&path);
+    String::from_utf8(did).unwrap_or_else(|_| panic!("Failed to parse DID from {}", path))
 }
 
+pub(crate) fn load_vector(name: &str) -> Vec<u8> {
+    vectors::get(name)
+}
+
+// The vectors written by `pnpm start --no-random` in tools/gen-test-vectors
 fn get_test_vector_path(filename: &str) -> String {
     format!("../tools/gen-test-vectors/test-vectors/{}", filename)
 }
@@ -80,10 +84,8 @@ fn test_vectors_5_6() {
 #[test]
 fn test_vectors_6_7() {
     // tombstone is not allowed
-    let prev_path = get_test_vector_path("6-update-handle.cbor");
-    let cur_path = get_test_vector_path("7-tombstone.cbor");
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector("6-update-handle.cbor");
+    let cur_buf = load_vector("7-tombstone.cbor");
     let result = validate_2_operations(&prev_buf, &cur_buf, 0);
     assert!(result.is_err());
 }
@@ -98,9 +100,7 @@ fn test_vector_legacy_1_2() {
 
 #[test]
 fn test_genesis_operation() {
-    let genesis_path = get_test_vector_path("1-did-creation.cbor");
-    let genesis_buf =
-        read(&genesis_path).unwrap_or_else(|_| panic!("Failed to read {}", genesis_path));
+    let genesis_buf = load_vector("1-did-creation.cbor");
     let did = load_did("creation");
     let result = validate_genesis_operation(&genesis_buf, &parse_did(&did), 0);
     assert!(result.is_ok());
@@ -108,17 +108,14 @@ fn test_genesis_operation() {
 
 #[test]
 fn test_genesis_operation_wrong_did() {
-    let genesis_path = get_test_vector_path("1-did-creation.cbor");
-    let genesis_buf = read(&genesis_path).expect(&format!("Failed to read {}", genesis_path));
-    let result = validate_genesis_operation(&genesis_buf, &vec![0; 15], 0);
+    let genesis_buf = load_vector("1-did-creation.cbor");
+    let result = validate_genesis_operation(&genesis_buf, &[0; 15], 0);
     assert!(matches!(result, Err(Error::DidMismatched)));
 }
 
 #[test]
 fn test_legacy_genesis_operation() {
-    let genesis_path = get_test_vector_path("1-did-creation-legacy.cbor");
-    let genesis_buf =
-        read(&genesis_path).unwrap_or_else(|_| panic!("Failed to read {}", genesis_path));
+    let genesis_buf = load_vector("1-did-creation-legacy.cbor");
     let did = load_did("creation-legacy");
     let result = validate_genesis_operation(&genesis_buf, &parse_did(&did), 0);
     assert!(result.is_ok());
@@ -148,99 +145,39 @@ fn remove_operation_services(buf: &[u8]) -> Vec<u8> {
     writer.into_inner()
 }
 
-fn remove_operation_type(buf: &[u8]) -> Vec<u8> {
-    let mut reader = SliceReader::new(buf);
-    let raw = Value::decode(&mut reader).unwrap();
-    let update_raw = match raw {
-        Value::Map(map) => {
-            let new_map = map
-                .into_iter()
-                .map(|(key, value)| {
-                    if key == Value::Text("type".to_string()) {
-                        // remove the type field
-                        (key, Value::Null)
-                    } else {
-                        (key, value)
-                    }
-                })
-                .collect();
-            Value::Map(new_map)
-        }
-        _ => raw,
-    };
-    let mut writer = BufWriter::new(Vec::new());
-    update_raw.encode(&mut writer).unwrap();
-    writer.into_inner()
-}
-
-fn modify_operation_sig(buf: &[u8]) -> Vec<u8> {
-    let mut reader = SliceReader::new(buf);
-    let raw = Value::decode(&mut reader).unwrap();
-    let update_raw = match raw {
-        Value::Map(map) => {
-            let new_map = map
-                .into_iter()
-                .map(|(key, value)| {
-                    if key == Value::Text("sig".to_string()) {
-                        match value {
-                            Value::Text(sig) => {
-                                let engine = URL_SAFE_NO_PAD;
-                                let sig = engine.decode(sig).unwrap();
-                                let mut sig = sig.clone();
-                                sig[0] ^= 1;
-                                let sig = engine.encode(sig);
-                                (key, Value::Text(sig))
-                            }
-                            _ => (key, value),
-                        }
-                    } else {
-                        (key, value)
-                    }
-                })
-                .collect();
-            Value::Map(new_map)
-        }
-        _ => raw,
-    };
-    let mut writer = BufWriter::new(Vec::new());
-    update_raw.encode(&mut writer).unwrap();
-    writer.into_inner()
-}
-
 #[test]
 fn test_vectors_1_2_wrong_sig() {
-    let prev_path = get_test_vector_path("1-did-creation.cbor");
-    let cur_path = get_test_vector_path("2-update-handle.cbor");
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector("1-did-creation.cbor");
+    let cur_buf = load_vector("2-update-handle.cbor");
 
-    let cur_buf = modify_operation_sig(&cur_buf);
+    let cur_buf = vectors::variant(&cur_buf, Variant::BadSignature);
 
     let result = validate_2_operations(&prev_buf, &cur_buf, 0);
     assert!(result.is_err());
 }
 #[test]
 fn test_vectors_1_2_wrong_cid() {
-    let prev_path = get_test_vector_path("1-did-creation.cbor");
-    let cur_path = get_test_vector_path("2-update-handle.cbor");
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector("1-did-creation.cbor");
+    let cur_buf = load_vector("2-update-handle.cbor");
 
     // update previous operation's signature(part of content) to make CID changed
-    let prev_buf = modify_operation_sig(&prev_buf);
+    let prev_buf = vectors::variant(&prev_buf, Variant::BadSignature);
 
     let result = validate_2_operations(&prev_buf, &cur_buf, 0);
     assert!(matches!(result, Err(Error::InvalidPrev)));
+
+    let prev_buf = load_vector("1-did-creation.cbor");
+    let cur_buf = vectors::variant(&cur_buf, Variant::BadCid);
+    let result = validate_2_operations(&prev_buf, &cur_buf, 0);
+    assert!(matches!(result, Err(Error::InvalidPrev)));
 }
 
 #[test]
 fn test_vector_1_2_wrong_operation_type() {
-    let prev_path = get_test_vector_path("1-did-creation.cbor");
-    let cur_path = get_test_vector_path("2-update-handle.cbor");
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector("1-did-creation.cbor");
+    let cur_buf = load_vector("2-update-handle.cbor");
 
-    let prev_buf = remove_operation_type(&prev_buf);
+    let prev_buf = vectors::variant(&prev_buf, Variant::WrongType);
 
     let result = validate_2_operations(&prev_buf, &cur_buf, 0);
     assert!(matches!(result, Err(Error::InvalidOperation)));
@@ -248,10 +185,8 @@ fn test_vector_1_2_wrong_operation_type() {
 
 #[test]
 fn test_vector_1_2_wrong_operation_content() {
-    let prev_path = get_test_vector_path("1-did-creation.cbor");
-    let cur_path = get_test_vector_path("2-update-handle.cbor");
-    let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
-    let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
+    let prev_buf = load_vector("1-did-creation.cbor");
+    let cur_buf = load_vector("2-update-handle.cbor");
 
     // remove operation service to make content changed, it causes the signature validation failed.
     let cur_buf = remove_operation_services(&cur_buf);
@@ -262,8 +197,7 @@ fn test_vector_1_2_wrong_operation_content() {
 #[test]
 fn test_not_genesis_operation() {
     // Only true genesis operations (with prev as null) can pass validation
-    let non_genesis_path = get_test_vector_path("2-update-handle.cbor"); // This is an update operation with prev
-    let buf = read(&non_genesis_path).expect("Failed to read file");
+    let buf = load_vector("2-update-handle.cbor");
     let binary_did = vec![0u8; 15]; // Arbitrary DID, should match in practice
     let result = validate_genesis_operation(&buf, &binary_did, 0);
     assert!(matches!(result, Err(Error::NotGenesisOperation)));
@@ -371,6 +305,12 @@ fn test_utils_error_invalid_did_format() {
     let invalid_base32 = b"did:plc:invalid_base32";
     let result2 = parse_local_id(invalid_base32);
     assert!(matches!(result2, Err(Error::InvalidDidFormat)));
+
+    // Test non-canonical lengths, which base32 would decode to the same or a
+    // truncated binary DID
+    let did = load_did("creation");
+    assert!(parse_local_id(format!("{}a", did).as_bytes()).is_err());
+    assert!(parse_local_id(&did.as_bytes()[..did.len() - 1]).is_err());
 }
 
 #[test]
@@ -390,8 +330,7 @@ fn test_utils_error_tombstone_in_history() {
 
     let mut history: Vec<Cursor> = vec![];
     for file in files {
-        let path = get_test_vector_path(file);
-        let buf = read(&path).unwrap_or_else(|_| panic!("Failed to read {}", path));
+        let buf = load_vector(file);
         let total_size = buf.len();
         history.push(Cursor::new(
             total_size,
@@ -505,8 +444,8 @@ fn did_key_from_bytes(bytes: &[u8]) -> String {
 
 #[test]
 fn test_rotation_keys_decode_error() {
-    let prev = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
-    let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = load_vector("2-update-handle.cbor");
 
     let prev_bad = set_rotation_keys_to_string(&prev);
     let new_cid = {
@@ -520,14 +459,15 @@ fn test_rotation_keys_decode_error() {
 
 #[test]
 fn test_invalid_key() {
-    let prev = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
-    let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = load_vector("2-update-handle.cbor");
 
     {
+        // the structure of both operations is checked before the chain
         let prev_bad = set_rotation_keys_to_string(&prev);
         let res = validate_2_operations(&prev_bad, &cur, 0);
-        eprintln!("[case0] expect InvalidPrev -> got: {res:?}");
-        assert!(matches!(res, Err(Error::InvalidPrev)));
+        eprintln!("[case0] expect RotationKeysDecodeError -> got: {res:?}");
+        assert!(matches!(res, Err(Error::RotationKeysDecodeError)));
     }
 
     // Helper to run a case that expects InvalidKey:
@@ -640,8 +580,8 @@ fn test_invalid_key() {
 
 #[test]
 fn test_invalid_key_index() {
-    let prev = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
-    let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = load_vector("2-update-handle.cbor");
 
     let res = validate_2_operations(&prev, &cur, 99);
     assert!(matches!(res, Err(Error::InvalidKeyIndex)));
@@ -661,8 +601,8 @@ fn test_invalid_signature() {
     };
     use std::fs::read;
 
-    let prev = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
-    let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = load_vector("2-update-handle.cbor");
 
     // helper: set top-level "sig" as Text to a specific string
     let set_sig_text = |buf: &[u8], s: &str| {
@@ -708,8 +648,8 @@ fn test_invalid_signature_padding() {
     use ckb_did_plc_utils::{error::Error, operation::validate_2_operations};
     use std::fs::read;
 
-    let prev = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
-    let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
+    let prev = load_vector("1-did-creation.cbor");
+    let cur = load_vector("2-update-handle.cbor");
 
     // ---- read original sig text from `cur` (must be URL_SAFE_NO_PAD, meaning no '=') ----
     let orig_sig: String = 'find_sig: {
@@ -779,3 +719,283 @@ fn test_invalid_signature_padding() {
         assert!(matches!(res, Err(Error::InvalidSignaturePadding)));
     }
 }
+
+// Run with `cargo test -p ckb-did-plc-utils-tests typescript -- --ignored`
+// after `pnpm start --no-random` in tools/gen-test-vectors, as in CI
+#[test]
+#[ignore]
+fn test_generated_vectors_match_typescript() {
+    let mut count = 0;
+    for (name, data) in vectors::generate() {
+        // the tool doesn't write every vector
+        if let Ok(expected) = read(get_test_vector_path(&name)) {
+            assert_eq!(
+                data, expected,
+                "{} differs from the TypeScript vector",
+                name
+            );
+            count += 1;
+        }
+    }
+    assert!(count > 0, "no TypeScript vector, run the tool first");
+}
+
+#[test]
+fn test_generated_history() {
+    let did = load_did("creation");
+    let history: Vec<Vec<u8>> = [
+        "1-did-creation.cbor",
+        "2-update-handle.cbor",
+        "3-update-pds.cbor",
+        "4-update-atproto-key.cbor",
+        "5-update-rotation-keys.cbor",
+        "6-update-handle.cbor",
+    ]
+    .iter()
+    .map(|name| load_vector(name))
+    .collect();
+    let cursors = history.into_iter().map(new_cursor).collect();
+    // the final signature is made by the p256 rotation key
+    let msg = [0u8; 32];
+    let sig = vectors::p256_key().sign(&msg);
+    let result = validate_operation_history(
+        &parse_did(&did),
+        cursors,
+        vec![0, 0, 0, 0, 0, 1, 1],
+        &msg,
+        &sig,
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_unspecified_rotation_key_indices() {
+    use crate::plc::{History, Key};
+    use ckb_did_plc_utils::operation::UNSPECIFIED_KEY_INDEX;
+
+    const U: usize = UNSPECIFIED_KEY_INDEX;
+    let msg = [0u8; 32];
+    let validate = |history: &History, indices: Vec<usize>, sig: &[u8]| {
+        let cursors = history.operations.iter().cloned().map(new_cursor).collect();
+        validate_operation_history(&history.binary_did(), cursors, indices, &msg, sig)
+    };
+
+    // operations signed by keys 0, 1, 2 and 0, authorized by key 2
+    let keys = vec![Key::secp256k1(1), Key::p256(2), Key::secp256k1(3)];
+    let history = History::new(keys.clone(), 4);
+    let sig = keys[2].sign(&msg);
+    assert_eq!(
+        validate(&history, vec![U; 5], &sig).unwrap(),
+        [0, 1, 2, 0, 2]
+    );
+    assert_eq!(
+        validate(&history, vec![0, U, 2, U, 2], &sig).unwrap(),
+        [0, 1, 2, 0, 2]
+    );
+    // a specified index is still checked as is
+    let e = validate(&history, vec![U, 0, U, U, U], &sig).unwrap_err();
+    assert_eq!(e.error_code(), 36);
+    // no key signed the authorization
+    let e = validate(&history, vec![U; 5], &Key::p256(4).sign(&msg)).unwrap_err();
+    assert_eq!(e.error_code(), 34);
+
+    // operations signed by keys 0 to 4: finding them all takes 10 failed
+    // verifications, more than `MAX_KEY_TRIALS`
+    let keys: Vec<Key> = (1..=5).map(Key::secp256k1).collect();
+    let history = History::new(keys.clone(), 5);
+    let sig = keys[0].sign(&msg);
+    let e = validate(&history, vec![U; 6], &sig).unwrap_err();
+    assert_eq!(e.error_code(), 71);
+    assert_eq!(
+        validate(&history, vec![U, U, U, U, 4, U], &sig).unwrap(),
+        [0, 1, 2, 3, 4, 0]
+    );
+}
+
+#[test]
+fn test_unspecified_rotation_key_invalid_key() {
+    use crate::plc::{self, Key, operation_fields, text};
+    use ckb_did_plc_utils::operation::UNSPECIFIED_KEY_INDEX;
+
+    const U: usize = UNSPECIFIED_KEY_INDEX;
+    let msg = [0u8; 32];
+    // a well-formed did:key, but not a point of the curve
+    let mut raw = vec![0xe7, 0x01];
+    raw.extend_from_slice(&[0x05; 33]);
+    let invalid = format!(
+        "did:key:{}",
+        multibase::encode(multibase::Base::Base58Btc, raw)
+    );
+    let key = Key::secp256k1(1);
+    let mut fields = operation_fields(&[key.clone()], None, "alice.test");
+    for (k, v) in fields.iter_mut() {
+        if *k == text("rotationKeys") {
+            *v = Value::Array(vec![text(&invalid), text(&key.did_key())]);
+        }
+    }
+    let genesis = plc::sign_operation(fields, &key);
+    let validate = |indices: Vec<usize>| {
+        let binary_did = plc::binary_did(&genesis).unwrap();
+        let cursors = vec![new_cursor(genesis.clone())];
+        validate_operation_history(&binary_did, cursors, indices, &msg, &key.sign(&msg))
+    };
+
+    // the invalid key fails on its own, the next one is tried
+    assert_eq!(validate(vec![U, U]).unwrap(), [1, 1]);
+    assert_eq!(validate(vec![0, 1]).unwrap_err().error_code(), 36);
+    assert_eq!(validate(vec![1, 0]).unwrap_err().error_code(), 33);
+}
+
+#[test]
+fn test_unspecified_rotation_key_malformed_signature() {
+    use crate::plc::{self, History, Key, text};
+    use ckb_did_plc_utils::operation::UNSPECIFIED_KEY_INDEX;
+
+    const U: usize = UNSPECIFIED_KEY_INDEX;
+    let msg = [0u8; 32];
+    let validate = |history: &History, indices: Vec<usize>, sig: &[u8]| {
+        let cursors = history.operations.iter().cloned().map(new_cursor).collect();
+        validate_operation_history(&history.binary_did(), cursors, indices, &msg, sig)
+    };
+
+    // operations signed by keys 0 to 4: finding the first four takes 6 failed
+    // verifications, trying all 5 keys once more would exceed `MAX_KEY_TRIALS`
+    let keys: Vec<Key> = (1..=5).map(Key::secp256k1).collect();
+    let history = History::new(keys.clone(), 5);
+    let sig = keys[0].sign(&msg);
+    let indices = vec![U, U, U, U, U, U];
+    assert_eq!(
+        validate(&history, vec![U, U, U, U, 4, U], &sig).unwrap(),
+        [0, 1, 2, 3, 4, 0]
+    );
+
+    // a final signature of another length fails whatever the key
+    let e = validate(&history, vec![U, U, U, U, 4, U], &sig[..63]).unwrap_err();
+    assert!(matches!(e.root(), Error::InvalidSignature));
+
+    // so does the signature of an operation, not base64 or of another length
+    for malformed in ["!!!", &"A".repeat(84)] {
+        let mut history = History::new(keys.clone(), 5);
+        let mut fields = plc::decode_fields(&history.operations[4]).unwrap();
+        for (k, v) in fields.iter_mut() {
+            if *k == text("sig") {
+                *v = text(malformed);
+            }
+        }
+        history.operations[4] = plc::encode(&Value::Map(fields));
+        let e = validate(&history, indices.clone(), &sig).unwrap_err();
+        assert!(matches!(e.root(), Error::InvalidSignature), "{}", malformed);
+    }
+}
+
+#[test]
+fn test_operation_schema() {
+    use crate::plc::{self, Key, operation_fields, sign_operation, text};
+
+    let keys: Vec<Key> = (1..=6).map(Key::secp256k1).collect();
+    // a genesis operation with `name` set to `value`
+    let genesis = |rotation_keys: &[Key], name: &str, value: Option<Value>| {
+        let mut fields = operation_fields(rotation_keys, None, "alice.test");
+        if let Some(value) = value {
+            for (k, v) in fields.iter_mut() {
+                if *k == text(name) {
+                    *v = value.clone();
+                }
+            }
+        }
+        sign_operation(fields, &keys[0])
+    };
+    let validate = |op: &[u8]| validate_genesis_operation(op, &plc::binary_did(op).unwrap(), 0);
+
+    // 1 to 5 rotation keys
+    for count in [1, 5] {
+        validate(&genesis(&keys[..count], "", None)).unwrap();
+    }
+    let e = validate(&genesis(&keys[..6], "", None)).unwrap_err();
+    assert!(matches!(e, Error::InvalidRotationKeyCount));
+    let e = validate(&genesis(
+        &keys[..1],
+        "rotationKeys",
+        Some(Value::Array(vec![])),
+    ))
+    .unwrap_err();
+    assert!(matches!(e, Error::InvalidRotationKeyCount));
+
+    let did_key = text(&keys[1].did_key());
+    let service = |members: Vec<(&str, Value)>| {
+        Value::Map(vec![(
+            text("atproto_pds"),
+            Value::Map(members.into_iter().map(|(k, v)| (text(k), v)).collect()),
+        )])
+    };
+    let cases: Vec<(&str, Value, i8)> = vec![
+        ("rotationKeys", text("did:key:z"), 32),
+        (
+            "rotationKeys",
+            Value::Array(vec![text("did:web:example.com")]),
+            33,
+        ),
+        (
+            "rotationKeys",
+            Value::Array(vec![did_key.clone(), Value::Integer(1)]),
+            33,
+        ),
+        (
+            "verificationMethods",
+            Value::Array(vec![did_key.clone()]),
+            67,
+        ),
+        (
+            "verificationMethods",
+            Value::Map(vec![(text("atproto"), text("zQ3shSigningKey"))]),
+            67,
+        ),
+        (
+            "verificationMethods",
+            Value::Map(vec![(Value::Integer(0), did_key.clone())]),
+            67,
+        ),
+        ("alsoKnownAs", text("at://alice.test"), 68),
+        (
+            "alsoKnownAs",
+            Value::Array(vec![text("https://alice.test")]),
+            68,
+        ),
+        ("alsoKnownAs", Value::Array(vec![text("at://")]), 68),
+        ("services", Value::Integer(5), 69),
+        (
+            "services",
+            service(vec![("type", text("AtprotoPersonalDataServer"))]),
+            69,
+        ),
+        (
+            "services",
+            service(vec![
+                ("type", text("AtprotoPersonalDataServer")),
+                ("endpoint", Value::Integer(443)),
+            ]),
+            69,
+        ),
+    ];
+    for (name, value, code) in cases {
+        let e = validate(&genesis(&keys[..1], name, Some(value.clone()))).unwrap_err();
+        assert_eq!(e.error_code(), code, "{} = {:?}", name, value);
+    }
+
+    // empty collections, and other members of a service
+    for (name, value) in [
+        ("verificationMethods", Value::Map(vec![])),
+        ("alsoKnownAs", Value::Array(vec![])),
+        ("services", Value::Map(vec![])),
+        (
+            "services",
+            service(vec![
+                ("type", text("AtprotoPersonalDataServer")),
+                ("endpoint", text("https://pds.example.com")),
+                ("x-priority", Value::Integer(1)),
+            ]),
+        ),
+    ] {
+        validate(&genesis(&keys[..1], name, Some(value))).unwrap();
+    }
+}
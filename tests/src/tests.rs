@@ -1,52 +1,388 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::Loader;
+use crate::binding::bound_state_document;
+use crate::test_vectors::{load_did, load_vector};
+use crate::vectors;
+use ckb_testtool::builtin::ALWAYS_SUCCESS;
+use ckb_testtool::ckb_error::Error as CKBError;
+use ckb_testtool::ckb_hash::new_blake2b;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{TransactionBuilder, TransactionView},
+    packed::{CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use did_ckb_molecules::types::{
+    DidCkbData, DidCkbDataV1, DidCkbWitness, PlcAuthorization, WitnessArgs,
+};
 
-// generated unit test for contract did-ckb-ts
-#[test]
-fn test_did_ckb_ts() {
-    // deploy contract
-    let mut context = Context::default();
-    let contract_bin: Bytes = Loader::default().load_binary("did-ckb-ts");
-    let out_point = context.deploy_cell(contract_bin);
+pub(crate) const MAX_CYCLES: u64 = 3_500_000_000;
+const CAPACITY: u64 = 1_000 * 100_000_000;
+// {"a": 1}
+const DOCUMENT: [u8; 4] = [0xa1, 0x61, 0x61, 0x01];
 
-    // prepare scripts
-    let lock_script = context
-        .build_script(&out_point, Bytes::from(vec![42]))
-        .expect("script");
+// Operations 1 to 6 of the test vectors and the indices of the rotation keys
+// signing them
+const HISTORY: [&str; 6] = [
+    "1-did-creation.cbor",
+    "2-update-handle.cbor",
+    "3-update-pds.cbor",
+    "4-update-atproto-key.cbor",
+    "5-update-rotation-keys.cbor",
+    "6-update-handle.cbor",
+];
+const HISTORY_INDICES: [u8; 6] = [0, 0, 0, 0, 0, 1];
 
-    // prepare cells
-    let input_out_point = context.create_cell(
-        CellOutput::new_builder()
-            .capacity(1000u64.pack())
-            .lock(lock_script.clone())
-            .build(),
-        Bytes::new(),
-    );
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
-        .build();
-    let outputs = vec![
+// The rotation keys of operation 6: the secp256k1 key number 3 at index 0 and
+// the p256 key at index 1
+fn sign_secp256k1(msg: &[u8]) -> Vec<u8> {
+    vectors::secp256k1_key(3).sign(msg)
+}
+
+pub(crate) fn sign_p256(msg: &[u8]) -> Vec<u8> {
+    vectors::p256_key().sign(msg)
+}
+
+pub(crate) struct Env {
+    pub(crate) context: Context,
+    did_ckb: OutPoint,
+    lock: Script,
+}
+
+impl Env {
+    pub(crate) fn new() -> Self {
+        Self::with_binary(Loader::default().load_binary("did-ckb-ts"))
+    }
+
+    pub(crate) fn with_binary(binary: Bytes) -> Self {
+        let mut context = Context::default();
+        let did_ckb = context.deploy_cell(binary);
+        let always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+        let lock = context
+            .build_script(&always_success, Bytes::new())
+            .expect("lock script");
+        Self {
+            context,
+            did_ckb,
+            lock,
+        }
+    }
+
+    pub(crate) fn plain_output(&self) -> CellOutput {
         CellOutput::new_builder()
-            .capacity(500u64.pack())
-            .lock(lock_script.clone())
-            .build(),
+            .capacity(CAPACITY.pack())
+            .lock(self.lock.clone())
+            .build()
+    }
+
+    fn did_ckb_output(&mut self, args: Bytes) -> CellOutput {
+        let type_ = self
+            .context
+            .build_script(&self.did_ckb, args)
+            .expect("type script");
         CellOutput::new_builder()
-            .capacity(500u64.pack())
-            .lock(lock_script)
-            .build(),
-    ];
+            .capacity(CAPACITY.pack())
+            .lock(self.lock.clone())
+            .type_(Some(type_).pack())
+            .build()
+    }
+
+    fn input(&mut self, output: CellOutput, data: Bytes) -> CellInput {
+        let out_point = self.context.create_cell(output, data);
+        CellInput::new_builder().previous_output(out_point).build()
+    }
+
+    pub(crate) fn plain_input(&mut self) -> CellInput {
+        let output = self.plain_output();
+        self.input(output, Bytes::new())
+    }
+
+    fn verify(&mut self, tx: TransactionView) -> Result<u64, i8> {
+        let tx = self.context.complete_tx(tx);
+        self.context.verify_tx(&tx, MAX_CYCLES).map_err(error_code)
+    }
+}
 
-    let outputs_data = vec![Bytes::new(); 2];
+fn error_code(err: CKBError) -> i8 {
+    let message = err.to_string();
+    message
+        .split("error code ")
+        .nth(1)
+        .and_then(|s| s.split_whitespace().next())
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| panic!("no error code in {}", message))
+}
 
-    // build transaction
+// The type ID args of the output at `index` created with `input` as the first
+// input
+fn type_id_args(input: &CellInput, index: u64) -> Bytes {
+    let mut blake2b = new_blake2b();
+    blake2b.update(input.as_slice());
+    blake2b.update(&index.to_le_bytes());
+    let mut hash = [0u8; 32];
+    blake2b.finalize(&mut hash);
+    Bytes::copy_from_slice(&hash[..20])
+}
+
+pub(crate) fn cell_data(document: &[u8], local_id: Option<&str>) -> Bytes {
+    DidCkbData::DidCkbDataV1(DidCkbDataV1 {
+        document: document.to_vec(),
+        local_id: local_id.map(str::to_owned),
+    })
+    .to_vec()
+    .into()
+}
+
+fn history() -> Vec<Vec<u8>> {
+    HISTORY.iter().map(|name| load_vector(name)).collect()
+}
+
+// A witness authorizing the local ID of the test vectors with `sig`, made by
+// the rotation key at `final_index`
+pub(crate) fn authorization_witness(sig: Vec<u8>, final_index: u8) -> Bytes {
+    let mut rotation_key_indices = HISTORY_INDICES.to_vec();
+    rotation_key_indices.push(final_index);
+    let witness = DidCkbWitness {
+        local_id_authorization: PlcAuthorization {
+            history: history(),
+            sig,
+            rotation_key_indices,
+        },
+    };
+    WitnessArgs {
+        output_type: Some(witness.to_vec()),
+        ..Default::default()
+    }
+    .to_vec()
+    .into()
+}
+
+// An unsigned mint of one did:ckb cell with `data`. Its cell deps are already
+// completed, they are covered by the signed transaction hash.
+pub(crate) fn mint_tx(env: &mut Env, data: Bytes) -> TransactionView {
+    let input = env.plain_input();
+    let output = env.did_ckb_output(type_id_args(&input, 0));
     let tx = TransactionBuilder::default()
         .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
+        .output(output)
+        .output_data(data.pack())
         .build();
-    let tx = context.complete_tx(tx);
+    env.context.complete_tx(tx)
+}
+
+pub(crate) fn with_witness(tx: TransactionView, witness: Bytes) -> TransactionView {
+    tx.as_advanced_builder()
+        .set_witnesses(vec![witness.pack()])
+        .build()
+}
 
-    // run
-    let cycles = context
-        .verify_tx(&tx, 10_000_000)
-        .expect("pass verification");
+#[test]
+fn test_mint_without_local_id() {
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, None));
+    let cycles = env.verify(tx).expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
+
+#[test]
+fn test_mint_with_local_id() {
+    let local_id = load_did("creation");
+    for final_index in [0, 1] {
+        let mut env = Env::new();
+        let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+        let sig = match final_index {
+            0 => sign_secp256k1(tx.hash().as_slice()),
+            _ => sign_p256(tx.hash().as_slice()),
+        };
+        let tx = with_witness(tx, authorization_witness(sig, final_index));
+        let cycles = env.verify(tx).expect("pass verification");
+        println!("consume cycles: {}", cycles);
+    }
+}
+
+#[test]
+fn test_mint_with_local_id_wrong_signature() {
+    let local_id = load_did("creation");
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+    // signed by the key at index 0 but claimed for index 1
+    let sig = sign_secp256k1(tx.hash().as_slice());
+    let tx = with_witness(tx, authorization_witness(sig, 1));
+    assert_eq!(env.verify(tx), Err(34));
+
+    // signed over another transaction
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+    let sig = sign_secp256k1(&[0u8; 32]);
+    let tx = with_witness(tx, authorization_witness(sig, 0));
+    assert_eq!(env.verify(tx), Err(34));
+}
+
+#[test]
+fn test_mint_with_local_id_wrong_did() {
+    let local_id = load_did("creation-legacy");
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, cell_data(&DOCUMENT, Some(&local_id)));
+    let sig = sign_secp256k1(tx.hash().as_slice());
+    let tx = with_witness(tx, authorization_witness(sig, 0));
+    assert_eq!(env.verify(tx), Err(40));
+}
+
+#[test]
+fn test_mint_with_local_id_witness_errors() {
+    let local_id = load_did("creation");
+    let data = cell_data(&DOCUMENT, Some(&local_id));
+
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, data.clone());
+    assert_eq!(env.verify(tx), Err(54));
+
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, data.clone());
+    let witness = WitnessArgs::from_slice(&authorization_witness(vec![0; 64], 0)).unwrap();
+    let misplaced = WitnessArgs {
+        input_type: witness.output_type,
+        ..Default::default()
+    };
+    let tx = with_witness(tx, misplaced.to_vec().into());
+    assert_eq!(env.verify(tx), Err(57));
+
+    let mut env = Env::new();
+    let tx = mint_tx(&mut env, data);
+    let tx = with_witness(tx, WitnessArgs::default().to_vec().into());
+    assert_eq!(env.verify(tx), Err(56));
+}
+
+#[test]
+fn test_mint_invalid_document() {
+    let mut env = Env::new();
+    // an array expecting two elements
+    let tx = mint_tx(&mut env, cell_data(&[0x82], None));
+    assert_eq!(env.verify(tx), Err(52));
+}
+
+#[test]
+fn test_mint_invalid_type_id() {
+    let mut env = Env::new();
+    let input = env.plain_input();
+    let output = env.did_ckb_output(Bytes::from(vec![0u8; 20]));
+    let tx = TransactionBuilder::default()
+        .input(input)
+        .output(output)
+        .output_data(cell_data(&DOCUMENT, None).pack())
+        .build();
+    assert_eq!(env.verify(tx), Err(26));
+}
+
+// An update of a did:ckb cell holding `local_id` to one holding `new_local_id`
+fn update_tx(env: &mut Env, local_id: Option<&str>, new_local_id: Option<&str>) -> TransactionView {
+    let args = Bytes::from(vec![0x42u8; 20]);
+    let output = env.did_ckb_output(args);
+    let input = env.input(output.clone(), cell_data(&DOCUMENT, local_id));
+    TransactionBuilder::default()
+        .input(input)
+        .output(output)
+        .output_data(cell_data(&[0xa0], new_local_id).pack())
+        .build()
+}
+
+#[test]
+fn test_update() {
+    let local_id = load_did("creation");
+    let mut env = Env::new();
+    let tx = update_tx(&mut env, Some(&local_id), Some(&local_id));
+    env.verify(tx).expect("pass verification");
+
+    let mut env = Env::new();
+    let tx = update_tx(&mut env, None, None);
+    env.verify(tx).expect("pass verification");
+}
+
+#[test]
+fn test_update_bound_document() {
+    let local_id = load_did("creation");
+    let operation = load_vector(HISTORY[HISTORY.len() - 1]);
+    let document = bound_state_document(&operation, "strict", |_| {});
+    for input_type in [false, true] {
+        let mut env = Env::new();
+        let output = env.did_ckb_output(Bytes::from(vec![0x42u8; 20]));
+        let input = env.input(output.clone(), cell_data(&DOCUMENT, Some(&local_id)));
+        let tx = TransactionBuilder::default()
+            .input(input)
+            .output(output)
+            .output_data(cell_data(&document, Some(&local_id)).pack())
+            .build();
+        let tx = env.context.complete_tx(tx);
+        let sig = sign_p256(tx.hash().as_slice());
+        let mut witness = authorization_witness(sig, 1);
+        // the authorization can also sit next to the state it replaces
+        if input_type {
+            let args = WitnessArgs::from_slice(&witness).unwrap();
+            let args = WitnessArgs {
+                input_type: args.output_type,
+                ..Default::default()
+            };
+            witness = args.to_vec().into();
+        }
+        let tx = with_witness(tx, witness);
+        env.verify(tx).expect("pass verification");
+    }
+}
+
+#[test]
+fn test_update_dropping_binding() {
+    let local_id = load_did("creation");
+    let operation = load_vector(HISTORY[HISTORY.len() - 1]);
+    let document = bound_state_document(&operation, "strict", |_| {});
+    for authorized in [false, true] {
+        let mut env = Env::new();
+        let output = env.did_ckb_output(Bytes::from(vec![0x42u8; 20]));
+        let input = env.input(output.clone(), cell_data(&document, Some(&local_id)));
+        let tx = TransactionBuilder::default()
+            .input(input)
+            .output(output)
+            .output_data(cell_data(&DOCUMENT, Some(&local_id)).pack())
+            .build();
+        let tx = env.context.complete_tx(tx);
+        if authorized {
+            let sig = sign_p256(tx.hash().as_slice());
+            let tx = with_witness(tx, authorization_witness(sig, 1));
+            env.verify(tx).expect("pass verification");
+        } else {
+            assert_eq!(env.verify(tx), Err(54));
+        }
+    }
+}
+
+#[test]
+fn test_update_mismatched_local_id() {
+    let local_id = load_did("creation");
+    let other = load_did("creation-legacy");
+    let mut env = Env::new();
+    let tx = update_tx(&mut env, Some(&local_id), Some(&other));
+    assert_eq!(env.verify(tx), Err(53));
+
+    let mut env = Env::new();
+    let tx = update_tx(&mut env, Some(&local_id), None);
+    assert_eq!(env.verify(tx), Err(53));
+
+    let mut env = Env::new();
+    let tx = update_tx(&mut env, None, Some(&local_id));
+    assert_eq!(env.verify(tx), Err(53));
+}
+
+#[test]
+fn test_burn() {
+    let local_id = load_did("creation");
+    let mut env = Env::new();
+    let output = env.did_ckb_output(Bytes::from(vec![0x42u8; 20]));
+    let input = env.input(output, cell_data(&DOCUMENT, Some(&local_id)));
+    let tx = TransactionBuilder::default()
+        .input(input)
+        .output(env.plain_output())
+        .output_data(Bytes::new().pack())
+        .build();
+    env.verify(tx).expect("pass verification");
+}
@@ -0,0 +1,292 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+//! Generates the did:plc test vectors in-process, byte for byte the ones
+//! `tools/gen-test-vectors` writes with `--no-random`, so `cargo test` doesn't
+//! need pnpm.
+//!
+//! Operations are built the way @did-plc/lib builds them: updates start from
+//! the previous operation, normalized when it is a legacy `create`, and every
+//! operation is encoded as DAG-CBOR with its map keys sorted.
+use crate::plc::{self, Key, encode, sign_operation, text};
+use ckb_did_plc_utils::{
+    base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD},
+    cbor4ii::core::Value,
+    ensure_atproto_prefix, ensure_http_prefix,
+};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// The secp256k1 key `index` of the TypeScript tool: 0x01..=0x20 with `index`
+/// added to the first byte.
+pub fn secp256k1_key(index: u8) -> Key {
+    let mut key: Vec<u8> = (0x01..=0x20).collect();
+    key[0] += index;
+    Key::secp256k1_from_slice(&key)
+}
+
+pub fn p256_key() -> Key {
+    let key: Vec<u8> = (0x21..=0x40).collect();
+    Key::p256_from_slice(&key)
+}
+
+fn field<'a>(fields: &'a [(Value, Value)], name: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(k, _)| *k == text(name))
+        .map(|(_, v)| v)
+}
+
+fn text_field(fields: &[(Value, Value)], name: &str) -> String {
+    match field(fields, name) {
+        Some(Value::Text(value)) => value.clone(),
+        _ => panic!("no text field {}", name),
+    }
+}
+
+/// The data of a `plc_operation` with an atproto PDS.
+#[derive(Clone, Debug)]
+pub struct PlcOperation {
+    pub rotation_keys: Vec<String>,
+    pub atproto_key: String,
+    pub handle: String,
+    pub pds: String,
+}
+
+impl PlcOperation {
+    pub fn new(rotation_keys: &[&Key], atproto_key: &Key, handle: &str, pds: &str) -> Self {
+        Self {
+            rotation_keys: rotation_keys.iter().map(|k| k.did_key()).collect(),
+            atproto_key: atproto_key.did_key(),
+            handle: ensure_atproto_prefix(handle),
+            pds: ensure_http_prefix(pds),
+        }
+    }
+
+    /// The data of the encoded operation `op`. A legacy `create` is normalized
+    /// like @did-plc/lib does, with the recovery key first.
+    pub fn decode(op: &[u8]) -> Self {
+        let fields = plc::decode_fields(op).expect("operation");
+        if text_field(&fields, "type") == "create" {
+            return Self {
+                rotation_keys: vec![
+                    text_field(&fields, "recoveryKey"),
+                    text_field(&fields, "signingKey"),
+                ],
+                atproto_key: text_field(&fields, "signingKey"),
+                handle: ensure_atproto_prefix(&text_field(&fields, "handle")),
+                pds: ensure_http_prefix(&text_field(&fields, "service")),
+            };
+        }
+        let texts = |value: Option<&Value>| match value {
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Text(item) => item.clone(),
+                    _ => panic!("not a text"),
+                })
+                .collect::<Vec<_>>(),
+            _ => panic!("not an array"),
+        };
+        let (atproto_key, pds) = match (
+            field(&fields, "verificationMethods"),
+            field(&fields, "services"),
+        ) {
+            (Some(Value::Map(methods)), Some(Value::Map(services))) => {
+                let pds = match field(services, "atproto_pds") {
+                    Some(Value::Map(pds)) => text_field(pds, "endpoint"),
+                    _ => panic!("no atproto_pds"),
+                };
+                (text_field(methods, "atproto"), pds)
+            }
+            _ => panic!("no verificationMethods or services"),
+        };
+        Self {
+            rotation_keys: texts(field(&fields, "rotationKeys")),
+            atproto_key,
+            handle: texts(field(&fields, "alsoKnownAs"))[0].clone(),
+            pds,
+        }
+    }
+
+    pub fn fields(&self, prev: Option<String>) -> Vec<(Value, Value)> {
+        vec![
+            (text("type"), text("plc_operation")),
+            (
+                text("rotationKeys"),
+                Value::Array(self.rotation_keys.iter().map(|k| text(k)).collect()),
+            ),
+            (
+                text("verificationMethods"),
+                Value::Map(vec![(text("atproto"), text(&self.atproto_key))]),
+            ),
+            (text("alsoKnownAs"), Value::Array(vec![text(&self.handle)])),
+            (
+                text("services"),
+                Value::Map(vec![(
+                    text("atproto_pds"),
+                    Value::Map(vec![
+                        (text("type"), text("AtprotoPersonalDataServer")),
+                        (text("endpoint"), text(&self.pds)),
+                    ]),
+                )]),
+            ),
+            (text("prev"), prev.map_or(Value::Null, |prev| text(&prev))),
+        ]
+    }
+
+    /// Signs the operation following the encoded operation `prev`, or a
+    /// genesis operation.
+    pub fn sign(&self, prev: Option<&[u8]>, key: &Key) -> Vec<u8> {
+        let prev = prev.map(|prev| plc::cid(prev).expect("prev operation"));
+        sign_operation(self.fields(prev), key)
+    }
+}
+
+/// The update of the encoded operation `prev` changed by `update`, signed by
+/// `key`.
+pub fn update(prev: &[u8], key: &Key, update: impl FnOnce(&mut PlcOperation)) -> Vec<u8> {
+    let mut op = PlcOperation::decode(prev);
+    update(&mut op);
+    op.sign(Some(prev), key)
+}
+
+/// A legacy `create` operation, signed by its signing key.
+pub fn legacy_create(
+    signing_key: &Key,
+    recovery_key: &Key,
+    handle: &str,
+    service: &str,
+) -> Vec<u8> {
+    let fields = vec![
+        (text("type"), text("create")),
+        (text("signingKey"), text(&signing_key.did_key())),
+        (text("recoveryKey"), text(&recovery_key.did_key())),
+        (text("handle"), text(handle)),
+        (text("service"), text(service)),
+        (text("prev"), Value::Null),
+    ];
+    sign_operation(fields, signing_key)
+}
+
+/// A tombstone following the encoded operation `prev`.
+pub fn tombstone(prev: &[u8], key: &Key) -> Vec<u8> {
+    let fields = vec![
+        (text("type"), text("plc_tombstone")),
+        (text("prev"), text(&plc::cid(prev).expect("prev operation"))),
+    ];
+    sign_operation(fields, key)
+}
+
+/// A way to break a valid operation.
+#[derive(Clone, Copy, Debug)]
+pub enum Variant {
+    /// The first byte of the signature flipped
+    BadSignature,
+    /// `prev` set to the CID of the operation itself, so it never matches the
+    /// previous operation
+    BadCid,
+    /// `type` set to null
+    WrongType,
+}
+
+/// `op` broken by `variant`, keeping its other fields and their order.
+pub fn variant(op: &[u8], variant: Variant) -> Vec<u8> {
+    let mut fields = plc::decode_fields(op).expect("operation");
+    let (name, value) = match variant {
+        Variant::BadSignature => {
+            let mut sig = URL_SAFE_NO_PAD
+                .decode(text_field(&fields, "sig"))
+                .expect("base64url signature");
+            sig[0] ^= 1;
+            ("sig", text(&URL_SAFE_NO_PAD.encode(sig)))
+        }
+        Variant::BadCid => ("prev", text(&plc::cid(op).expect("operation"))),
+        Variant::WrongType => ("type", Value::Null),
+    };
+    for (k, v) in fields.iter_mut() {
+        if *k == text(name) {
+            *v = value.clone();
+        }
+    }
+    encode(&Value::Map(fields))
+}
+
+/// The vectors by file name, as `tools/gen-test-vectors` names them: encoded
+/// operations in `.cbor` files and DIDs in `.did` files.
+pub fn generate() -> BTreeMap<String, Vec<u8>> {
+    let mut vectors = BTreeMap::new();
+    let mut add = |name: &str, data: &[u8]| {
+        vectors.insert(name.to_owned(), data.to_vec());
+    };
+
+    let mut signing_key = secp256k1_key(0);
+    let mut rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = p256_key();
+
+    let op1 = PlcOperation::new(
+        &[&rotation_key1, &rotation_key2],
+        &signing_key,
+        "at://alice.example.com",
+        "https://example.com",
+    )
+    .sign(None, &rotation_key1);
+    add("1-did-creation.cbor", &op1);
+    add(
+        "creation.did",
+        plc::did(&plc::binary_did(&op1).unwrap()).as_bytes(),
+    );
+
+    let op2 = update(&op1, &rotation_key1, |op| {
+        op.handle = ensure_atproto_prefix("alice.example2.com")
+    });
+    add("2-update-handle.cbor", &op2);
+
+    let op3 = update(&op2, &rotation_key1, |op| {
+        op.pds = ensure_http_prefix("example2.com")
+    });
+    add("3-update-pds.cbor", &op3);
+
+    let new_signing_key = secp256k1_key(2);
+    let op4 = update(&op3, &rotation_key1, |op| {
+        op.atproto_key = new_signing_key.did_key()
+    });
+    add("4-update-atproto-key.cbor", &op4);
+    signing_key = new_signing_key;
+
+    let new_rotation_key = secp256k1_key(3);
+    let op5 = update(&op4, &rotation_key1, |op| {
+        op.rotation_keys = vec![new_rotation_key.did_key(), rotation_key2.did_key()]
+    });
+    add("5-update-rotation-keys.cbor", &op5);
+    rotation_key1 = new_rotation_key;
+
+    let handle = "at://ali.example.com";
+    let op6 = update(&op5, &rotation_key2, |op| op.handle = handle.to_owned());
+    add("6-update-handle.cbor", &op6);
+
+    add("7-tombstone.cbor", &tombstone(&op6, &rotation_key1));
+
+    let legacy1 = legacy_create(&signing_key, &rotation_key2, handle, "https://example2.com");
+    add("1-did-creation-legacy.cbor", &legacy1);
+    add(
+        "creation-legacy.did",
+        plc::did(&plc::binary_did(&legacy1).unwrap()).as_bytes(),
+    );
+
+    let legacy2 = update(&legacy1, &rotation_key2, |op| {
+        op.rotation_keys = vec![rotation_key1.did_key(), rotation_key2.did_key()]
+    });
+    add("2-update-rotation-keys-legacy.cbor", &legacy2);
+
+    vectors
+}
+
+/// The vector named `name`, generated once per test run.
+pub fn get(name: &str) -> Vec<u8> {
+    static VECTORS: OnceLock<BTreeMap<String, Vec<u8>>> = OnceLock::new();
+    VECTORS
+        .get_or_init(generate)
+        .get(name)
+        .unwrap_or_else(|| panic!("no test vector {}", name))
+        .clone()
+}
@@ -0,0 +1,265 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use ckb_types::{
+    bytes::Bytes,
+    core::{ScriptHashType, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use did_ckb_molecules::types::{DidCkbWitness, WitnessArgs};
+use did_ckb_verifier::{ResolvedInput, error::Error, verify_script_group, verify_transaction};
+
+// The local ID extension example from ts-tests/did-ckb-ts, a mint bound to
+// did:plc:bxvfvvygwbcnbmknn73t6pbu and signed by its first rotation key.
+const PREVIOUS_TX_HASH: &str = "1ecbf88d692a14d7cbc0bfd1a3d5019e4b613247ae438bad52f94148c6009559";
+const ALWAYS_SUCCESS_CODE_HASH: &str =
+    "3dc9cdb5b2dcff8d4999004406558d4456ecf0f14c757ca353684c0bd4d3b50d";
+pub(crate) const DID_CKB_CODE_HASH: &str =
+    "b95123c71a870e3f0f74a7ee1dab8268dbfbc1407b46733ebd1b41f854b4324a";
+const TYPE_ID: &str = "8434cfe81aa825c275d513eee20e4235294e3420";
+const MIGRATION_TX_HASH: &str = "cb86447276a89c20276a667ada34640341e9e9ea47b68548241a934ff49e9fea";
+pub(crate) const MIGRATION_DATA: &str = "00000000d80000000c000000b4000000a4000000a3687365727669636573a16b617470726f746f5f706473a264747970657819417470726f746f506572736f6e616c4461746153657276657268656e64706f696e747468747470733a2f2f6578616d706c652e746573746b616c736f4b6e6f776e4173816f61743a2f2f616c6963652e7465737473766572696669636174696f6e4d6574686f6473a167617470726f746f736469643a6b65793a7a5369676e696e674b6579200000006469643a706c633a62787666767679677762636e626d6b6e6e37337436706275";
+pub(crate) const MIGRATION_WITNESS: &str = "4c0200001000000010000000100000003802000038020000080000003002000010000000e60100002a020000d601000008000000ca010000a76373696778564c2d6b72665f6f385f4b4a5754362d51316532572d52466252443777313564575f4e4b726b6d5f634c2d355147346b5f70736270714a5f314f48626a3034716a70764f70724143507357384765582d6e5253433030416470726576f664747970656d706c635f6f7065726174696f6e687365727669636573a16b617470726f746f5f706473a264747970657819417470726f746f506572736f6e616c4461746153657276657268656e64706f696e747368747470733a2f2f6578616d706c652e636f6d6b616c736f4b6e6f776e4173817661743a2f2f616c6963652e6578616d706c652e636f6d6c726f746174696f6e4b6579738278396469643a6b65793a7a5133736859314d5351545a6f584431505370616a566237487642734841754a69455039354271426b5358316d6365794278396469643a6b65793a7a446e616570684464367a5a4c433353397873536e6b7579784b39316d62663467654e53666e6a334c715a65666158693373766572696669636174696f6e4d6574686f6473a167617470726f746f78396469643a6b65793a7a513373686e6d517963716d38344d684c48544a6675614a6843326a66774a69597a697a784c6162574e466269796d5154400000000b870938facdec00272e48435fde929beb2194cf32374ff5de6e678c572319d100aa175c346eda92d54fac45b84a4160f0a7808c4f4106b82f041cba3baf1b87020000000000";
+// The spec example documents without local ID, with `alsoKnownAs` changed
+// from at://alice.test to at://bob.test.
+pub(crate) const SPEC_DATA_0: &str = "00000000b40000000c000000b4000000a4000000a3687365727669636573a16b617470726f746f5f706473a264747970657819417470726f746f506572736f6e616c4461746153657276657268656e64706f696e747468747470733a2f2f6578616d706c652e746573746b616c736f4b6e6f776e4173816f61743a2f2f616c6963652e7465737473766572696669636174696f6e4d6574686f6473a167617470726f746f736469643a6b65793a7a5369676e696e674b6579";
+pub(crate) const SPEC_DATA_1: &str = "00000000b20000000c000000b2000000a2000000a3687365727669636573a16b617470726f746f5f706473a264747970657819417470726f746f506572736f6e616c4461746153657276657268656e64706f696e747468747470733a2f2f6578616d706c652e746573746b616c736f4b6e6f776e4173816d61743a2f2f626f622e7465737473766572696669636174696f6e4d6574686f6473a167617470726f746f736469643a6b65793a7a5369676e696e674b6579";
+
+pub(crate) fn hash(s: &str) -> Byte32 {
+    let mut buf = [0u8; 32];
+    hex::decode_to_slice(s, &mut buf).unwrap();
+    buf.pack()
+}
+
+pub(crate) fn bytes(s: &str) -> Bytes {
+    Bytes::from(hex::decode(s).unwrap())
+}
+
+fn always_success_lock() -> Script {
+    Script::new_builder()
+        .code_hash(hash(ALWAYS_SUCCESS_CODE_HASH))
+        .hash_type(ScriptHashType::Type.into())
+        .build()
+}
+
+fn did_ckb_type(args: &str) -> Script {
+    Script::new_builder()
+        .code_hash(hash(DID_CKB_CODE_HASH))
+        .hash_type(ScriptHashType::Type.into())
+        .args(bytes(args).pack())
+        .build()
+}
+
+pub(crate) fn did_ckb_output(capacity: u64, args: &str) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(always_success_lock())
+        .type_(Some(did_ckb_type(args)).pack())
+        .build()
+}
+
+fn plain_output(capacity: u64) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(always_success_lock())
+        .build()
+}
+
+fn tx_builder(input_index: u32) -> TransactionBuilder {
+    let out_point = |index: u32| {
+        OutPoint::new_builder()
+            .tx_hash(hash(PREVIOUS_TX_HASH))
+            .index(index.pack())
+            .build()
+    };
+    TransactionBuilder::default()
+        .cell_dep(CellDep::new_builder().out_point(out_point(0)).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point(1)).build())
+        .input(CellInput::new(out_point(input_index), 0))
+}
+
+fn migration_tx() -> TransactionView {
+    tx_builder(2)
+        .output(did_ckb_output(600, TYPE_ID))
+        .output_data(bytes(MIGRATION_DATA).pack())
+        .witness(bytes(MIGRATION_WITNESS).pack())
+        .build()
+}
+
+fn mint_inputs() -> Vec<ResolvedInput> {
+    vec![ResolvedInput::new(plain_output(1000), Bytes::new())]
+}
+
+fn verify(tx: &TransactionView, inputs: &[ResolvedInput]) -> Result<(), i8> {
+    verify_transaction(tx, inputs, &hash(DID_CKB_CODE_HASH), ScriptHashType::Type)
+        .map_err(|(_, e)| e.error_code())
+}
+
+#[test]
+fn test_verify_mint_with_local_id() {
+    let tx = migration_tx();
+    assert_eq!(tx.hash(), hash(MIGRATION_TX_HASH));
+    assert_eq!(verify(&tx, &mint_inputs()), Ok(()));
+}
+
+#[test]
+fn test_verify_mint_without_local_id() {
+    let tx = tx_builder(2)
+        .output(did_ckb_output(600, TYPE_ID))
+        .output_data(bytes(SPEC_DATA_0).pack())
+        .build();
+    assert_eq!(verify(&tx, &mint_inputs()), Ok(()));
+}
+
+#[test]
+fn test_verify_mint_signed_over_other_tx() {
+    // any change to the transaction invalidates the final authorization signature
+    let tx = migration_tx()
+        .as_advanced_builder()
+        .set_outputs(vec![did_ckb_output(599, TYPE_ID)])
+        .build();
+    let err = verify_script_group(&tx, &mint_inputs(), &did_ckb_type(TYPE_ID)).unwrap_err();
+    assert_eq!(err.error_code(), 34);
+    assert!(err.to_string().starts_with("error code 34: "));
+}
+
+#[test]
+fn test_verify_mint_wrong_type_id() {
+    let tx = tx_builder(3)
+        .output(did_ckb_output(600, TYPE_ID))
+        .output_data(bytes(SPEC_DATA_0).pack())
+        .build();
+    assert_eq!(verify(&tx, &mint_inputs()), Err(26));
+}
+
+#[test]
+fn test_verify_mint_missing_witness() {
+    let tx = migration_tx()
+        .as_advanced_builder()
+        .set_witnesses(vec![])
+        .build();
+    assert_eq!(verify(&tx, &mint_inputs()), Err(54));
+    let tx = tx
+        .as_advanced_builder()
+        .witness(Bytes::new().pack())
+        .build();
+    assert_eq!(verify(&tx, &mint_inputs()), Err(54));
+}
+
+#[test]
+fn test_verify_mint_witness_placement() {
+    let with_witness = |witness: Vec<u8>| {
+        migration_tx()
+            .as_advanced_builder()
+            .set_witnesses(vec![Bytes::from(witness).pack()])
+            .build()
+    };
+    let args = WitnessArgs::from_slice(&bytes(MIGRATION_WITNESS)).unwrap();
+
+    let misplaced = WitnessArgs {
+        input_type: args.output_type.clone(),
+        output_type: None,
+        ..args.clone()
+    };
+    assert_eq!(
+        verify(&with_witness(misplaced.to_vec()), &mint_inputs()),
+        Err(57)
+    );
+    let missing = WitnessArgs {
+        lock: Some(vec![0; 65]),
+        ..Default::default()
+    };
+    assert_eq!(
+        verify(&with_witness(missing.to_vec()), &mint_inputs()),
+        Err(56)
+    );
+    let invalid = WitnessArgs {
+        output_type: Some(vec![1, 2, 3]),
+        ..args.clone()
+    };
+    assert_eq!(
+        verify(&with_witness(invalid.to_vec()), &mint_inputs()),
+        Err(58)
+    );
+    assert_eq!(
+        verify(&with_witness(vec![1, 2, 3]), &mint_inputs()),
+        Err(55)
+    );
+
+    // the lock script is free to use `lock`, witnesses are not signed over
+    let with_lock = WitnessArgs {
+        lock: Some(vec![0; 65]),
+        ..args
+    };
+    assert_eq!(
+        verify(&with_witness(with_lock.to_vec()), &mint_inputs()),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_verify_mint_corrupted_history() {
+    let args = WitnessArgs::from_slice(&bytes(MIGRATION_WITNESS)).unwrap();
+    let witness = DidCkbWitness::from_slice(args.output_type.as_ref().unwrap()).unwrap();
+    let op = &witness.local_id_authorization.history[0];
+    // the history item claims one byte more than it holds, which
+    // `BytesVec::verify` doesn't check
+    let mut output_type = witness.to_vec();
+    let mut header = (op.len() as u32).to_le_bytes().to_vec();
+    header.extend_from_slice(op);
+    let at = output_type
+        .windows(header.len())
+        .position(|w| w == header)
+        .unwrap();
+    output_type[at..at + 4].copy_from_slice(&(op.len() as u32 + 1).to_le_bytes());
+    let corrupted = WitnessArgs {
+        output_type: Some(output_type),
+        ..args
+    };
+    let tx = migration_tx()
+        .as_advanced_builder()
+        .set_witnesses(vec![Bytes::from(corrupted.to_vec()).pack()])
+        .build();
+    assert_eq!(verify(&tx, &mint_inputs()), Err(51));
+}
+
+#[test]
+fn test_verify_update_and_burn() {
+    let inputs = vec![ResolvedInput::new(
+        did_ckb_output(600, TYPE_ID),
+        bytes(SPEC_DATA_0),
+    )];
+    let update = tx_builder(0)
+        .output(did_ckb_output(599, TYPE_ID))
+        .output_data(bytes(SPEC_DATA_1).pack())
+        .build();
+    assert_eq!(verify(&update, &inputs), Ok(()));
+
+    let burn = tx_builder(0)
+        .output(plain_output(598))
+        .output_data(Bytes::new().pack())
+        .build();
+    assert_eq!(verify(&burn, &inputs), Ok(()));
+}
+
+#[test]
+fn test_verify_unresolved_inputs() {
+    let tx = migration_tx();
+    assert_eq!(verify(&tx, &[]), Err(-2));
+    let inputs = [mint_inputs(), mint_inputs()].concat();
+    let e = verify_script_group(&tx, &inputs, &did_ckb_type(TYPE_ID)).unwrap_err();
+    assert!(matches!(e, Error::UnresolvedInputs));
+}
+
+#[test]
+fn test_verify_update_local_id_changed() {
+    let inputs = vec![ResolvedInput::new(
+        did_ckb_output(600, TYPE_ID),
+        bytes(SPEC_DATA_0),
+    )];
+    let tx = tx_builder(0)
+        .output(did_ckb_output(600, TYPE_ID))
+        .output_data(bytes(MIGRATION_DATA).pack())
+        .build();
+    assert_eq!(verify(&tx, &inputs), Err(53));
+}
@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::cli::{unauthorized_mint, verify};
+use crate::plc::Key;
+use ckb_did_plc_utils::{
+    json::{MAX_DEPTH, Value},
+    local_id::{AuthorizationKind, Registry},
+    web::check_did_web,
+};
+use ckb_types::{core::TransactionView, prelude::*};
+use did_ckb_cli::{error::Error, tx, web};
+use did_ckb_molecules::types::{KeyAuthorization, WebAuthorization};
+use did_ckb_verifier::verify_authorization;
+use serde_json::json;
+use std::thread;
+use tiny_http::{Response, Server};
+
+const DID: &str = "did:web:example.com";
+
+fn document(did: &str, key: &Key) -> Vec<u8> {
+    let multibase = key.did_key()["did:key:".len()..].to_owned();
+    serde_json::to_vec(&json!({
+        "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+        "id": did,
+        "verificationMethod": [
+            {
+                "id": format!("{}#ed25519", did),
+                "type": "Multikey",
+                "controller": did,
+                "publicKeyMultibase": "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            },
+            {
+                "id": "#key-1",
+                "type": "Multikey",
+                "controller": did,
+                "publicKeyMultibase": multibase,
+            },
+        ],
+        "authentication": ["#key-1"],
+    }))
+    .unwrap()
+}
+
+// Serves `document` at `path` on a local port, standing in for the domain of
+// a did:web. Returns the origin to fetch from.
+fn serve(path: &'static str, document: Vec<u8>) -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let origin = format!("http://{}", server.server_addr().to_ip().unwrap());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match request.url() == path {
+                true => Response::from_data(document.clone()),
+                false => Response::from_data(vec![]).with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    });
+    origin
+}
+
+// Mints a did:ckb bound to `did`, authorized by the verification method
+// `method` of `document` signed by `signer`.
+fn web_mint(did: &str, document: Vec<u8>, method: &str, signer: &Key) -> TransactionView {
+    let tx = unauthorized_mint(did);
+    let sig = signer.sign(tx.hash().as_slice());
+    let authorization = WebAuthorization {
+        document,
+        verification_method: method.as_bytes().to_vec(),
+        sig,
+    };
+    tx::authorize_web(tx, authorization).unwrap()
+}
+
+fn witness(tx: &TransactionView) -> Vec<u8> {
+    tx.witnesses().get(0).unwrap().raw_data().to_vec()
+}
+
+#[test]
+fn test_did_web_syntax() {
+    for did in [
+        "did:web:example.com",
+        "did:web:localhost%3A8443",
+        "did:web:w3c-ccg.github.io:user:alice",
+        "did:web:example.com:u%40ser:a_b.c~d",
+    ] {
+        check_did_web(did).unwrap();
+        Registry::default().check_local_id(did.as_bytes()).unwrap();
+    }
+    for did in [
+        "did:web:",
+        "did:web:example.com:",
+        "did:web:example..com",
+        "did:web:-example.com",
+        "did:web:example.com%3A",
+        "did:web:example.com%3A443x",
+        "did:web:example.com/user",
+        "did:web:example.com:user%4",
+        "did:web:user@example.com",
+    ] {
+        let e = check_did_web(did).unwrap_err();
+        assert_eq!(e.error_code(), 50, "{}", did);
+        let e = Registry::default()
+            .check_local_id(did.as_bytes())
+            .unwrap_err();
+        assert_eq!(e.error_code(), 50, "{}", did);
+    }
+    let registry = Registry::default();
+    let verifier = registry.find(DID.as_bytes()).unwrap();
+    assert_eq!(verifier.kind(), AuthorizationKind::Web);
+}
+
+#[test]
+fn test_document_url() {
+    for (did, url) in [
+        (
+            "did:web:example.com",
+            "https://example.com/.well-known/did.json",
+        ),
+        (
+            "did:web:localhost%3A8443",
+            "https://localhost:8443/.well-known/did.json",
+        ),
+        (
+            "did:web:example.com:user:alice",
+            "https://example.com/user/alice/did.json",
+        ),
+    ] {
+        assert_eq!(web::document_url(did).unwrap(), url);
+    }
+    assert!(web::document_url("did:web:example.com/user").is_err());
+}
+
+#[test]
+fn test_json() {
+    let value =
+        Value::parse(br#" {"a": [1, -2.5e3, true, null], "b": "\u00e9\ud83d\ude00\n"} "#).unwrap();
+    assert_eq!(
+        value.get("a").unwrap().as_array().unwrap()[1],
+        Value::Number("-2.5e3".into())
+    );
+    assert_eq!(value.get("b").unwrap().as_str(), Some("\u{e9}\u{1f600}\n"));
+    // duplicate keys are ambiguous
+    assert_eq!(Value::parse(br#"{"a": 1, "a": 2}"#).unwrap().get("a"), None);
+
+    for text in [
+        &b"{"[..],
+        b"{\"a\" 1}",
+        b"[1,]",
+        b"01",
+        b"\"\x01\"",
+        b"\"\\ud83d\"",
+        b"{} {}",
+        b"\"\xff\"",
+    ] {
+        assert_eq!(Value::parse(text), None, "{:?}", text);
+    }
+    let nested = |depth| [vec![b'['; depth], vec![b']'; depth]].concat();
+    assert!(Value::parse(&nested(MAX_DEPTH + 1)).is_some());
+    assert!(Value::parse(&nested(MAX_DEPTH + 2)).is_none());
+}
+
+#[test]
+fn test_fetch_document() {
+    let key = Key::secp256k1(1);
+    let origin = serve(
+        "/user/alice/did.json",
+        document("did:web:example.com:user:alice", &key),
+    );
+    let fetched = web::fetch_document("did:web:example.com:user:alice", Some(&origin)).unwrap();
+    assert_eq!(fetched, document("did:web:example.com:user:alice", &key));
+
+    let e = web::fetch_document(DID, Some(&origin)).unwrap_err();
+    assert!(matches!(e, Error::Fetch(_)), "{:?}", e);
+}
+
+#[test]
+fn test_mint_did_web() {
+    let key = Key::p256(2);
+    let origin = serve("/.well-known/did.json", document(DID, &key));
+    let fetched = web::fetch_document(DID, Some(&origin)).unwrap();
+
+    // a relative and an absolute verification method id
+    for method in ["#key-1", "did:web:example.com#key-1"] {
+        let tx = web_mint(DID, fetched.clone(), method, &key);
+        assert_eq!(verify(&tx), Ok(()));
+        verify_authorization(&witness(&tx), DID, &tx.hash().unpack()).unwrap();
+        web::authorization(DID, fetched.clone(), method, vec![]).unwrap();
+    }
+
+    // signed by another key
+    let tx = web_mint(DID, fetched.clone(), "#key-1", &Key::p256(3));
+    assert_eq!(verify(&tx), Err(63));
+
+    // the document of another did:web
+    let other = document("did:web:example.org", &key);
+    let tx = web_mint(DID, other.clone(), "#key-1", &key);
+    assert_eq!(verify(&tx), Err(61));
+    let tx = web_mint(DID, b"<html></html>".to_vec(), "#key-1", &key);
+    assert_eq!(verify(&tx), Err(61));
+    assert!(web::authorization(DID, other, "#key-1", vec![]).is_err());
+
+    // a method not in the document, and one with an unsupported key
+    for method in ["#key-2", "#ed25519", "did:web:example.org#key-1"] {
+        let tx = web_mint(DID, fetched.clone(), method, &key);
+        assert_eq!(verify(&tx), Err(62), "{}", method);
+        let e = web::authorization(DID, fetched.clone(), method, vec![]).unwrap_err();
+        assert!(matches!(e, Error::Utils(_)), "{:?}", e);
+    }
+
+    // the witness of a did:key
+    let tx = unauthorized_mint(DID);
+    let sig = key.sign(tx.hash().as_slice());
+    let tx = tx::authorize_key(tx, KeyAuthorization { sig }).unwrap();
+    assert_eq!(verify(&tx), Err(58));
+}